@@ -0,0 +1,248 @@
+use std::sync::{OnceLock, RwLock};
+
+use crate::executor::{
+    cat::CatCmd, cd::CdCmd, grep::GrepCmd, history::HistoryCmd, job::JobCmd, ls::LsCmd,
+    shopt::ShoptCmd, variable::VariableCmd, Command,
+};
+use crate::parser::ast_node_trait::CommandAstNode;
+use crate::token::token::TokenType;
+
+// The registry of the builtin commands.
+// A builtin command is declared once with its name, its options and the constructor of the command,
+// then the lexer classifies its name at the position of a command, the parser parses its options and values
+// by the declaration, and the executor builds the command with the constructor.
+// So a crate that uses the shell can add its own builtin commands, see [`register`]:
+//
+//     builtin::register(
+//         Builtin::new("hello", |cmd| Box::new(HelloCmd::from(cmd)))
+//             .with_options(vec![OptionSpec::new(Some('n'), Some("name")).with_value()]),
+//     );
+//
+// The shell itself builds every command in the thread that runs the command line,
+// but a crate may register its commands in another thread than the one that runs the shell,
+// and the tests run the shell in several threads at once, so the registry is one for the process behind a lock.
+static REGISTRY: OnceLock<RwLock<Vec<Builtin>>> = OnceLock::new();
+
+// Build the command from the AST node that has been parsed by the declaration of the builtin command.
+pub type Constructor = fn(Box<dyn CommandAstNode>) -> Box<dyn Command>;
+
+// An option of the builtin command, such as '-l, --long' of 'ls'.
+// The option is stored with the name as it's written, such as '-l' or '--long',
+// so the command looks up both names, see [`CommandAstNode::get_option`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionSpec {
+    short: Option<char>,
+    long: Option<&'static str>,
+
+    // Whether the option takes a value, such as '--depth 3' or '--depth=3'.
+    takes_value: bool,
+}
+
+impl OptionSpec {
+    pub fn new(short: Option<char>, long: Option<&'static str>) -> Self {
+        OptionSpec {
+            short,
+            long,
+            takes_value: false,
+        }
+    }
+
+    pub fn with_value(mut self) -> Self {
+        self.takes_value = true;
+        self
+    }
+
+    pub fn short(&self) -> Option<char> {
+        self.short
+    }
+
+    pub fn long(&self) -> Option<&'static str> {
+        self.long
+    }
+
+    pub fn takes_value(&self) -> bool {
+        self.takes_value
+    }
+}
+
+// How the words after the options are parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operands {
+    // The words are the values one by one, such as the job specs of 'fg %1'.
+    Words,
+    // The words are paths, which are separated by blanks or commas, such as 'ls src,tests'.
+    Paths,
+}
+
+// The declaration of the builtin command.
+#[derive(Debug, Clone)]
+pub struct Builtin {
+    name: String,
+
+    // The token type of the name, the commands of other crates share [`TokenType::Builtin`].
+    token_type: TokenType,
+
+    // The options are parsed only if there are some, so the words such as '-' in 'cd -' stay values.
+    options: Vec<OptionSpec>,
+
+    operands: Operands,
+
+    // The names of the values that must be given, such as the pattern of 'grep'.
+    required: Vec<&'static str>,
+
+    constructor: Constructor,
+}
+
+impl Builtin {
+    pub fn new(name: &str, constructor: Constructor) -> Self {
+        Builtin {
+            name: name.to_string(),
+            token_type: TokenType::Builtin,
+            options: Vec::new(),
+            operands: Operands::Words,
+            required: Vec::new(),
+            constructor,
+        }
+    }
+
+    pub fn with_options(mut self, options: Vec<OptionSpec>) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_operands(mut self, operands: Operands) -> Self {
+        self.operands = operands;
+        self
+    }
+
+    // Add the name of the value that must be given, it's shown in the parse error, such as 'Missing pattern'.
+    pub fn with_required(mut self, name: &'static str) -> Self {
+        self.required.push(name);
+        self
+    }
+
+    // The builtin commands of the shell keep their own token types, which their commands check.
+    fn with_token_type(mut self, token_type: TokenType) -> Self {
+        self.token_type = token_type;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn token_type(&self) -> &TokenType {
+        &self.token_type
+    }
+
+    pub fn options(&self) -> &[OptionSpec] {
+        &self.options
+    }
+
+    // Get the option by its short name, such as 'l' of '-l'.
+    pub fn short_option(&self, short: char) -> Option<&OptionSpec> {
+        self.options
+            .iter()
+            .find(|option| option.short == Some(short))
+    }
+
+    // Get the option by its long name, such as 'long' of '--long'.
+    pub fn long_option(&self, long: &str) -> Option<&OptionSpec> {
+        self.options.iter().find(|option| option.long == Some(long))
+    }
+
+    pub fn operands(&self) -> Operands {
+        self.operands
+    }
+
+    pub fn required(&self) -> &[&'static str] {
+        &self.required
+    }
+
+    // Build the command from the AST node.
+    pub fn build(&self, cmd: Box<dyn CommandAstNode>) -> Box<dyn Command> {
+        (self.constructor)(cmd)
+    }
+}
+
+// Register the builtin command, it replaces the one with the same name.
+pub fn register(builtin: Builtin) {
+    let mut builtins = registry().write().unwrap_or_else(|e| e.into_inner());
+    builtins.retain(|b| b.name != builtin.name);
+    builtins.push(builtin);
+}
+
+// Get the declaration of the builtin command by its name.
+pub fn lookup(name: &str) -> Option<Builtin> {
+    let builtins = registry().read().unwrap_or_else(|e| e.into_inner());
+    builtins.iter().find(|b| b.name == name).cloned()
+}
+
+// Get the names of the builtin commands.
+pub fn names() -> Vec<String> {
+    let builtins = registry().read().unwrap_or_else(|e| e.into_inner());
+    builtins.iter().map(|b| b.name.clone()).collect()
+}
+
+fn registry() -> &'static RwLock<Vec<Builtin>> {
+    REGISTRY.get_or_init(|| RwLock::new(defaults()))
+}
+
+// The builtin commands of the shell.
+fn defaults() -> Vec<Builtin> {
+    vec![
+        Builtin::new("ls", |cmd| Box::new(LsCmd::from(cmd)))
+            .with_token_type(TokenType::Ls)
+            .with_operands(Operands::Paths)
+            .with_options(vec![
+                OptionSpec::new(Some('l'), Some("long")),
+                OptionSpec::new(Some('a'), Some("all")),
+                OptionSpec::new(Some('h'), Some("human-readable")),
+                OptionSpec::new(Some('r'), Some("reverse")),
+                OptionSpec::new(Some('t'), Some("time")),
+                OptionSpec::new(Some('s'), Some("size")),
+                OptionSpec::new(None, Some("tree")),
+                OptionSpec::new(None, Some("depth")).with_value(),
+            ]),
+        // 'cd' has no options, '-' changes to the previous directory.
+        Builtin::new("cd", |cmd| Box::new(CdCmd::from(cmd)))
+            .with_token_type(TokenType::Cd)
+            .with_operands(Operands::Paths),
+        // The data is read from the input if there is no path, such as 'ls | grep foo'.
+        Builtin::new("grep", |cmd| Box::new(GrepCmd::from(cmd)))
+            .with_token_type(TokenType::Grep)
+            .with_operands(Operands::Paths)
+            .with_required("pattern")
+            .with_options(vec![
+                OptionSpec::new(Some('i'), Some("ignore-case")),
+                OptionSpec::new(Some('v'), Some("invert-match")),
+                OptionSpec::new(Some('c'), Some("count")),
+                OptionSpec::new(Some('n'), Some("line-number")),
+            ]),
+        Builtin::new("cat", |cmd| Box::new(CatCmd::from(cmd)))
+            .with_token_type(TokenType::Cat)
+            .with_operands(Operands::Paths)
+            .with_options(vec![
+                OptionSpec::new(Some('n'), Some("number")),
+                OptionSpec::new(Some('b'), Some("number-nonblank")),
+                OptionSpec::new(Some('s'), Some("squeeze-blank")),
+                OptionSpec::new(Some('e'), Some("show-ends")),
+            ]),
+        Builtin::new("jobs", |cmd| Box::new(JobCmd::from(cmd))).with_token_type(TokenType::Jobs),
+        Builtin::new("fg", |cmd| Box::new(JobCmd::from(cmd))).with_token_type(TokenType::Fg),
+        Builtin::new("bg", |cmd| Box::new(JobCmd::from(cmd))).with_token_type(TokenType::Bg),
+        Builtin::new("wait", |cmd| Box::new(JobCmd::from(cmd))).with_token_type(TokenType::Wait),
+        Builtin::new("disown", |cmd| Box::new(JobCmd::from(cmd)))
+            .with_token_type(TokenType::Disown),
+        Builtin::new("export", |cmd| Box::new(VariableCmd::from(cmd)))
+            .with_token_type(TokenType::Export),
+        Builtin::new("unset", |cmd| Box::new(VariableCmd::from(cmd)))
+            .with_token_type(TokenType::Unset),
+        Builtin::new("shopt", |cmd| Box::new(ShoptCmd::from(cmd)))
+            .with_token_type(TokenType::Shopt),
+        Builtin::new("history", |cmd| Box::new(HistoryCmd::from(cmd))).with_options(vec![
+            OptionSpec::new(Some('c'), Some("clear")),
+            OptionSpec::new(Some('d'), Some("delete")).with_value(),
+        ]),
+    ]
+}
@@ -0,0 +1,186 @@
+// The kinds of the edits, the edits of the same kind in a row are undone together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Insert,
+    Delete,
+    Kill,
+    Replace,
+}
+
+// The text of the line being edited and the cursor in it.
+// The cursor is an index of the chars instead of the bytes, so a multi-byte char, such as '你', is moved over as a whole.
+// The edits can be undone one by one, the chars typed in a row until a blank are undone together like a word,
+// and so are the chars deleted in a row.
+#[derive(Debug, Clone, Default)]
+pub struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+
+    // The text and the cursor before each edit.
+    undo: Vec<(Vec<char>, usize)>,
+    last_edit: Option<Edit>,
+}
+
+impl LineBuffer {
+    pub fn new() -> Self {
+        LineBuffer::default()
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn chars(&self) -> &[char] {
+        &self.chars
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    // Replace the whole line, such as the line recalled from the history, the cursor is moved to the end.
+    // It can't be undone, because the line isn't edited.
+    pub fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+        self.undo.clear();
+        self.last_edit = None;
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.save(Edit::Insert, c.is_whitespace());
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    // Replace the chars from the start to the cursor with the text, such as the word that is completed.
+    pub fn replace(&mut self, start: usize, text: &str) {
+        self.save(Edit::Replace, true);
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+        for c in text.chars() {
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    // Delete the char before the cursor, false is returned if there is none.
+    pub fn backspace(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.save(Edit::Delete, false);
+        self.cursor -= 1;
+        self.chars.remove(self.cursor);
+        true
+    }
+
+    // Delete the char under the cursor, false is returned if there is none.
+    pub fn delete(&mut self) -> bool {
+        if self.cursor == self.chars.len() {
+            return false;
+        }
+        self.save(Edit::Delete, false);
+        self.chars.remove(self.cursor);
+        true
+    }
+
+    // Delete the chars from the cursor to the end of the line.
+    pub fn kill_to_end(&mut self) {
+        self.kill(self.cursor, self.chars.len());
+    }
+
+    // Delete the chars from the start of the line to the cursor.
+    pub fn kill_to_start(&mut self) {
+        self.kill(0, self.cursor);
+    }
+
+    // Delete the word before the cursor and the blanks after it, such as 'src ' in 'ls src |'.
+    pub fn kill_word_before(&mut self) {
+        self.kill(self.word_start(), self.cursor);
+    }
+
+    fn kill(&mut self, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        self.save(Edit::Kill, true);
+        self.chars.drain(start..end);
+        self.cursor = start;
+    }
+
+    pub fn move_left(&mut self) {
+        self.move_to(self.cursor.saturating_sub(1));
+    }
+
+    pub fn move_right(&mut self) {
+        self.move_to((self.cursor + 1).min(self.chars.len()));
+    }
+
+    pub fn move_home(&mut self) {
+        self.move_to(0);
+    }
+
+    pub fn move_end(&mut self) {
+        self.move_to(self.chars.len());
+    }
+
+    // Move to the start of the word before the cursor.
+    pub fn move_word_left(&mut self) {
+        self.move_to(self.word_start());
+    }
+
+    // Move to the end of the word after the cursor.
+    pub fn move_word_right(&mut self) {
+        let mut end = self.cursor;
+        while end < self.chars.len() && self.chars[end].is_whitespace() {
+            end += 1;
+        }
+        while end < self.chars.len() && !self.chars[end].is_whitespace() {
+            end += 1;
+        }
+        self.move_to(end);
+    }
+
+    fn move_to(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        // The chars typed after moving are a new edit.
+        self.last_edit = None;
+    }
+
+    fn word_start(&self) -> usize {
+        let mut start = self.cursor;
+        while start > 0 && self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        start
+    }
+
+    // Undo the last edit, false is returned if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some((chars, cursor)) => {
+                self.chars = chars;
+                self.cursor = cursor;
+                self.last_edit = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Save the line before the edit, unless it continues the last edit of the same kind.
+    fn save(&mut self, edit: Edit, new_step: bool) {
+        if new_step || self.last_edit != Some(edit) {
+            self.undo.push((self.chars.clone(), self.cursor));
+        }
+        self.last_edit = Some(edit);
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use dirs_next::home_dir;
+
+use crate::builtin;
+use crate::lexer::{self, quote, Lexer};
+use crate::token::token::{Token, TokenType};
+
+use super::terminal::display_width;
+
+// The completion of the word before the cursor.
+// The lexer splits the line before the cursor into tokens, the word is made of the tokens that end at the cursor
+// without blanks or operators between them, and the tokens before it tell what the word is:
+// a command name if it's at the position of a command, such as 'gr' in 'ls | gr',
+// an option of the command if it starts with '-', such as '--de' in 'ls --de',
+// or a path otherwise, such as 'src/ma' in 'cat src/ma'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    // The index of the char where the word starts, the word goes on to the cursor.
+    pub start: usize,
+
+    // The words that can replace the word, they're sorted.
+    // The special chars of the paths are escaped, and the directories end with '/'.
+    pub candidates: Vec<String>,
+}
+
+// Complete the word before the cursor, which is an index of the chars.
+pub fn complete(line: &str, cursor: usize) -> Completion {
+    let before: String = line.chars().take(cursor).collect();
+    let lexer = Lexer::new(&before);
+    let tokens: Vec<Token> = std::iter::from_fn(|| lexer.next_token())
+        .filter(|token| *token.token_type() != TokenType::Eof)
+        .collect();
+
+    let mut start = cursor;
+    let mut count = 0;
+    for token in tokens.iter().rev() {
+        if token.span().end != start || is_operator(token.token_type()) {
+            break;
+        }
+        start = token.span().start;
+        count += 1;
+    }
+    let word: String = before.chars().skip(start).collect();
+
+    // Find the command of the word, it's the first token at the position of a command.
+    let mut command_position = true;
+    let mut command: Option<&Token> = None;
+    for token in tokens[..tokens.len() - count].iter() {
+        if command_position && *token.token_type() != TokenType::AssignmentWord {
+            command = Some(token);
+        }
+        command_position = lexer::is_command_position_after(command_position, token.token_type());
+    }
+
+    let candidates = if word.starts_with('-') && !command_position {
+        command.map_or_else(Vec::new, |command| complete_option(command, &word))
+    } else if command_position && !word.contains('/') {
+        complete_command(&word)
+    } else {
+        complete_path(&word)
+    };
+
+    Completion { start, candidates }
+}
+
+// Get the longest prefix that all the candidates share.
+pub fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix: Vec<char> = match candidates.first() {
+        Some(first) => first.chars().collect(),
+        None => return String::new(),
+    };
+
+    for candidate in candidates.iter().skip(1) {
+        let len = prefix
+            .iter()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        prefix.truncate(len);
+    }
+
+    prefix.into_iter().collect()
+}
+
+// Get the name of the candidate that is listed, which is the last part of the path, such as 'main.rs' of 'src/main.rs'.
+pub fn display_name(candidate: &str) -> String {
+    let (path, slash) = match candidate.strip_suffix('/') {
+        Some(path) => (path, "/"),
+        None => (candidate, ""),
+    };
+
+    match path.rfind('/') {
+        Some(index) => format!("{}{}", &path[index + 1..], slash),
+        None => candidate.to_string(),
+    }
+}
+
+// Lay out the items in columns that fit in the width, the items go down the columns one by one like 'ls'.
+pub fn columns(items: &[String], width: usize) -> Vec<String> {
+    let column_width = items
+        .iter()
+        .map(|item| display_width(item))
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let column_count = (width / column_width).max(1);
+    let row_count = items.len().div_ceil(column_count);
+
+    (0..row_count)
+        .map(|row| {
+            let mut line = String::new();
+            for column in 0..column_count {
+                if let Some(item) = items.get(column * row_count + row) {
+                    line.push_str(item);
+                    line.push_str(&" ".repeat(column_width - display_width(item)));
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+// The tokens that separate the words, so a word never goes on over them.
+fn is_operator(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Pipe
+            | TokenType::Semicolon
+            | TokenType::Background
+            | TokenType::And
+            | TokenType::Or
+            | TokenType::GreaterThan
+            | TokenType::DoubleGreaterThan
+            | TokenType::GreaterThanAnd
+            | TokenType::LessThan
+            | TokenType::DoubleLessThan
+            | TokenType::LeftParen
+            | TokenType::RightParen
+            | TokenType::LeftBrace
+            | TokenType::RightBrace
+            | TokenType::Comma
+    )
+}
+
+// Complete the option of the builtin command by its declaration, see [`crate::builtin`].
+// The external commands have no options to complete, because only the program knows them.
+fn complete_option(command: &Token, word: &str) -> Vec<String> {
+    if *command.token_type() == TokenType::Literal {
+        return Vec::new();
+    }
+    let builtin = match builtin::lookup(command.literal()) {
+        Some(builtin) => builtin,
+        None => return Vec::new(),
+    };
+
+    let mut options = BTreeSet::new();
+    for option in builtin.options() {
+        if let Some(short) = option.short() {
+            options.insert(format!("-{}", short));
+        }
+        if let Some(long) = option.long() {
+            options.insert(format!("--{}", long));
+        }
+    }
+
+    options
+        .into_iter()
+        .filter(|option| option.starts_with(word))
+        .collect()
+}
+
+// Complete the name of the command, which is a builtin command or a program in $PATH.
+fn complete_command(word: &str) -> Vec<String> {
+    let mut names: BTreeSet<String> = builtin::names()
+        .into_iter()
+        .filter(|name| name.starts_with(word))
+        .collect();
+
+    let path = env::var("PATH").unwrap_or_default();
+    for dir in env::split_paths(&path) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(word) && is_executable(&entry.path()) {
+                names.insert(escape(&name));
+            }
+        }
+    }
+
+    names.into_iter().collect()
+}
+
+// Complete the path, the directory part of the word is kept as it's typed, such as '~/' in '~/Doc'.
+// The hidden files are completed only if the name starts with '.'.
+fn complete_path(word: &str) -> Vec<String> {
+    let (dir, name) = match word.rfind('/') {
+        Some(index) => word.split_at(index + 1),
+        None => ("", word),
+    };
+    let name = quote::unescape(&quote::unquote(name));
+
+    // The directory is looked up without the quotes, and '~' is the home directory.
+    let mut dir_path = quote::unescape(&quote::unquote(dir));
+    if dir_path.starts_with("~/") {
+        if let Some(home) = home_dir() {
+            dir_path = format!("{}{}", home.to_string_lossy(), &dir_path[1..]);
+        }
+    }
+    if dir_path.is_empty() {
+        dir_path = String::from(".");
+    }
+
+    let entries = match fs::read_dir(&dir_path) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths = BTreeSet::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !file_name.starts_with(&name) || (file_name.starts_with('.') && !name.starts_with('.')) {
+            continue;
+        }
+
+        // The symbolic links to directories are directories too.
+        let slash = if entry.path().is_dir() { "/" } else { "" };
+        paths.insert(format!("{}{}{}", dir, escape(&file_name), slash));
+    }
+
+    paths.into_iter().collect()
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+// Escape the chars of the name that would be special in the command line, such as the blanks.
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for c in name.chars() {
+        if c.is_whitespace() || "\\'\"$`*?[]|&;<>(){}#!".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
@@ -0,0 +1,141 @@
+use std::io::{self, Read};
+
+// A key pressed in the terminal, it's decoded from the bytes that the terminal sends in raw mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    // A char that is typed, it may be a multi-byte char, such as '你'.
+    Char(char),
+    // Ctrl with a letter, such as Ctrl-A, the letter is lowercase. Ctrl-_ is one too.
+    Ctrl(char),
+    // Alt with a char, the terminal sends Esc before the char, such as Alt-B.
+    Alt(char),
+
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    CtrlLeft,
+    CtrlRight,
+
+    // The escape sequences that aren't supported, they're ignored.
+    Unknown,
+}
+
+// Read one key, None is returned at the end of the input.
+// The error of the first byte is returned as it is, so the editor can see the interruption of a signal,
+// such as the resize of the terminal, the other bytes are read again if they're interrupted.
+pub fn read_key(reader: &mut impl Read) -> io::Result<Option<Key>> {
+    let byte = match read_byte(reader)? {
+        Some(byte) => byte,
+        None => return Ok(None),
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => read_escape(reader)?,
+        0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+        0x1f => Key::Ctrl('_'),
+        0x00..=0x1f => Key::Unknown,
+        _ => read_char(reader, byte)?,
+    };
+
+    Ok(Some(key))
+}
+
+// Read the escape sequence after Esc, such as '[A' of Up or '[1;5C' of Ctrl-Right.
+fn read_escape(reader: &mut impl Read) -> io::Result<Key> {
+    let byte = match read_rest(reader)? {
+        Some(byte) => byte,
+        None => return Ok(Key::Unknown),
+    };
+
+    match byte {
+        b'[' => {
+            // The parameters are followed by the final byte, which is in '@'..='~'.
+            let mut params = String::new();
+            loop {
+                match read_rest(reader)? {
+                    Some(byte @ 0x40..=0x7e) => return Ok(csi_key(&params, byte)),
+                    Some(byte) => params.push(byte as char),
+                    None => return Ok(Key::Unknown),
+                }
+            }
+        }
+        b'O' => Ok(match read_rest(reader)? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            _ => Key::Unknown,
+        }),
+        0x20..=0x7e => Ok(Key::Alt(byte as char)),
+        _ => Ok(Key::Unknown),
+    }
+}
+
+fn csi_key(params: &str, byte: u8) -> Key {
+    match (params, byte) {
+        ("", b'A') => Key::Up,
+        ("", b'B') => Key::Down,
+        ("", b'C') => Key::Right,
+        ("", b'D') => Key::Left,
+        ("", b'H') | ("1", b'~') | ("7", b'~') => Key::Home,
+        ("", b'F') | ("4", b'~') | ("8", b'~') => Key::End,
+        ("3", b'~') => Key::Delete,
+        ("1;5", b'C') => Key::CtrlRight,
+        ("1;5", b'D') => Key::CtrlLeft,
+        _ => Key::Unknown,
+    }
+}
+
+// Read the bytes after the first byte of a UTF-8 char, the invalid chars are ignored.
+fn read_char(reader: &mut impl Read, first: u8) -> io::Result<Key> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        0x20..=0x7e => 1,
+        _ => return Ok(Key::Unknown),
+    };
+
+    let mut bytes = vec![first];
+    while bytes.len() < len {
+        match read_rest(reader)? {
+            Some(byte) => bytes.push(byte),
+            None => return Ok(Key::Unknown),
+        }
+    }
+
+    Ok(match std::str::from_utf8(&bytes) {
+        Ok(s) => s.chars().next().map_or(Key::Unknown, Key::Char),
+        Err(_) => Key::Unknown,
+    })
+}
+
+fn read_byte(reader: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut buf = [0u8; 1];
+    match reader.read(&mut buf)? {
+        0 => Ok(None),
+        _ => Ok(Some(buf[0])),
+    }
+}
+
+// Read the next byte of the key, it's read again if it's interrupted.
+fn read_rest(reader: &mut impl Read) -> io::Result<Option<u8>> {
+    loop {
+        match read_byte(reader) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::io::{self, Write};
+
+use self::buffer::LineBuffer;
+use self::key::Key;
+use self::terminal::{RawMode, TerminalInput};
+
+pub mod buffer;
+pub mod completion;
+pub mod key;
+pub mod terminal;
+
+// What the editor does after a key has been handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    // The line may have been changed, it's drawn again.
+    Edit,
+    // Enter is pressed, the line is done.
+    Accept(String),
+    // Ctrl-C is pressed, the line is dropped.
+    Cancel,
+    // Ctrl-D is pressed on an empty line, which is the end of the input.
+    Eof,
+    // Ctrl-L is pressed.
+    ClearScreen,
+    // Tab is pressed, and the word can be completed in several ways, which are listed under the line.
+    List(Vec<String>),
+}
+
+// The line editor of the REPL, it reads the keys in raw mode and draws the line after each of them.
+// The line can be edited like in other shells:
+// Left/Right, Ctrl-B/Ctrl-F: move the cursor by one char
+// Ctrl-Left/Ctrl-Right, Alt-B/Alt-F: move the cursor by one word
+// Home/End, Ctrl-A/Ctrl-E: move the cursor to the start or the end of the line
+// Up/Down, Ctrl-P/Ctrl-N: recall the previous or the next line of the history
+// Backspace, Delete/Ctrl-D: delete the char before or under the cursor
+// Ctrl-W, Ctrl-U, Ctrl-K: delete the word before the cursor, the chars before it, or the chars after it
+// Ctrl-_: undo the last edit
+// Tab: complete the command, the option or the path before the cursor, see [`completion`]
+// Ctrl-C: drop the line, Ctrl-D: the end of the input on an empty line, Ctrl-L: clear the screen
+//
+// The line is read without editing if the input or the output isn't a terminal, such as a script piped in.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    buffer: LineBuffer,
+
+    history: Vec<String>,
+    // The position in the history while it's browsed, it's the length of the history on the line being typed.
+    history_index: usize,
+    // The line being typed, it's kept while the history is browsed.
+    draft: String,
+
+    // The row of the cursor below the row where the prompt starts, the line is drawn again from there.
+    cursor_row: usize,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        LineEditor::default()
+    }
+
+    pub fn buffer(&self) -> &LineBuffer {
+        &self.buffer
+    }
+
+    // Set the lines of the history that are recalled, the empty lines and the lines same as the one before are skipped.
+    pub fn set_history(&mut self, lines: &[String]) {
+        self.history.clear();
+        for line in lines {
+            if !line.trim().is_empty() && self.history.last() != Some(line) {
+                self.history.push(line.clone());
+            }
+        }
+        self.history_index = self.history.len();
+    }
+
+    // Read one line without the newline, None is returned at the end of the input.
+    // The prompt may have several lines, only the last one is drawn again with the line.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        if !terminal::is_terminal() {
+            return read_plain_line(prompt);
+        }
+
+        let (head, prompt) = prompt.split_at(prompt.rfind('\n').map_or(0, |i| i + 1));
+        let mut out = io::stdout();
+        write!(out, "{}", head)?;
+
+        let _raw_mode = RawMode::enable()?;
+        self.buffer = LineBuffer::new();
+        self.history_index = self.history.len();
+        self.draft.clear();
+        self.cursor_row = 0;
+        self.refresh(prompt, &mut out)?;
+
+        let mut input = TerminalInput;
+        loop {
+            let key = match key::read_key(&mut input) {
+                Ok(Some(key)) => key,
+                Ok(None) => return Ok(None),
+                // The terminal has been resized, the line is drawn again with the new width.
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    if terminal::take_resized() {
+                        let prompt_width = terminal::display_width(prompt);
+                        let (_, cursor) = self.layout(prompt_width, terminal::columns());
+                        self.cursor_row = cursor.0;
+                        self.refresh(prompt, &mut out)?;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            match self.handle_key(key) {
+                Action::Edit => self.refresh(prompt, &mut out)?,
+                Action::Accept(line) => {
+                    self.refresh(prompt, &mut out)?;
+                    write!(out, "\r\n")?;
+                    out.flush()?;
+                    return Ok(Some(line));
+                }
+                Action::Cancel => {
+                    self.refresh(prompt, &mut out)?;
+                    write!(out, "^C\r\n")?;
+                    out.flush()?;
+                    return Ok(Some(String::new()));
+                }
+                Action::Eof => {
+                    write!(out, "\r\n")?;
+                    out.flush()?;
+                    return Ok(None);
+                }
+                Action::ClearScreen => {
+                    write!(out, "\x1b[H\x1b[2J")?;
+                    self.cursor_row = 0;
+                    self.refresh(prompt, &mut out)?;
+                }
+                Action::List(items) => self.list(prompt, &items, &mut out)?,
+            }
+        }
+    }
+
+    // Handle the key, see [`LineEditor`] for the keys.
+    pub fn handle_key(&mut self, key: Key) -> Action {
+        match key {
+            Key::Char(c) => self.buffer.insert(c),
+            Key::Enter => {
+                self.buffer.move_end();
+                return Action::Accept(self.buffer.text());
+            }
+            Key::Ctrl('c') => {
+                self.buffer.move_end();
+                return Action::Cancel;
+            }
+            Key::Ctrl('d') if self.buffer.is_empty() => return Action::Eof,
+            Key::Ctrl('d') | Key::Delete => {
+                self.buffer.delete();
+            }
+            Key::Backspace => {
+                self.buffer.backspace();
+            }
+            Key::Left | Key::Ctrl('b') => self.buffer.move_left(),
+            Key::Right | Key::Ctrl('f') => self.buffer.move_right(),
+            Key::Home | Key::Ctrl('a') => self.buffer.move_home(),
+            Key::End | Key::Ctrl('e') => self.buffer.move_end(),
+            Key::CtrlLeft | Key::Alt('b') => self.buffer.move_word_left(),
+            Key::CtrlRight | Key::Alt('f') => self.buffer.move_word_right(),
+            Key::Up | Key::Ctrl('p') => self.history_prev(),
+            Key::Down | Key::Ctrl('n') => self.history_next(),
+            Key::Ctrl('w') => self.buffer.kill_word_before(),
+            Key::Ctrl('u') => self.buffer.kill_to_start(),
+            Key::Ctrl('k') => self.buffer.kill_to_end(),
+            Key::Ctrl('_') => {
+                self.buffer.undo();
+            }
+            Key::Ctrl('l') => return Action::ClearScreen,
+            Key::Tab => return self.complete(),
+            _ => {}
+        }
+
+        Action::Edit
+    }
+
+    // Complete the word before the cursor.
+    // The word is replaced by the only candidate, or by the prefix that all the candidates share,
+    // and the candidates are listed if the word can't be longer.
+    fn complete(&mut self) -> Action {
+        let completion = completion::complete(&self.buffer.text(), self.buffer.cursor());
+        let word_len = self.buffer.cursor() - completion.start;
+
+        match completion.candidates.as_slice() {
+            [] => {}
+            [candidate] => {
+                // The word is done unless it's a directory, which may go on with the files in it.
+                let mut word = candidate.clone();
+                let next = self.buffer.chars().get(self.buffer.cursor());
+                if !word.ends_with('/') && !next.is_some_and(|c| c.is_whitespace()) {
+                    word.push(' ');
+                }
+                self.buffer.replace(completion.start, &word);
+            }
+            candidates => {
+                let prefix = completion::common_prefix(candidates);
+                if prefix.chars().count() <= word_len {
+                    return Action::List(
+                        candidates
+                            .iter()
+                            .map(|c| completion::display_name(c))
+                            .collect(),
+                    );
+                }
+                self.buffer.replace(completion.start, &prefix);
+            }
+        }
+
+        Action::Edit
+    }
+
+    fn history_prev(&mut self) {
+        if self.history_index == 0 {
+            return;
+        }
+        if self.history_index == self.history.len() {
+            self.draft = self.buffer.text();
+        }
+        self.history_index -= 1;
+        self.buffer.set(&self.history[self.history_index]);
+    }
+
+    fn history_next(&mut self) {
+        if self.history_index >= self.history.len() {
+            return;
+        }
+        self.history_index += 1;
+        match self.history.get(self.history_index) {
+            Some(line) => self.buffer.set(line),
+            None => self.buffer.set(&self.draft),
+        }
+    }
+
+    // List the items in columns under the line, and draw the line again after them.
+    fn list(&mut self, prompt: &str, items: &[String], out: &mut impl Write) -> io::Result<()> {
+        let columns = terminal::columns();
+        let (end, _) = self.layout(terminal::display_width(prompt), columns);
+
+        let mut screen = String::new();
+        if end.0 > self.cursor_row {
+            screen.push_str(&format!("\x1b[{}B", end.0 - self.cursor_row));
+        }
+        screen.push_str("\r\n");
+        for row in completion::columns(items, columns) {
+            screen.push_str(&row);
+            screen.push_str("\r\n");
+        }
+        out.write_all(screen.as_bytes())?;
+
+        self.cursor_row = 0;
+        self.refresh(prompt, out)
+    }
+
+    // Draw the prompt and the line again, and put the cursor where it's in the line.
+    // The line may take several rows, and the wide chars that don't fit at the end of a row go to the next row.
+    fn refresh(&mut self, prompt: &str, out: &mut impl Write) -> io::Result<()> {
+        let (end, cursor) = self.layout(terminal::display_width(prompt), terminal::columns());
+
+        let mut screen = String::new();
+        // Go back to the row of the prompt, and clear everything after it.
+        if self.cursor_row > 0 {
+            screen.push_str(&format!("\x1b[{}A", self.cursor_row));
+        }
+        screen.push_str("\r\x1b[J");
+        screen.push_str(prompt);
+        for &c in self.buffer.chars() {
+            match terminal::caret(c) {
+                Some(caret) => {
+                    screen.push('^');
+                    screen.push(caret);
+                }
+                None => screen.push(c),
+            }
+        }
+
+        // The cursor stays at the last column after the row has been filled, so it's moved to the next row.
+        if end.1 == 0 && end.0 > 0 {
+            screen.push_str("\r\n");
+        }
+
+        // Go from the end of the line to the cursor.
+        if end.0 > cursor.0 {
+            screen.push_str(&format!("\x1b[{}A", end.0 - cursor.0));
+        }
+        screen.push('\r');
+        if cursor.1 > 0 {
+            screen.push_str(&format!("\x1b[{}C", cursor.1));
+        }
+        self.cursor_row = cursor.0;
+
+        out.write_all(screen.as_bytes())?;
+        out.flush()
+    }
+
+    // Get the rows and the columns of the end of the line and the cursor, the rows start from the prompt.
+    fn layout(&self, prompt_width: usize, columns: usize) -> ((usize, usize), (usize, usize)) {
+        let columns = columns.max(1);
+        let mut pos = (prompt_width / columns, prompt_width % columns);
+        let mut cursor = pos;
+
+        for (i, &c) in self.buffer.chars().iter().enumerate() {
+            let width = terminal::char_width(c);
+            if pos.1 + width > columns {
+                pos = (pos.0 + 1, 0);
+            }
+            if i == self.buffer.cursor() {
+                cursor = pos;
+            }
+            pos.1 += width;
+            if pos.1 >= columns {
+                pos = (pos.0 + 1, 0);
+            }
+        }
+        if self.buffer.cursor() == self.buffer.chars().len() {
+            cursor = pos;
+        }
+
+        (pos, cursor)
+    }
+}
+
+// Read the line from the input that isn't a terminal, the prompt is still shown.
+fn read_plain_line(prompt: &str) -> io::Result<Option<String>> {
+    print!("{}", prompt);
+    let _ = io::stdout().flush();
+
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+    }
+
+    Ok(Some(line))
+}
@@ -0,0 +1,144 @@
+use std::io::{self, Read};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use libc::c_int;
+use unicode_width::UnicodeWidthChar;
+
+// Whether the terminal has been resized since it was checked, it's set by the handler of SIGWINCH.
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_resize(_signal: c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+// Whether the terminal has been resized since the last call.
+pub fn take_resized() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+// Whether the line can be edited, which needs a terminal for both the input and the output.
+pub fn is_terminal() -> bool {
+    let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    unsafe {
+        !dumb && libc::isatty(libc::STDIN_FILENO) == 1 && libc::isatty(libc::STDOUT_FILENO) == 1
+    }
+}
+
+// Get the number of columns of the terminal, 80 is used if it's unknown.
+pub fn columns() -> usize {
+    let mut size: libc::winsize = unsafe { mem::zeroed() };
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if result == 0 && size.ws_col > 0 {
+        size.ws_col as usize
+    } else {
+        80
+    }
+}
+
+// The terminal is in raw mode while the value lives, so the keys are read one by one without being echoed,
+// and the previous mode is restored when it's dropped, before the command runs.
+// The resize of the terminal is watched meanwhile, it interrupts the reading of the keys,
+// so the line can be drawn again with the new width.
+pub struct RawMode {
+    original: libc::termios,
+    resize_action: libc::sigaction,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        let mut original: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The keys of the signals, such as Ctrl-C, and the flow control keys are read as keys too.
+        let mut raw = original;
+        raw.c_iflag &= !(libc::BRKINT | libc::ICRNL | libc::INPCK | libc::ISTRIP | libc::IXON);
+        raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::IEXTEN | libc::ISIG);
+        raw.c_cflag |= libc::CS8;
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &raw) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SA_RESTART isn't set, so the reading is interrupted by the signal.
+        let mut resize_action: libc::sigaction = unsafe { mem::zeroed() };
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_resize as *const () as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGWINCH, &action, &mut resize_action);
+        }
+
+        Ok(RawMode {
+            original,
+            resize_action,
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &self.original);
+            libc::sigaction(libc::SIGWINCH, &self.resize_action, ptr::null_mut());
+        }
+    }
+}
+
+// The input of the terminal, it's read without the buffer of stdin,
+// so the interruption of a signal is returned instead of being retried.
+pub struct TerminalInput;
+
+impl Read for TerminalInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        if n < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+}
+
+// Get the number of columns that the char takes in the terminal, a wide char, such as '你', takes two.
+// The control chars are shown in caret notation, such as '^J' for the newline, see [`caret`].
+pub fn char_width(c: char) -> usize {
+    match caret(c) {
+        Some(_) => 2,
+        None => c.width().unwrap_or(0),
+    }
+}
+
+// Get the caret notation of the control char, such as 'J' of '^J'.
+pub fn caret(c: char) -> Option<char> {
+    match c {
+        '\0'..='\x1f' | '\x7f' => Some(((c as u8) ^ 0x40) as char),
+        _ => None,
+    }
+}
+
+// Get the number of columns that the text takes in the terminal,
+// the escape sequences, such as the colors of the prompt, take none.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // The sequence is '\x1b[', the parameters, and the final char, which is in '@'..='~'.
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        width += char_width(c);
+    }
+    width
+}
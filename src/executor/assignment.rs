@@ -0,0 +1,100 @@
+use std::env;
+use std::rc::Rc;
+
+use crate::session;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::expansion::expand_assignment;
+use super::{analyze_node, Command};
+
+// The assignment operator sets the variables, such as 'A=1 B=$A'.
+// The values are expanded one by one, so an assignment sees the ones before it.
+//
+// If a command follows the assignments, such as 'FOO=1 env',
+// the variables are exported to the command only, and restored after it has run.
+pub struct AssignmentOperator {
+    // The assignments in the form of 'NAME=value'.
+    assignments: Vec<String>,
+
+    // The command that runs with the variables.
+    // It's built before the assignments are made, so its values don't see them, like other shells.
+    source: Option<Box<dyn Command>>,
+}
+
+impl AssignmentOperator {
+    fn new(assignments: Vec<String>, source: Option<Box<dyn Command>>) -> Self {
+        Self {
+            assignments,
+            source,
+        }
+    }
+
+    // Split the assignments into the names and the expanded values.
+    fn assignment(assignment: &str) -> (&str, String) {
+        let (name, value) = assignment.split_once('=').unwrap_or((assignment, ""));
+        (name, expand_assignment(value))
+    }
+}
+
+impl Command for AssignmentOperator {
+    // The exit status is the one of the command, or 0 if there is no command.
+    fn execute(&self) -> i32 {
+        let source = match &self.source {
+            Some(source) => source,
+            None => {
+                for assignment in self.assignments.iter() {
+                    let (name, value) = Self::assignment(assignment);
+                    session::set_variable(name, &value);
+                }
+                return 0;
+            }
+        };
+
+        // Put the variables into the environment, and remember the old values to restore them.
+        let mut old_values = Vec::new();
+        for assignment in self.assignments.iter() {
+            let (name, value) = Self::assignment(assignment);
+            old_values.push((name, env::var_os(name)));
+            env::set_var(name, value);
+        }
+
+        let status = source.execute();
+
+        // Restore them in reverse order, so the oldest value wins if a name is assigned twice.
+        for (name, old_value) in old_values.into_iter().rev() {
+            match old_value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+
+        status
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        if let Some(source) = &mut self.source {
+            source.add_stream(stream);
+        }
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        if let Some(source) = &mut self.source {
+            source.add_err_stream(stream);
+        }
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        if let Some(source) = &mut self.source {
+            source.add_input_stream(stream);
+        }
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for AssignmentOperator {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        Self::new(
+            cmd.get_values().unwrap_or_default(),
+            cmd.get_source().map(analyze_node),
+        )
+    }
+}
@@ -0,0 +1,85 @@
+use std::rc::Rc;
+
+use crate::jobs;
+use crate::stream::console_stream::ConsoleStream;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::{analyze_node, error_stream, Command};
+
+// The background operator runs the command in the background, such as 'sleep 10 &'.
+// The command runs in a child process of the shell and is added to the job table,
+// the shell prints the number of the job and the pid, and continues without waiting for it.
+pub struct BackgroundOperator {
+    // The command that runs in the background.
+    // It's built in the child process, so the AST is kept here.
+    source: Box<dyn CommandAstNode>,
+
+    // The command line of the command, it's shown in the job table.
+    command: String,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl BackgroundOperator {
+    fn new(source: Box<dyn CommandAstNode>, command: String) -> Self {
+        Self {
+            source,
+            command,
+            stream: None,
+            err_stream: None,
+        }
+    }
+}
+
+impl Command for BackgroundOperator {
+    // The exit status is 0 if the command has been started.
+    fn execute(&self) -> i32 {
+        let stream = self.stream.as_ref().unwrap();
+
+        let result = jobs::spawn_background(&self.command, || {
+            // The output of the job goes to the terminal directly,
+            // because the streams of the shell are not shared with the child process.
+            let console_stream = Rc::new(ConsoleStream::new());
+            let mut cmd = analyze_node(self.source.clone());
+            cmd.add_stream(console_stream.clone());
+            cmd.add_err_stream(Rc::new(ConsoleStream::stderr()));
+
+            let status = cmd.execute();
+            console_stream.output();
+
+            status
+        });
+
+        match result {
+            Ok((id, pid)) => {
+                stream.input(format!("[{}] {}", id, pid));
+                0
+            }
+            Err(e) => {
+                error_stream(&self.err_stream, &self.stream)
+                    .input(format!("ru_shell: fork: {}", e));
+                1
+            }
+        }
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for BackgroundOperator {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        let command = cmd
+            .get_values()
+            .and_then(|values| values.first().cloned())
+            .unwrap_or_default();
+
+        Self::new(cmd.get_source().unwrap(), command)
+    }
+}
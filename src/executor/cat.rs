@@ -1,13 +1,16 @@
 use std::{
     fs::File,
-    io::{self, BufRead},
-    path::PathBuf,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
-use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+use crate::{
+    parser::ast_node_trait::CommandAstNode,
+    stream::{self, Stream},
+};
 
-use super::Command;
+use super::{error::ShellError, error_stream, Command};
 
 pub struct CatCmd {
     // Output line number
@@ -22,144 +25,200 @@ pub struct CatCmd {
     // Display $ at end of each line
     show_ends: bool,
 
-    file: Option<PathBuf>,
+    // The files are read one by one.
+    files: Vec<PathBuf>,
 
     stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+
+    // The data is read from this stream if no file is given.
+    input_stream: Option<Rc<dyn Stream>>,
 }
 
 impl CatCmd {
-    fn new(file: Option<PathBuf>) -> Self {
+    fn new(files: Vec<PathBuf>) -> Self {
         CatCmd {
             line_number: false,
             line_number_non_blank: false,
             squeeze_blank: false,
             show_ends: false,
-            file,
+            files,
             stream: None,
+            err_stream: None,
+            input_stream: None,
         }
     }
 }
 
+// The state of the lines that goes on from one file to the next one, such as the line number.
+struct LineState {
+    line_num: u32,
+    prev_line_empty: bool,
+}
+
 impl CatCmd {
-    fn read_with_options(&self) -> Vec<(u32, String)> {
-        let data: Vec<String> = if self.stream.as_ref().unwrap().is_empty() {
-            // Get the data from the file
-            let file = File::open(&self.file.as_ref().unwrap()).unwrap();
-            let reader = io::BufReader::new(file);
-            reader.lines().map(|line| {
-                line.unwrap_or_else(|_| {
-                    panic!("Error reading file: {}", self.file.as_ref().unwrap().display());
-                })
-            }).collect()
+    // Write the data to the stream while it's read, and stop early if the reader of the stream has stopped,
+    // such as 'cat big.log | head -1'.
+    // Without the options, the data is copied as it is, so binary files and CRLF line breaks are kept.
+    fn read(&self, reader: &mut dyn BufRead, state: &mut LineState) -> io::Result<()> {
+        if self.line_number || self.line_number_non_blank || self.squeeze_blank || self.show_ends {
+            self.read_lines(reader, state)
         } else {
-            // Get the data from the stream
-            self.stream.as_ref().unwrap().output().lines().map(|line| line.to_string()).collect()
-        };
+            self.copy(reader)
+        }
+    }
 
-        let mut result: Vec<(u32, String)> = vec![];
+    // Read the file, the error tells which file can't be read.
+    fn read_file(&self, path: &Path, state: &mut LineState) -> Result<(), ShellError> {
+        let error = |e| ShellError::io("cat", &path.display().to_string(), e);
 
-        let mut line_num = 1;
-        let mut prev_line_empty = false;
+        let file = File::open(path).map_err(error)?;
+        if file.metadata().is_ok_and(|metadata| metadata.is_dir()) {
+            return Err(ShellError::new(
+                "cat",
+                format!("{}: Is a directory", path.display()),
+            ));
+        }
+
+        self.read(&mut BufReader::new(file), state).map_err(error)
+    }
+
+    // Copy the data as it is.
+    fn copy(&self, reader: &mut dyn BufRead) -> io::Result<()> {
+        let stream = self.stream.as_ref().unwrap();
+
+        loop {
+            let data = reader.fill_buf()?;
+            if data.is_empty() {
+                return Ok(());
+            }
+
+            stream.write(data);
+            let len = data.len();
+            reader.consume(len);
+
+            if stream.is_closed() {
+                return Ok(());
+            }
+        }
+    }
+
+    // Write the data line by line with the options, the bytes of each line are kept as they are.
+    fn read_lines(&self, reader: &mut dyn BufRead, state: &mut LineState) -> io::Result<()> {
+        let stream = self.stream.as_ref().unwrap();
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+
+            // The last line may have no line break.
+            let line_break = line.ends_with(b"\n");
+            if line_break {
+                line.pop();
+            }
 
-        for line in data.iter() {
             if self.line_number_non_blank && line.is_empty() {
                 continue;
             } else if self.squeeze_blank && line.is_empty() {
-                if prev_line_empty {
+                if state.prev_line_empty {
                     continue;
                 }
-                prev_line_empty = true;
+                state.prev_line_empty = true;
             } else {
-                prev_line_empty = false;
+                state.prev_line_empty = false;
             }
-            result.push((line_num, line.trim().to_string()));
-            line_num += 1;
-        }
-
-        result
-    }
 
-    fn read(&self) {
-        let results: Vec<String> = self.read_with_options()
-            .iter()
-            .map(|(num, line_str)| {
-                if self.line_number {
-                    format!("{} {}", num, line_str)
-                } else {
-                    format!("{}", line_str)
-                }
-            })
-            .map(|line| {
-                if self.show_ends {
-                    format!("{}$", line)
-                } else {
-                    line
-                }
-            })
-            .map(|line| {
-                format!("{}", line)
-            }).collect();
+            let mut data = Vec::new();
+            if self.line_number {
+                data.extend(format!("{} ", state.line_num).bytes());
+            }
+            data.extend(&line);
+            if self.show_ends {
+                data.push(b'$');
+            }
+            if line_break {
+                data.push(b'\n');
+            }
+            state.line_num += 1;
 
-        for line in results.iter() {
-            self.stream.as_ref().unwrap().input(line.to_string());
+            stream.write(&data);
+            if stream.is_closed() {
+                return Ok(());
+            }
         }
     }
 }
 
 impl Command for CatCmd {
-    fn execute(&self) {
-        self.read();
+    // The exit status is 1 if any file can't be read, the other files are still read.
+    fn execute(&self) -> i32 {
+        let mut state = LineState {
+            line_num: 1,
+            prev_line_empty: false,
+        };
+
+        // The data is read from the input stream if no file is given.
+        if self.files.is_empty() {
+            let input_stream = match &self.input_stream {
+                Some(input_stream) => input_stream,
+                None => return 0,
+            };
+            let mut reader = BufReader::new(stream::reader(input_stream.as_ref()));
+            return match self.read(&mut reader, &mut state) {
+                Ok(_) => 0,
+                Err(e) => {
+                    error_stream(&self.err_stream, &self.stream)
+                        .input(ShellError::io("cat", "-", e).to_string());
+                    1
+                }
+            };
+        }
+
+        let mut status = 0;
+        for file in self.files.iter() {
+            if let Err(e) = self.read_file(file, &mut state) {
+                error_stream(&self.err_stream, &self.stream).input(e.to_string());
+                status = 1;
+            }
+            if self.stream.as_ref().unwrap().is_closed() {
+                break;
+            }
+        }
+
+        status
     }
 
     fn add_stream(&mut self, stream: Rc<dyn Stream>) {
         self.stream = Some(stream);
     }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.input_stream = Some(stream);
+    }
 }
 
 impl From<Box<dyn CommandAstNode>> for CatCmd {
     fn from(cmd: Box<dyn CommandAstNode>) -> Self {
-        // Get values
-        let values = cmd.get_values().unwrap_or_else(|| Vec::new());
-
-        // Get file
-        let file = match values.get(0) {
-            Some(values) => values,
-            None => "",
-        };
-
-        let mut cat_cmd = if file.is_empty() {
-            CatCmd::new(None)
-        } else {
-            // Check if file exists
-            let file_buf = PathBuf::from(file);
-            if file_buf.exists() == false {
-                panic!("File {} does not exist", file_buf.display());
-            }
-
-            CatCmd::new(Some(file_buf))
-        };
+        // Get files, they're checked when the command is executed.
+        let files = cmd.get_values().unwrap_or_default();
+        let mut cat_cmd = CatCmd::new(files.into_iter().map(PathBuf::from).collect());
 
         // Get options
-        cat_cmd.line_number = match cmd.get_option("-n").or(cmd.get_option("--number")) {
-            Some(_) => true,
-            None => false,
-        };
+        cat_cmd.line_number = cmd.get_option("-n").or(cmd.get_option("--number")).is_some();
 
         cat_cmd.line_number_non_blank =
-            match cmd.get_option("-b").or(cmd.get_option("--number-nonblank")) {
-                Some(_) => true,
-                None => false,
-            };
+            cmd.get_option("-b").or(cmd.get_option("--number-nonblank")).is_some();
 
-        cat_cmd.squeeze_blank = match cmd.get_option("-s").or(cmd.get_option("--squeeze-blank")) {
-            Some(_) => true,
-            None => false,
-        };
+        cat_cmd.squeeze_blank = cmd.get_option("-s").or(cmd.get_option("--squeeze-blank")).is_some();
 
-        cat_cmd.show_ends = match cmd.get_option("-e").or(cmd.get_option("--show-ends")) {
-            Some(_) => true,
-            None => false,
-        };
+        cat_cmd.show_ends = cmd.get_option("-e").or(cmd.get_option("--show-ends")).is_some();
 
         cat_cmd
     }
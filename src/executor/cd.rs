@@ -0,0 +1,209 @@
+use std::{
+    env,
+    io,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::expansion::home_dir;
+use super::{error_stream, Command};
+
+// The 'cd' command changes the working directory of the shell process.
+//
+// These are the values that the 'cd' command supports:
+//     cd          : change to the home directory
+//     cd -        : change to the previous directory ($OLDPWD) and print it
+//     cd path     : change to the path, a relative path is looked up in $CDPATH first
+//
+// After the directory has been changed, $OLDPWD is set to the old directory
+// and $PWD is set to the new one.
+pub struct CdCmd {
+    // The directory to change to, None means the home directory.
+    target: Option<String>,
+
+    // 'cd' accepts only one directory, the other ones are kept to report the error.
+    extra_targets: usize,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl CdCmd {
+    fn new(target: Option<String>, extra_targets: usize) -> Self {
+        CdCmd {
+            target,
+            extra_targets,
+            stream: None,
+            err_stream: None,
+        }
+    }
+
+    // Work out which directory to change to.
+    // The second value tells whether the directory should be printed,
+    // which is what 'cd -' and a match found through $CDPATH do.
+    fn resolve_target(&self) -> Result<(PathBuf, bool), String> {
+        let target = match &self.target {
+            None => return home().map(|home| (home, false)),
+            Some(target) => target.as_str(),
+        };
+
+        if target == "-" {
+            return match env::var("OLDPWD") {
+                Ok(old_pwd) if !old_pwd.is_empty() => Ok((PathBuf::from(old_pwd), true)),
+                _ => Err(String::from("OLDPWD not set")),
+            };
+        }
+
+        let path = PathBuf::from(target);
+        if path.is_absolute() {
+            return Ok((path, false));
+        }
+
+        // $CDPATH isn't used for paths starting with '.' or '..'.
+        let explicit_relative = matches!(
+            path.components().next(),
+            Some(Component::CurDir) | Some(Component::ParentDir)
+        );
+        if !explicit_relative {
+            if let Some(found) = search_cdpath(&path) {
+                return Ok(found);
+            }
+        }
+
+        Ok((current_pwd()?.join(path), false))
+    }
+
+    // Change the working directory, and update $PWD and $OLDPWD.
+    fn change_dir(&self) -> Result<Option<PathBuf>, String> {
+        if self.extra_targets > 0 {
+            return Err(String::from("too many arguments"));
+        }
+
+        let (target, print) = self.resolve_target()?;
+        let old_pwd = current_pwd()?;
+
+        // The '..' in the path is resolved logically, like other shells do,
+        // so that 'cd ..' goes back through the symbolic link that was followed.
+        let new_pwd = normalize(&old_pwd.join(&target));
+        if let Err(e) = env::set_current_dir(&new_pwd) {
+            let name = self.target.clone().unwrap_or_else(|| target.display().to_string());
+            return Err(format!("{}: {}", name, describe_error(&new_pwd, &e)));
+        }
+
+        env::set_var("OLDPWD", &old_pwd);
+        env::set_var("PWD", &new_pwd);
+
+        Ok(if print { Some(new_pwd) } else { None })
+    }
+}
+
+impl Command for CdCmd {
+    // The exit status is 1 if the directory can't be changed.
+    fn execute(&self) -> i32 {
+        let stream = self.stream.as_ref().unwrap();
+
+        match self.change_dir() {
+            Ok(Some(path)) => stream.input(path.display().to_string()),
+            Ok(None) => {}
+            Err(msg) => {
+                error_stream(&self.err_stream, &self.stream).input(format!("cd: {}", msg));
+                return 1;
+            }
+        }
+
+        0
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for CdCmd {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        // Get values
+        let values = cmd.get_values().unwrap_or_default();
+
+        CdCmd::new(values.first().cloned(), values.len().saturating_sub(1))
+    }
+}
+
+fn home() -> Result<PathBuf, String> {
+    home_dir().ok_or_else(|| String::from("HOME not set"))
+}
+
+// Get the logical working directory.
+// $PWD is used if it still points to the working directory, because it keeps the symbolic links.
+fn current_pwd() -> Result<PathBuf, String> {
+    let cwd = env::current_dir().map_err(|e| e.to_string())?;
+
+    match env::var("PWD") {
+        Ok(pwd) if Path::new(&pwd).is_absolute() && same_file(Path::new(&pwd), &cwd) => {
+            Ok(PathBuf::from(pwd))
+        }
+        _ => Ok(cwd),
+    }
+}
+
+// Look up the relative path in the directories listed in $CDPATH in order.
+// An empty entry or '.' means the current directory, in which case the path isn't printed.
+fn search_cdpath(path: &Path) -> Option<(PathBuf, bool)> {
+    let cdpath = env::var("CDPATH").ok()?;
+
+    cdpath.split(':').find_map(|dir| {
+        let (candidate, print) = if dir.is_empty() || dir == "." {
+            (path.to_path_buf(), false)
+        } else {
+            (Path::new(dir).join(path), true)
+        };
+
+        if candidate.is_dir() {
+            Some((candidate, print))
+        } else {
+            None
+        }
+    })
+}
+
+// Resolve '.' and '..' in the path without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            other => result.push(other.as_os_str()),
+        }
+    }
+
+    result
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Turn the error of changing directory to a message like other shells print.
+fn describe_error(path: &Path, e: &io::Error) -> String {
+    if !path.exists() {
+        String::from("No such file or directory")
+    } else if !path.is_dir() {
+        String::from("Not a directory")
+    } else if e.kind() == io::ErrorKind::PermissionDenied {
+        String::from("Permission denied")
+    } else {
+        e.to_string()
+    }
+}
@@ -0,0 +1,121 @@
+use std::{error::Error, fmt, io, rc::Rc};
+
+use crate::stream::Stream;
+
+use super::{error_stream, io_error_message, Command};
+
+// The error of a command, which is reported as a line that starts with the name of the command,
+// such as "ls: cannot open directory 'x': Permission denied", and the shell goes on.
+// The commands decide their exit status, because it's different from one command to another.
+#[derive(Debug)]
+pub enum ShellError {
+    // The file can't be used by the command, such as "cat: x: No such file or directory".
+    // The action tells what the command tried to do, such as "cannot open directory",
+    // it's put before the quoted path if it's given.
+    Io {
+        cmd: String,
+        action: Option<&'static str>,
+        path: String,
+        error: io::Error,
+    },
+
+    // The other errors, such as "grep: regex parse error".
+    Message {
+        cmd: String,
+        msg: String,
+    },
+}
+
+impl ShellError {
+    pub fn io(cmd: &str, path: &str, error: io::Error) -> Self {
+        ShellError::Io {
+            cmd: cmd.to_string(),
+            action: None,
+            path: path.to_string(),
+            error,
+        }
+    }
+
+    pub fn io_action(cmd: &str, action: &'static str, path: &str, error: io::Error) -> Self {
+        ShellError::Io {
+            cmd: cmd.to_string(),
+            action: Some(action),
+            path: path.to_string(),
+            error,
+        }
+    }
+
+    pub fn new(cmd: &str, msg: impl Into<String>) -> Self {
+        ShellError::Message {
+            cmd: cmd.to_string(),
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellError::Io {
+                cmd,
+                action: Some(action),
+                path,
+                error,
+            } => write!(
+                f,
+                "{}: {} '{}': {}",
+                cmd,
+                action,
+                path,
+                io_error_message(error)
+            ),
+            ShellError::Io {
+                cmd, path, error, ..
+            } => write!(f, "{}: {}: {}", cmd, path, io_error_message(error)),
+            ShellError::Message { cmd, msg } => write!(f, "{}: {}", cmd, msg),
+        }
+    }
+}
+
+impl Error for ShellError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShellError::Io { error, .. } => Some(error),
+            ShellError::Message { .. } => None,
+        }
+    }
+}
+
+// The command that reports the error instead of running, such as an expansion that fails,
+// like 'ru_shell: no match: *.xyz', or a command that can't be built from the AST.
+pub struct ErrorCmd {
+    error: ShellError,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl ErrorCmd {
+    pub fn new(error: ShellError) -> Self {
+        ErrorCmd {
+            error,
+            stream: None,
+            err_stream: None,
+        }
+    }
+}
+
+impl Command for ErrorCmd {
+    fn execute(&self) -> i32 {
+        error_stream(&self.err_stream, &self.stream).input(self.error.to_string());
+        1
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
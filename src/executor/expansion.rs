@@ -0,0 +1,309 @@
+use crate::lexer::{is_variable_name, quote};
+use crate::parser::ast_node_trait::{CommandAstNode, CommandType};
+use std::env;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use users::os::unix::UserExt;
+
+use crate::jobs;
+use crate::session;
+use crate::stream::console_stream::ConsoleStream;
+use crate::stream::file_stream::PipeFileStream;
+use crate::stream::Stream;
+use crate::token::token::TokenType;
+
+use super::glob;
+use super::subshell::{pipe, raw_fds};
+
+// Expand the values of the command right before it's built,
+// so that the values see the state left by the commands that ran before it, such as 'false; echo $?'.
+// The assignments are expanded one by one when they're made, see [`super::assignment`].
+// The error is returned if a pattern matches nothing while 'failglob' is set.
+pub fn expand_node(cmd: &mut Box<dyn CommandAstNode>) -> Result<(), String> {
+    let values = match cmd.get_values() {
+        Some(values) => values,
+        None => return Ok(()),
+    };
+
+    let expanded = match (cmd.cmd_type(), cmd.token_type()) {
+        // The values of the command are split into words by the results of the expansions that aren't quoted,
+        // and then the words are expanded to the paths if they're patterns.
+        (CommandType::ExtCommand, _) => {
+            let mut expanded = Vec::new();
+            for value in values.iter() {
+                for word in expand_parameters(&expand_tilde(value), true) {
+                    expanded.extend(expand_pathname(&word)?);
+                }
+            }
+            expanded
+        }
+        // The body of the here-document is kept as it is if its delimiter is quoted,
+        // otherwise it's expanded like the text in double quotes.
+        (CommandType::RedirectCommand, TokenType::DoubleLessThan) => {
+            if cmd.get_option("quoted").is_some() {
+                return Ok(());
+            }
+            values
+                .iter()
+                .map(|value| expand_word(&quote::escape_heredoc(value)))
+                .collect()
+        }
+        (CommandType::RedirectCommand, _) => {
+            values.iter().map(|value| expand_word(value)).collect()
+        }
+        _ => return Ok(()),
+    };
+
+    cmd.clear_values();
+    cmd.set_values(expanded);
+
+    Ok(())
+}
+
+// Replace the pattern with the paths that match it, see [`glob`].
+// The pattern that matches nothing is kept as it is, unless 'nullglob' or 'failglob' is set.
+fn expand_pathname(word: &str) -> Result<Vec<String>, String> {
+    if !glob::has_glob(word) {
+        return Ok(vec![quote::unescape(word)]);
+    }
+
+    let paths = glob::glob(word, session::option("dotglob"));
+    if !paths.is_empty() {
+        return Ok(paths);
+    }
+
+    if session::option("failglob") {
+        Err(format!("no match: {}", quote::unescape(word)))
+    } else if session::option("nullglob") {
+        Ok(Vec::new())
+    } else {
+        Ok(vec![quote::unescape(word)])
+    }
+}
+
+// Expand the word, whose quotes have been replaced by backslashes, see [`quote::unquote`].
+// The tilde, the variables and the command substitutions that aren't escaped are replaced with their results,
+// and then the backslashes are removed, the results aren't split into words.
+pub fn expand_word(word: &str) -> String {
+    quote::unescape(&expand_parameters(&expand_tilde(word), false).concat())
+}
+
+// Expand the value of the assignment, the tilde is also expanded after every ':', such as 'PATH=~/bin:~/.cargo/bin'.
+pub fn expand_assignment(value: &str) -> String {
+    let value: Vec<String> = value.split(':').map(expand_tilde).collect();
+    quote::unescape(&expand_parameters(&value.join(":"), false).concat())
+}
+
+// Replace the tilde at the start of the word with the directory, the directory is escaped to be literal.
+// '~'      : the home directory
+// '~user'  : the home directory of the user
+// '~+'     : the working directory ($PWD)
+// '~-'     : the previous working directory ($OLDPWD)
+// The word is kept as it is if the directory is unknown, or any char before the first '/' is quoted.
+fn expand_tilde(word: &str) -> String {
+    let rest = match word.strip_prefix('~') {
+        Some(rest) => rest,
+        None => return word.to_string(),
+    };
+    let (prefix, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if prefix.contains('\\') {
+        return word.to_string();
+    }
+
+    let dir = match prefix {
+        "" => home_dir().map(|home| home.display().to_string()),
+        "+" => env::var("PWD").ok(),
+        "-" => env::var("OLDPWD").ok(),
+        user => users::get_user_by_name(user).map(|user| user.home_dir().display().to_string()),
+    };
+
+    match dir {
+        Some(dir) => format!("{}{}", quote::escape(&dir), path),
+        None => word.to_string(),
+    }
+}
+
+// Get the home directory, $HOME is preferred over the one in the user database.
+pub fn home_dir() -> Option<PathBuf> {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() => Some(PathBuf::from(home)),
+        _ => dirs_next::home_dir(),
+    }
+}
+
+// Replace the variables and the command substitutions in the word with their results.
+// '$?'              : the exit status of the last command
+// '$NAME', '${NAME}': the value of the variable, it's empty if the variable isn't set
+// '$(...)', '`...`' : the output of the commands, without the newlines at the end
+// A '$' that isn't followed by a variable is kept as it is.
+//
+// The results in double quotes are escaped to be literal, see [`quote::unquote`].
+// The other ones are split into words by the chars in $IFS if 'split' is true, and the words can be patterns,
+// such as 'cat $(ls | grep toml)', and the word that is empty after the expansions is removed.
+// The words are returned with the backslashes, and the double quotes are removed.
+fn expand_parameters(word: &str, split: bool) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut words = Vec::new();
+    let mut cur_word = String::new();
+    // Whether the current word is kept even if it's empty, which is the case if it has any quoted part.
+    let mut keep = chars.is_empty();
+    let mut quoted = false;
+
+    let mut index = 0;
+    while index < chars.len() {
+        let (result, len) = match chars[index] {
+            '\\' => {
+                cur_word.extend(&chars[index..(index + 2).min(chars.len())]);
+                keep = true;
+                index += 2;
+                continue;
+            }
+            '"' => {
+                quoted = !quoted;
+                keep = true;
+                index += 1;
+                continue;
+            }
+            '$' | '`' => match expand_parameter(&chars[index..]) {
+                Some(expansion) => expansion,
+                None => (chars[index].to_string(), 1),
+            },
+            c => {
+                cur_word.push(c);
+                keep = true;
+                index += 1;
+                continue;
+            }
+        };
+        index += len;
+
+        if quoted || !split {
+            cur_word.push_str(&quote::escape(&result));
+            continue;
+        }
+
+        // The first field goes on the current word, and each of the other ones starts a new word.
+        let ifs = session::variable("IFS").unwrap_or(String::from(" \t\n"));
+        let mut fields = result.split(|c| ifs.contains(c));
+        if let Some(field) = fields.next() {
+            cur_word.push_str(&escape_field(field));
+        }
+        for field in fields {
+            if keep || !cur_word.is_empty() {
+                words.push(std::mem::take(&mut cur_word));
+            }
+            keep = false;
+            cur_word.push_str(&escape_field(field));
+        }
+    }
+
+    if keep || !cur_word.is_empty() {
+        words.push(cur_word);
+    }
+
+    words
+}
+
+// Expand the variable or the command substitution at the start of the chars.
+// Return the result and the number of chars it takes, None is returned if it isn't an expansion.
+fn expand_parameter(chars: &[char]) -> Option<(String, usize)> {
+    if chars[0] == '`' {
+        let end = quote::backquote_end(chars, 1)?;
+        let cmd: String = chars[1..end].iter().collect();
+        return Some((substitute_command(&unescape_backquoted(&cmd)), end + 1));
+    }
+
+    match chars.get(1) {
+        Some('(') => {
+            let end = quote::substitution_end(chars, 2)?;
+            let cmd: String = chars[2..end].iter().collect();
+            Some((substitute_command(&cmd), end + 1))
+        }
+        Some('?') => Some((session::last_status().to_string(), 2)),
+        // The name is either in braces or made of the chars that a name can have.
+        Some('{') => {
+            let end = chars.iter().position(|c| *c == '}')?;
+            let name: String = chars[2..end].iter().collect();
+            is_variable_name(&name).then(|| (session::variable(&name).unwrap_or_default(), end + 1))
+        }
+        _ => {
+            let len = chars[1..]
+                .iter()
+                .position(|c| !(c.is_ascii_alphanumeric() || *c == '_'))
+                .unwrap_or(chars.len() - 1);
+            let name: String = chars[1..1 + len].iter().collect();
+            is_variable_name(&name).then(|| (session::variable(&name).unwrap_or_default(), len + 1))
+        }
+    }
+}
+
+// Run the commands of the substitution and get their output, the newlines at the end are removed.
+// The errors aren't a part of the output, they go to stderr of the shell.
+// The exit status of the commands is kept as the last status, the same as when they run in the shell.
+fn substitute_command(cmd: &str) -> String {
+    match run_substitution(cmd) {
+        Ok((output, status)) => {
+            session::set_last_status(status);
+            String::from_utf8_lossy(&output)
+                .trim_end_matches('\n')
+                .to_string()
+        }
+        Err(e) => {
+            ConsoleStream::stderr()
+                .input(format!("ru_shell: fork: {}", super::io_error_message(&e)));
+            session::set_last_status(1);
+            String::new()
+        }
+    }
+}
+
+// Run the commands in a child process, which writes the output to a pipe, and return the output and the exit status.
+// The child is a copy of the shell, so the changes the commands make don't stay, such as '$(cd dir)' or '$(A=1)'.
+fn run_substitution(cmd: &str) -> io::Result<(Vec<u8>, i32)> {
+    let (mut reader, writer) = pipe()?;
+
+    let keep_fds = raw_fds(&[Some(&writer)]);
+    let pid = jobs::fork_subshell(&keep_fds, move || {
+        let stream: Rc<dyn Stream> = Rc::new(PipeFileStream::new(writer));
+        super::execute_with_err_stream(cmd, stream, Rc::new(ConsoleStream::stderr()))
+    })?;
+
+    // The end for writing has been dropped with the closure, so reading stops when the child exits.
+    let mut output = Vec::new();
+    let result = reader.read_to_end(&mut output);
+    let status = jobs::wait_pid(pid, 0)?;
+    result?;
+
+    Ok((output, jobs::exit_code(status)))
+}
+
+// The backslash in the backquotes only escapes '$', '`' and '\', the other ones are kept.
+fn unescape_backquoted(cmd: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = cmd.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('$' | '`' | '\\')) => unescaped.push(chars.next().unwrap()),
+            _ => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+// Escape the field of the result that isn't quoted, the chars of the patterns are kept,
+// so that the field can be expanded to the paths.
+fn escape_field(field: &str) -> String {
+    let mut escaped = String::new();
+    for c in field.chars() {
+        match c {
+            '*' | '?' | '[' | ']' => escaped.push(c),
+            _ => escaped.push_str(&quote::escape(&c.to_string())),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,351 @@
+use std::{
+    cell::Cell,
+    env,
+    io::{self, Read, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        process::{CommandExt, ExitStatusExt},
+    },
+    path::{Path, PathBuf},
+    process::{self, ChildStdin, ExitStatus, Stdio},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+use crate::jobs::{self, JobState};
+use crate::{parser::ast_node_trait::CommandAstNode, session, stream::Stream};
+
+use super::{error::ShellError, Command};
+
+// The external command runs a program that isn't a builtin command of the shell.
+// If the program contains a '/', it's used as a path directly,
+// otherwise it will be looked up in the directories listed in $PATH.
+//
+// With job control, a program that reads and writes the terminal runs in its own process group
+// which owns the terminal, so Ctrl-Z stops the program only and it's added to the job table.
+pub struct ExternalCmd {
+    // The name or the path of the program
+    program: String,
+
+    // The arguments passed to the program
+    args: Vec<String>,
+
+    // The exit status of the program, it's set after the program has finished.
+    exit_status: Cell<Option<ExitStatus>>,
+
+    stream: Option<Rc<dyn Stream>>,
+
+    // The errors of the program are written to the terminal if this stream is not set.
+    err_stream: Option<Rc<dyn Stream>>,
+
+    // The input of the program is read from the terminal if this stream is not set.
+    input_stream: Option<Rc<dyn Stream>>,
+}
+
+impl ExternalCmd {
+    fn new(program: String, args: Vec<String>) -> Self {
+        ExternalCmd {
+            program,
+            args,
+            exit_status: Cell::new(None),
+            stream: None,
+            err_stream: None,
+            input_stream: None,
+        }
+    }
+
+    // Get the exit status of the program.
+    // It's None if the program hasn't been executed or couldn't be started.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        self.exit_status.get()
+    }
+
+    // Spawn the program and wait for it to finish.
+    fn run(&self, path: &Path) -> io::Result<ExitStatus> {
+        let stream = self.stream.as_ref().unwrap();
+
+        let mut command = process::Command::new(path);
+        command.arg0(&self.program).args(&self.args);
+
+        // When the program writes to the console, it takes over the terminal directly.
+        // Otherwise, its output is connected to the stream.
+        if stream.is_console() {
+            // Print the data that other commands have written before the program takes over.
+            stream.output();
+            command.stdout(Stdio::inherit());
+        } else {
+            command.stdout(Stdio::piped());
+        }
+
+        // The input is written to the program while its output is read.
+        if self.input_stream.is_some() {
+            command.stdin(Stdio::piped());
+        } else {
+            command.stdin(Stdio::inherit());
+        }
+
+        let err_stream = self.err_stream.as_ref().filter(|s| !s.is_console());
+        if err_stream.is_some() {
+            command.stderr(Stdio::piped());
+        }
+
+        // The program can be stopped only if the shell doesn't wait for its output.
+        let job_control = session::job_control()
+            && stream.is_console()
+            && self.input_stream.is_none()
+            && err_stream.is_none();
+        unsafe {
+            command.pre_exec(move || {
+                if job_control {
+                    libc::setpgid(0, 0);
+                    libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+                }
+                jobs::reset_signals(job_control);
+                Ok(())
+            });
+        }
+
+        let mut child = command.spawn()?;
+
+        if job_control {
+            return self.wait_job(child.id() as libc::pid_t);
+        }
+
+        // The output of the program is relayed by other threads as it is,
+        // and the input is written to it by another thread one chunk at a time,
+        // so the program won't be blocked by a full pipe while we are waiting for the input or the output.
+        let (sender, receiver) = mpsc::sync_channel(RELAY_CAPACITY);
+        let mut relays = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            relays.push(relay(stdout, sender.clone(), Output::Stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            relays.push(relay(stderr, sender.clone(), Output::Stderr));
+        }
+
+        // The next chunk of the input is sent when the last one has been written,
+        // the program gets to the end of the input when the sender is dropped.
+        let mut chunks = None;
+        if let Some(stdin) = child.stdin.take() {
+            let (chunk_sender, chunk_receiver) = mpsc::sync_channel(1);
+            relays.push(write_input(stdin, chunk_receiver, sender.clone()));
+            chunks = Some(chunk_sender);
+        }
+        drop(sender);
+
+        let send_input = |chunks: &mut Option<SyncSender<Vec<u8>>>| {
+            let data = self
+                .input_stream
+                .as_ref()
+                .and_then(|input_stream| input_stream.read());
+            let sent = match (chunks.as_ref(), data) {
+                (Some(chunk_sender), Some(data)) => chunk_sender.send(data).is_ok(),
+                _ => false,
+            };
+            if !sent {
+                chunks.take();
+            }
+        };
+        send_input(&mut chunks);
+
+        // Write the output to the streams until the reader of the output stops.
+        for output in receiver.iter() {
+            match output {
+                Output::Stdout(data) => stream.write(&data),
+                Output::Stderr(data) => {
+                    if let Some(err_stream) = err_stream {
+                        err_stream.write(&data);
+                    }
+                }
+                Output::Written => send_input(&mut chunks),
+            }
+
+            if stream.is_closed() {
+                break;
+            }
+        }
+
+        // The relays stop when the receiver is dropped,
+        // then the program is terminated by SIGPIPE if it goes on writing, such as 'yes | head -1'.
+        drop(chunks);
+        drop(receiver);
+        for relay in relays {
+            let _ = relay.join();
+        }
+
+        child.wait()
+    }
+
+    // Wait for the program that runs in the foreground as a job.
+    // If it's stopped, it's added to the job table, so that it can be continued by 'fg' or 'bg'.
+    fn wait_job(&self, pid: libc::pid_t) -> io::Result<ExitStatus> {
+        // Set the process group in the shell too, so it's ready whichever process runs first.
+        unsafe {
+            libc::setpgid(pid, pid);
+        }
+
+        let status = jobs::wait_in_foreground(pid, false)?;
+        if libc::WIFSTOPPED(status) {
+            let mut command = vec![self.program.clone()];
+            command.extend(self.args.iter().cloned());
+
+            let line = session::with_jobs(|jobs| {
+                let id = jobs.add(pid, &command.join(" "), JobState::Stopped);
+                jobs.find(Some(&id.to_string()))
+                    .map(|job| job.describe(jobs.marker(id)))
+                    .unwrap_or_default()
+            });
+            // The terminal has echoed '^Z', so the job is reported in a new line.
+            self.stream.as_ref().unwrap().input(String::new());
+            self.stream.as_ref().unwrap().input(line);
+        }
+
+        Ok(ExitStatus::from_raw(status))
+    }
+}
+
+impl Command for ExternalCmd {
+    // Like other shells, the exit status is 127 if the program can't be found,
+    // 126 if it can't be executed, and 128 + N if it was terminated by the signal N.
+    fn execute(&self) -> i32 {
+        let err_stream = super::error_stream(&self.err_stream, &self.stream);
+
+        let path = match find_program(&self.program) {
+            Some(path) => path,
+            None => {
+                err_stream.input(format!("ru_shell: command not found: {}", self.program));
+                return 127;
+            }
+        };
+
+        match self.run(&path) {
+            Ok(status) => {
+                self.exit_status.set(Some(status));
+
+                // The exit code is None if the program was terminated by a signal.
+                match (status.code(), status.signal()) {
+                    (Some(code), _) => code,
+                    // The reader of the output has stopped, it's not an error to report.
+                    (None, Some(libc::SIGPIPE)) => 128 + libc::SIGPIPE,
+                    (None, Some(signal)) => {
+                        err_stream.input(format!(
+                            "ru_shell: {} terminated by signal {}",
+                            self.program, signal
+                        ));
+                        128 + signal
+                    }
+                    // The program has been stopped and added to the job table.
+                    (None, None) => status.stopped_signal().map_or(1, |signal| 128 + signal),
+                }
+            }
+            Err(e) => {
+                err_stream.input(ShellError::io("ru_shell", &self.program, e).to_string());
+                126
+            }
+        }
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.input_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for ExternalCmd {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        // The values are stored like 'argv', the first one is the program.
+        let mut values = cmd.get_values().unwrap_or_default().into_iter();
+        let program = values.next().unwrap_or_default();
+
+        ExternalCmd::new(program, values.collect())
+    }
+}
+
+// The number of chunks of the output that are relayed before the relays wait for the shell,
+// so a program that writes faster than the stream takes the data doesn't fill the memory.
+const RELAY_CAPACITY: usize = 16;
+
+// A chunk of the output of the program, which is relayed to the stream of the shell.
+enum Output {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+
+    // A chunk of the input has been written to the program, so the next one can be sent.
+    Written,
+}
+
+// Read the data from the pipe of the program and send it until the pipe is closed or the receiver is dropped.
+fn relay<R: Read + Send + 'static>(
+    mut pipe: R,
+    sender: SyncSender<Output>,
+    output: fn(Vec<u8>) -> Output,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0; 8192];
+        loop {
+            let len = match pipe.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+
+            if sender.send(output(buf[..len].to_vec())).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+// Write the chunks of the input to the program until there is no more or the program stops reading,
+// each chunk is reported when it has been written.
+fn write_input(
+    mut stdin: ChildStdin,
+    chunks: Receiver<Vec<u8>>,
+    sender: SyncSender<Output>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for chunk in chunks {
+            // The program may exit without reading all of the input, stop at the broken pipe.
+            if stdin.write_all(&chunk).is_err() || sender.send(Output::Written).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+// Find the program that will be executed.
+// A program containing '/' is a path, so it won't be looked up in $PATH.
+fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return if is_executable(&path) { Some(path) } else { None };
+    }
+
+    find_in_path(program)
+}
+
+// Look up the program in the directories listed in $PATH, the first match wins.
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+
+    env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+// Check if the path is a file that can be executed by someone.
+fn is_executable(path: &Path) -> bool {
+    match path.metadata() {
+        Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
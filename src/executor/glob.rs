@@ -0,0 +1,260 @@
+use std::fs;
+use std::path::Path;
+
+use crate::lexer::quote;
+
+// The pathname expansion, which replaces a pattern with the paths that match it.
+//
+// The pattern is a word whose quotes have been replaced by backslashes, see [`quote::unquote`],
+// so only the chars that aren't escaped are special:
+//     *     : any string, including the empty string
+//     ?     : any char
+//     [...] : any char in the brackets, such as '[abc]' and '[a-z]', '[!...]' or '[^...]' negates it
+//     **    : a whole component of the path, any files and zero or more directories
+//
+// A name starting with '.' only matches a pattern starting with '.', unless 'dotglob' is set,
+// and '.' and '..' never match a pattern.
+
+// A part of the pattern for one component of the path.
+#[derive(Debug)]
+enum Pattern {
+    Char(char),
+    Any,
+    Star,
+    Class(bool, Vec<(char, char)>),
+}
+
+// Whether the word has any char that makes it a pattern.
+pub fn has_glob(word: &str) -> bool {
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' => return true,
+            '[' if chars.clone().any(|c| c == ']') => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+// Get the paths that match the pattern, they're sorted.
+pub fn glob(pattern: &str, dotglob: bool) -> Vec<String> {
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => ("/", rest),
+        None => ("", pattern),
+    };
+    let components: Vec<&str> = rest.split('/').collect();
+
+    let mut paths = Vec::new();
+    walk(root.to_string(), &components, dotglob, &mut paths);
+    paths.sort();
+    paths.dedup();
+
+    paths
+}
+
+// Match the components one by one from the path.
+fn walk(path: String, components: &[&str], dotglob: bool, paths: &mut Vec<String>) {
+    let (component, rest) = match components.split_first() {
+        Some(split) => split,
+        None => {
+            // The path with a trailing '/' must be a directory, and a broken link is still a path.
+            let exists = match path.ends_with('/') {
+                true => fs::metadata(&path).is_ok(),
+                false => fs::symlink_metadata(&path).is_ok(),
+            };
+            if exists {
+                paths.push(path);
+            }
+            return;
+        }
+    };
+
+    // The empty component comes from a '/' at the end or two '/' in a row.
+    if component.is_empty() {
+        if !path.is_empty() && !path.ends_with('/') {
+            walk(format!("{}/", path), rest, dotglob, paths);
+        } else if rest.is_empty() {
+            walk(path, rest, dotglob, paths);
+        }
+        return;
+    }
+
+    if *component == "**" {
+        // '**' at the end matches all the files and directories below the path.
+        if rest.is_empty() {
+            for dir in subpaths(&path, dotglob, false) {
+                paths.push(dir);
+            }
+            return;
+        }
+
+        walk(path.clone(), rest, dotglob, paths);
+        for dir in subpaths(&path, dotglob, true) {
+            walk(dir, rest, dotglob, paths);
+        }
+        return;
+    }
+
+    if !has_glob(component) {
+        walk(
+            join(&path, &quote::unescape(component)),
+            rest,
+            dotglob,
+            paths,
+        );
+        return;
+    }
+
+    let pattern = compile(component);
+    let explicit_dot = component.starts_with('.') || component.starts_with("\\.");
+    for name in read_names(&path) {
+        if name == "." || name == ".." {
+            continue;
+        }
+        if name.starts_with('.') && !(explicit_dot || dotglob) {
+            continue;
+        }
+        if match_name(&pattern, &name.chars().collect::<Vec<char>>()) {
+            walk(join(&path, &name), rest, dotglob, paths);
+        }
+    }
+}
+
+// Get the paths below the path recursively, only the directories are returned if 'dirs_only' is true.
+// The links to directories are not followed, so that a loop of links can't make it endless.
+fn subpaths(path: &str, dotglob: bool, dirs_only: bool) -> Vec<String> {
+    let mut found = Vec::new();
+
+    for name in read_names(path) {
+        if name.starts_with('.') && !dotglob {
+            continue;
+        }
+
+        let subpath = join(path, &name);
+        let is_dir = fs::symlink_metadata(&subpath).is_ok_and(|metadata| metadata.is_dir());
+        if is_dir || !dirs_only {
+            found.push(subpath.clone());
+        }
+        if is_dir {
+            found.extend(subpaths(&subpath, dotglob, dirs_only));
+        }
+    }
+
+    found
+}
+
+// Get the names of the entries in the directory, the current directory is used if the path is empty.
+fn read_names(path: &str) -> Vec<String> {
+    let dir = if path.is_empty() { "." } else { path };
+
+    match fs::read_dir(Path::new(dir)) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() || path.ends_with('/') {
+        format!("{}{}", path, name)
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+// Turn the component of the pattern into the parts to match.
+fn compile(component: &str) -> Vec<Pattern> {
+    let chars: Vec<char> = component.chars().collect();
+    let mut pattern = Vec::new();
+
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => {
+                index += 1;
+                if let Some(c) = chars.get(index) {
+                    pattern.push(Pattern::Char(*c));
+                }
+            }
+            '*' => pattern.push(Pattern::Star),
+            '?' => pattern.push(Pattern::Any),
+            '[' => match compile_class(&chars[index + 1..]) {
+                Some((class, len)) => {
+                    pattern.push(class);
+                    index += len;
+                }
+                None => pattern.push(Pattern::Char('[')),
+            },
+            c => pattern.push(Pattern::Char(c)),
+        }
+        index += 1;
+    }
+
+    pattern
+}
+
+// Turn the chars after '[' into the class of chars.
+// Return the class and the number of chars it takes, including the ']'.
+// None is returned if there is no ']', then the '[' is a literal char.
+fn compile_class(chars: &[char]) -> Option<(Pattern, usize)> {
+    let mut index = 0;
+    let negated = matches!(chars.first(), Some('!') | Some('^'));
+    if negated {
+        index += 1;
+    }
+
+    let mut ranges = Vec::new();
+    // A ']' right after '[' or '[!' is a member of the class.
+    let start = index;
+    while index < chars.len() {
+        let mut c = chars[index];
+        if c == ']' && index > start {
+            return Some((Pattern::Class(negated, ranges), index + 1));
+        }
+        if c == '\\' && index + 1 < chars.len() {
+            index += 1;
+            c = chars[index];
+        }
+
+        // A range such as 'a-z', the '-' at the end is a member of the class.
+        if chars.get(index + 1) == Some(&'-') && chars.get(index + 2).is_some_and(|c| *c != ']') {
+            ranges.push((c, chars[index + 2]));
+            index += 3;
+        } else {
+            ranges.push((c, c));
+            index += 1;
+        }
+    }
+
+    None
+}
+
+// Whether the name matches the pattern, the '*' tries the shortest string first.
+fn match_name(pattern: &[Pattern], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((Pattern::Star, rest)) => (0..=name.len()).any(|skip| match_name(rest, &name[skip..])),
+        Some((part, rest)) => match name.split_first() {
+            Some((c, name)) => match_char(part, *c) && match_name(rest, name),
+            None => false,
+        },
+    }
+}
+
+fn match_char(part: &Pattern, c: char) -> bool {
+    match part {
+        Pattern::Char(expected) => *expected == c,
+        Pattern::Any => true,
+        Pattern::Class(negated, ranges) => {
+            ranges.iter().any(|(from, to)| *from <= c && c <= *to) != *negated
+        }
+        Pattern::Star => true,
+    }
+}
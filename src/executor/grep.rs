@@ -1,14 +1,17 @@
 use std::{
     fs::File,
-    io::{self, BufRead},
-    path::PathBuf,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
 use colored::Colorize;
 use regex::Regex;
 
-use crate::{executor::Command, stream};
+use crate::{
+    executor::{error::ShellError, error_stream, Command},
+    stream,
+};
 use crate::parser::ast_node_trait::CommandAstNode;
 
 /*
@@ -32,8 +35,8 @@ pub struct GrepCmd {
     // The pattern to search for in the file
     pattern: String,
 
-    // The file to search
-    file: Option<PathBuf>,
+    // The files to search, one by one
+    files: Vec<PathBuf>,
 
     // Whether to ignore case
     ignore_case: bool,
@@ -48,80 +51,37 @@ pub struct GrepCmd {
     show_line_number: bool,
 
     stream: Option<Rc<dyn stream::Stream>>,
+    err_stream: Option<Rc<dyn stream::Stream>>,
+
+    // The data is read from this stream if no file is given.
+    input_stream: Option<Rc<dyn stream::Stream>>,
 }
 
 impl GrepCmd {
-    fn new(pattern: String, file: Option<PathBuf>) -> Self {
+    fn new(pattern: String, files: Vec<PathBuf>) -> Self {
         GrepCmd {
             pattern,
-            file,
+            files,
             ignore_case: true,
             invert_match: false,
             count: true,
             show_line_number: true,
             stream: None,
+            err_stream: None,
+            input_stream: None,
         }
     }
 
-    // match the pattern string from the file
-    fn grep_from_file(&self) -> Vec<(u32, String)> {
-        // Collect the results that contain the pattern str.
-        let mut result: Vec<(u32, String)> = Vec::new();
-
-        // Open the file
-        let file = File::open(&self.file.as_ref().unwrap()).unwrap();
-        let reader = io::BufReader::new(file);
-
-        // Read the file line by line
-        let mut line_num = 1;
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
-                    self.match_line(line).map(|line: String| {
-                        result.push((line_num, line));
-                    });
-                }
-                Err(e) => panic!("Error: {}", e),
-            }
-            line_num += 1;
-        }
-
-        result
-    }
-
-    // match the pattern string from the stream
-    fn grep_from_stream(&self) -> Vec<(u32, String)> {
-        // Collect the results that contain the pattern str.
-        let mut result: Vec<(u32, String)> = Vec::new();
-
-        // Get the data from the stream
-        let data = self.stream.as_ref().unwrap().output();
-
-        let mut line_num = 1;
-        for line in data.lines() {
-            self.match_line(line.to_string()).map(|line: String| {
-                result.push((line_num, line));
-            });
-            line_num += 1;
-        }
-
-        result
-    }
-
     // Match the line with the pattern string
-    fn match_line(&self, line: String) -> Option<String> {
-        let mut pattern = self.pattern.clone();
+    // The regex is compiled once by the caller, the pattern is lowercased if the case is ignored.
+    fn match_line(&self, re: &Regex, line: String) -> Option<String> {
         let mut line_temp = line.clone();
 
         // Check if the line contains the pattern string
         if self.ignore_case {
             line_temp = line_temp.to_lowercase();
-            pattern = pattern.to_lowercase();
         }
 
-        // Create a regex pattern
-        let re = Regex::new(&pattern).unwrap();
-
         if self.invert_match {
             // If the line contains the pattern string, return None
             if re.is_match(&line_temp) {
@@ -137,9 +97,9 @@ impl GrepCmd {
             let colored_str = line[find_str.start()..find_str.end()].to_string().red();
             line_temp = format!(
                 "{}{}{}",
-                line[0..find_str.start()].to_string(),
+                &line[0..find_str.start()],
                 colored_str,
-                line[find_str.end()..line.len()].to_string()
+                &line[find_str.end()..line.len()]
             );
         }
 
@@ -147,91 +107,182 @@ impl GrepCmd {
     }
 }
 
+impl GrepCmd {
+    // Search the lines of the reader, and return the number of the matching lines.
+    // The name is put before each line if there are several files, like the grep of other systems.
+    // It stops early if the reader of the stream has stopped, such as 'grep x big.log | head -1'.
+    fn search(
+        &self,
+        re: &Regex,
+        reader: &mut dyn BufRead,
+        name: Option<&str>,
+    ) -> io::Result<usize> {
+        let stream = self.stream.as_ref().unwrap();
+        let prefix = name.map(|name| format!("{}:", name)).unwrap_or_default();
+
+        // Only '\n' ends a line, so the '\r' of a CRLF line break stays in the matching line,
+        // and the bytes that aren't valid UTF-8 are replaced.
+        let lines = reader
+            .split(b'\n')
+            .map(|line| line.map(|line| String::from_utf8_lossy(&line).into_owned()));
+
+        let mut count = 0;
+        for (line_num, line) in (1..).zip(lines) {
+            let line = match self.match_line(re, line?) {
+                Some(line) => line,
+                None => continue,
+            };
+            count += 1;
+
+            if self.count {
+                continue;
+            }
+            if self.show_line_number {
+                // If the -n option is specified, display the line number before each matching line
+                stream.input(format!("{}{}: {}", prefix, line_num, line));
+            } else {
+                stream.input(format!("{}{}", prefix, line));
+            }
+            if stream.is_closed() {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    // Search the file, the error tells which file can't be read.
+    fn search_file(
+        &self,
+        re: &Regex,
+        file: &Path,
+        name: Option<&str>,
+    ) -> Result<usize, ShellError> {
+        let error = |e| ShellError::io("grep", &file.display().to_string(), e);
+
+        let f = File::open(file).map_err(error)?;
+        self.search(re, &mut BufReader::new(f), name).map_err(error)
+    }
+}
+
 impl Command for GrepCmd {
-    fn execute(&self) {
-        let results = if self.stream.as_ref().unwrap().is_empty() {
-            self.grep_from_file()
+    // Like the grep of other systems, the exit status is 0 if any line is matched,
+    // 1 if no line is matched, and 2 if an error occurred, even if some lines are matched.
+    // The files after the one that can't be read are still searched.
+    fn execute(&self) -> i32 {
+        let stream = self.stream.as_ref().unwrap();
+        let err_stream = error_stream(&self.err_stream, &self.stream);
+
+        let pattern = if self.ignore_case {
+            self.pattern.to_lowercase()
         } else {
-            self.grep_from_stream()
+            self.pattern.clone()
+        };
+        let re = match Regex::new(&pattern) {
+            Ok(re) => re,
+            Err(e) => {
+                err_stream.input(ShellError::new("grep", e.to_string()).to_string());
+                return 2;
+            }
         };
 
-        if self.count {
-            self.stream.as_ref().unwrap().input(format!(
-                "{}: {}",
-                self.file.as_ref().unwrap().display(),
-                results.len()
-            ));
-        } else {
-            if self.show_line_number {
-                // If the -n option is specified, display the line number before each matching line
-                for (line_num, line) in results {
-                    self.stream
-                        .as_ref()
-                        .unwrap()
-                        .input(format!("{}: {}", line_num, line));
+        // The data is read from the input stream if no file is given.
+        if self.files.is_empty() {
+            let mut reader: Box<dyn BufRead + '_> = match &self.input_stream {
+                Some(input_stream) => {
+                    Box::new(BufReader::new(stream::reader(input_stream.as_ref())))
                 }
+                None => Box::new(io::empty()),
+            };
+
+            let count = match self.search(&re, &mut reader, None) {
+                Ok(count) => count,
+                Err(e) => {
+                    err_stream.input(ShellError::io("grep", "-", e).to_string());
+                    return 2;
+                }
+            };
+            if self.count {
+                stream.input(count.to_string());
+            }
+
+            return if count > 0 { 0 } else { 1 };
+        }
+
+        let mut matched = false;
+        let mut failed = false;
+        for file in self.files.iter() {
+            let name = file.display().to_string();
+            let prefix = if self.files.len() > 1 {
+                Some(name.as_str())
             } else {
-                for (_, line) in results {
-                    self.stream.as_ref().unwrap().input(format!("{}", line));
+                None
+            };
+
+            match self.search_file(&re, file, prefix) {
+                Ok(count) => {
+                    matched |= count > 0;
+                    if self.count {
+                        // The file name is shown only if the data is read from a file.
+                        stream.input(format!("{}: {}", name, count));
+                    }
+                }
+                Err(e) => {
+                    err_stream.input(e.to_string());
+                    failed = true;
                 }
             }
+
+            if stream.is_closed() {
+                break;
+            }
+        }
+
+        if failed {
+            2
+        } else if matched {
+            0
+        } else {
+            1
         }
     }
 
     fn add_stream(&mut self, stream: Rc<dyn stream::Stream>) {
         self.stream = Some(stream);
     }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn stream::Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn stream::Stream>) {
+        self.input_stream = Some(stream);
+    }
 }
 
 impl From<Box<dyn CommandAstNode>> for GrepCmd {
     fn from(cmd: Box<dyn CommandAstNode>) -> Self {
         // Get values
-        let values = cmd.get_values().unwrap_or_else(|| Vec::new());
+        let values = cmd.get_values().unwrap_or_default();
 
         // Get pattern
-        let pattern = match values.get(0) {
+        let pattern = match values.first() {
             Some(pattern) => pattern.clone(),
             None => String::new(),
         };
 
-        // Get file
-        let file = match values.get(1) {
-            Some(values) => values,
-            None => "",
-        };
-
-        let mut grep_cmd = if file.is_empty() {
-            GrepCmd::new(pattern, None)
-        } else {
-            // Check if file exists
-            let file_buf = PathBuf::from(file);
-            if file_buf.exists() == false {
-                panic!("File {} does not exist", file_buf.display());
-            }
-
-            GrepCmd::new(pattern, Some(file_buf))
-        };
+        // Get files, they're checked when the command is executed.
+        let files = values.iter().skip(1).map(PathBuf::from).collect();
+        let mut grep_cmd = GrepCmd::new(pattern, files);
 
         // Get options
-        grep_cmd.ignore_case = match cmd.get_option("-i").or(cmd.get_option("--ignore-case")) {
-            Some(_) => true,
-            None => false,
-        };
+        grep_cmd.ignore_case = cmd.get_option("-i").or(cmd.get_option("--ignore-case")).is_some();
 
-        grep_cmd.invert_match = match cmd.get_option("-v").or(cmd.get_option("--invert-match")) {
-            Some(_) => true,
-            None => false,
-        };
+        grep_cmd.invert_match = cmd.get_option("-v").or(cmd.get_option("--invert-match")).is_some();
 
-        grep_cmd.count = match cmd.get_option("-c").or(cmd.get_option("--count")) {
-            Some(_) => true,
-            None => false,
-        };
+        grep_cmd.count = cmd.get_option("-c").or(cmd.get_option("--count")).is_some();
 
-        grep_cmd.show_line_number = match cmd.get_option("-n").or(cmd.get_option("--line-number")) {
-            Some(_) => true,
-            None => false,
-        };
+        grep_cmd.show_line_number = cmd.get_option("-n").or(cmd.get_option("--line-number")).is_some();
 
         grep_cmd
     }
@@ -0,0 +1,64 @@
+use std::rc::Rc;
+
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::{analyze_node, Command};
+
+// The group operator runs the commands in braces as one command, such as '{ ls; cat f; } | grep x',
+// so all of them write to the same stream and read from the same input.
+// Unlike the subshell, they run in the shell, so the changes they make stay, such as '{ cd dir; }'.
+pub struct GroupOperator {
+    // The commands in the braces.
+    // They're built when they are about to run, like the commands of a list, see [`super::list`].
+    source: Box<dyn CommandAstNode>,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+    input_stream: Option<Rc<dyn Stream>>,
+}
+
+impl GroupOperator {
+    fn new(source: Box<dyn CommandAstNode>) -> Self {
+        Self {
+            source,
+            stream: None,
+            err_stream: None,
+            input_stream: None,
+        }
+    }
+}
+
+impl Command for GroupOperator {
+    // The exit status of the group is the one of the last command in it.
+    fn execute(&self) -> i32 {
+        let mut cmd = analyze_node(self.source.clone());
+
+        cmd.add_stream(self.stream.clone().unwrap());
+        if let Some(err_stream) = &self.err_stream {
+            cmd.add_err_stream(err_stream.clone());
+        }
+        if let Some(input_stream) = &self.input_stream {
+            cmd.add_input_stream(input_stream.clone());
+        }
+
+        cmd.execute()
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.input_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for GroupOperator {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        Self::new(cmd.get_source().unwrap())
+    }
+}
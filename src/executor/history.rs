@@ -0,0 +1,122 @@
+use std::rc::Rc;
+
+use crate::session;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::error::ShellError;
+use super::{error_stream, Command};
+
+// The history command shows or changes the history of the command lines, see [`crate::history`].
+//     history            : show all lines with their numbers
+//     history n          : show the last n lines
+//     history -c         : clear the history
+//     history -d n       : delete the line n
+pub struct HistoryCmd {
+    clear: bool,
+    delete: Option<String>,
+    args: Vec<String>,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl HistoryCmd {
+    fn new(clear: bool, delete: Option<String>, args: Vec<String>) -> Self {
+        HistoryCmd {
+            clear,
+            delete,
+            args,
+            stream: None,
+            err_stream: None,
+        }
+    }
+
+    fn print(&self, msg: String) {
+        self.stream.as_ref().unwrap().input(msg);
+    }
+
+    fn print_error(&self, error: ShellError) {
+        error_stream(&self.err_stream, &self.stream).input(error.to_string());
+    }
+
+    fn show(&self) -> i32 {
+        let count = match self.args.first() {
+            Some(arg) => match arg.parse::<usize>() {
+                Ok(count) => Some(count),
+                Err(_) => {
+                    self.print_error(ShellError::new(
+                        "history",
+                        format!("{}: numeric argument required", arg),
+                    ));
+                    return 1;
+                }
+            },
+            None => None,
+        };
+        if self.args.len() > 1 {
+            self.print_error(ShellError::new("history", "too many arguments"));
+            return 1;
+        }
+
+        let lines = session::with_history(|history| history.lines().to_vec());
+        let skip = count.map_or(0, |count| lines.len().saturating_sub(count));
+        for (index, line) in lines.iter().enumerate().skip(skip) {
+            self.print(format!("{:>5}  {}", index + 1, line));
+        }
+
+        0
+    }
+}
+
+impl Command for HistoryCmd {
+    fn execute(&self) -> i32 {
+        if self.clear {
+            return match session::with_history(|history| history.clear()) {
+                Ok(_) => 0,
+                Err(e) => {
+                    self.print_error(ShellError::new("history", e.to_string()));
+                    1
+                }
+            };
+        }
+
+        if let Some(position) = &self.delete {
+            let result = match position.parse::<usize>() {
+                Ok(number) => session::with_history(|history| history.delete(number)),
+                Err(_) if position.is_empty() => {
+                    Err(String::from("-d: option requires an argument"))
+                }
+                Err(_) => Err(format!("{}: history position out of range", position)),
+            };
+            return match result {
+                Ok(_) => 0,
+                Err(msg) => {
+                    self.print_error(ShellError::new("history", msg));
+                    1
+                }
+            };
+        }
+
+        self.show()
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for HistoryCmd {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        let clear = cmd.get_option("-c").or(cmd.get_option("--clear")).is_some();
+        let delete = cmd
+            .get_option("-d")
+            .or(cmd.get_option("--delete"))
+            .map(|position| position.to_string());
+
+        HistoryCmd::new(clear, delete, cmd.get_values().unwrap_or_default())
+    }
+}
@@ -0,0 +1,268 @@
+use std::rc::Rc;
+
+use crate::jobs::{self, Job, JobState};
+use crate::token::token::TokenType;
+use crate::{parser::ast_node_trait::CommandAstNode, session, stream::Stream};
+
+use super::{error_stream, Command};
+
+// The job control commands manage the jobs in the job table.
+//
+// These are the commands and the values they support:
+//     jobs                : list the jobs, the finished ones are removed after being listed
+//     fg [job]            : continue the job in the foreground
+//     bg [job]            : continue the stopped job in the background
+//     wait [job|pid ...]  : wait for the jobs to finish, all jobs are waited for if none is given
+//     disown [-a] [job]   : remove the job from the job table, '-a' removes all jobs
+//
+// A job is given as a job spec, such as '%1', '%+' or '%-', the current job is used if it's not given.
+pub struct JobCmd {
+    // Which job control command it is.
+    kind: TokenType,
+
+    // The job specs, or the pids for 'wait'.
+    args: Vec<String>,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl JobCmd {
+    fn new(kind: TokenType, args: Vec<String>) -> Self {
+        JobCmd {
+            kind,
+            args,
+            stream: None,
+            err_stream: None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            TokenType::Jobs => "jobs",
+            TokenType::Fg => "fg",
+            TokenType::Bg => "bg",
+            TokenType::Wait => "wait",
+            _ => "disown",
+        }
+    }
+
+    fn print(&self, msg: String) {
+        self.stream.as_ref().unwrap().input(msg);
+    }
+
+    // Report the error and return the exit status.
+    fn report(&self, msg: String, status: i32) -> i32 {
+        error_stream(&self.err_stream, &self.stream).input(format!("{}: {}", self.name(), msg));
+        status
+    }
+
+    // Find the job by the first value, the current job is used if there is no value.
+    fn find_job(&self) -> Result<Job, String> {
+        session::with_jobs(|jobs| {
+            jobs.update();
+            jobs.find(self.args.first().map(|arg| arg.as_str()))
+        })
+    }
+
+    fn jobs(&self) -> i32 {
+        let lines: Vec<String> = session::with_jobs(|jobs| {
+            jobs.update();
+            let lines = jobs
+                .jobs()
+                .iter()
+                .map(|job| job.describe(jobs.marker(job.id)))
+                .collect();
+            jobs.take_done();
+
+            lines
+        });
+
+        for line in lines {
+            self.print(line);
+        }
+
+        0
+    }
+
+    fn fg(&self) -> i32 {
+        let job = match self.find_job() {
+            Ok(job) => job,
+            Err(msg) => return self.report(msg, 1),
+        };
+
+        // Print the command before the job takes over the terminal.
+        let stream = self.stream.as_ref().unwrap();
+        stream.input(job.command.clone());
+        if stream.is_console() {
+            stream.output();
+        }
+
+        if let JobState::Done(status) = job.state {
+            session::with_jobs(|jobs| jobs.remove(job.id));
+            return status;
+        }
+
+        session::with_jobs(|jobs| jobs.set_state(job.id, JobState::Running));
+        self.wait_job(&job, jobs::wait_in_foreground(job.pgid, true))
+    }
+
+    fn bg(&self) -> i32 {
+        let job = match self.find_job() {
+            Ok(job) => job,
+            Err(msg) => return self.report(msg, 1),
+        };
+
+        if job.state != JobState::Stopped {
+            return self.report(format!("job {} already in background", job.id), 0);
+        }
+
+        unsafe {
+            libc::kill(-job.pgid, libc::SIGCONT);
+        }
+
+        let line = session::with_jobs(|jobs| {
+            jobs.set_state(job.id, JobState::Running);
+            format!("[{}]{} {} &", job.id, jobs.marker(job.id), job.command)
+        });
+        self.print(line);
+
+        0
+    }
+
+    fn wait(&self) -> i32 {
+        // Without values, all running jobs are waited for, and the exit status is 0.
+        if self.args.is_empty() {
+            let running: Vec<Job> = session::with_jobs(|jobs| {
+                jobs.update();
+                jobs.jobs()
+                    .iter()
+                    .filter(|job| job.state == JobState::Running)
+                    .cloned()
+                    .collect()
+            });
+            for job in running {
+                self.wait_job(&job, jobs::wait_pid(job.pgid, libc::WUNTRACED));
+            }
+            session::with_jobs(|jobs| jobs.take_done());
+
+            return 0;
+        }
+
+        // Otherwise, the exit status is the one of the last job.
+        let mut status = 0;
+        for arg in self.args.iter() {
+            let job = if arg.starts_with('%') {
+                session::with_jobs(|jobs| {
+                    jobs.update();
+                    jobs.find(Some(arg))
+                })
+            } else {
+                match arg.parse::<libc::pid_t>() {
+                    Ok(pid) => session::with_jobs(|jobs| {
+                        jobs.update();
+                        jobs.find_by_pid(pid)
+                    })
+                    .ok_or_else(|| format!("pid {} is not a child of this shell", pid)),
+                    Err(_) => Err(format!("`{}': not a pid or valid job spec", arg)),
+                }
+            };
+
+            status = match job {
+                Ok(Job {
+                    id,
+                    state: JobState::Done(status),
+                    ..
+                }) => {
+                    session::with_jobs(|jobs| jobs.remove(id));
+                    status
+                }
+                Ok(job) => self.wait_job(&job, jobs::wait_pid(job.pgid, libc::WUNTRACED)),
+                Err(msg) => self.report(msg, 127),
+            };
+        }
+
+        status
+    }
+
+    fn disown(&self) -> i32 {
+        if self.args.iter().any(|arg| arg == "-a") {
+            session::with_jobs(|jobs| jobs.clear());
+            return 0;
+        }
+
+        let specs: Vec<Option<&str>> = if self.args.is_empty() {
+            vec![None]
+        } else {
+            self.args.iter().map(|arg| Some(arg.as_str())).collect()
+        };
+
+        let mut status = 0;
+        for spec in specs {
+            let result = session::with_jobs(|jobs| {
+                let job = jobs.find(spec)?;
+                jobs.remove(job.id);
+                Ok::<(), String>(())
+            });
+            if let Err(msg) = result {
+                status = self.report(msg, 1);
+            }
+        }
+
+        status
+    }
+
+    // Update the job table by the result of waiting for the job, and return the exit status.
+    // The job is kept if it has been stopped, otherwise it's removed.
+    fn wait_job(&self, job: &Job, result: std::io::Result<libc::c_int>) -> i32 {
+        match result {
+            Ok(status) if libc::WIFSTOPPED(status) => {
+                let line = session::with_jobs(|jobs| {
+                    jobs.set_state(job.id, JobState::Stopped);
+                    jobs.find(Some(&job.id.to_string()))
+                        .map(|job| job.describe(jobs.marker(job.id)))
+                        .unwrap_or_default()
+                });
+                // The terminal has echoed '^Z', so the job is reported in a new line.
+                self.print(String::new());
+                self.print(line);
+
+                jobs::exit_code(status)
+            }
+            Ok(status) => {
+                session::with_jobs(|jobs| jobs.remove(job.id));
+                jobs::exit_code(status)
+            }
+            Err(e) => {
+                session::with_jobs(|jobs| jobs.remove(job.id));
+                self.report(e.to_string(), 1)
+            }
+        }
+    }
+}
+
+impl Command for JobCmd {
+    fn execute(&self) -> i32 {
+        match self.kind {
+            TokenType::Jobs => self.jobs(),
+            TokenType::Fg => self.fg(),
+            TokenType::Bg => self.bg(),
+            TokenType::Wait => self.wait(),
+            _ => self.disown(),
+        }
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for JobCmd {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        JobCmd::new(cmd.token_type().clone(), cmd.get_values().unwrap_or_default())
+    }
+}
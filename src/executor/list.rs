@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use crate::session;
+use crate::token::token::TokenType;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::{analyze_node, Command};
+
+// The list operator runs the destination command after the source command.
+// 'a && b' : b runs only if a succeeded.
+// 'a || b' : b runs only if a failed.
+// 'a ; b'  : b always runs.
+//
+// The commands are built from the AST when they are about to run,
+// because the source command may change what the destination command sees, such as 'cd dir && ls'.
+pub struct ListOperator {
+    // The operator, which is '&&', '||' or ';'.
+    operator: TokenType,
+
+    source: Box<dyn CommandAstNode>,
+    destination: Box<dyn CommandAstNode>,
+
+    // Both commands write to the same streams.
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+
+    // The input is read by the source command.
+    input_stream: Option<Rc<dyn Stream>>,
+}
+
+impl ListOperator {
+    fn new(
+        operator: TokenType,
+        source: Box<dyn CommandAstNode>,
+        destination: Box<dyn CommandAstNode>,
+    ) -> Self {
+        Self {
+            operator,
+            source,
+            destination,
+            stream: None,
+            err_stream: None,
+            input_stream: None,
+        }
+    }
+
+    // Build the command from the AST and run it with the streams of the operator.
+    // The exit status is saved before the next command is built, so that it can read '$?'.
+    fn run(&self, node: &dyn CommandAstNode, input_stream: Option<Rc<dyn Stream>>) -> i32 {
+        let mut cmd = analyze_node(node.clone_cmd());
+
+        cmd.add_stream(self.stream.clone().unwrap());
+        if let Some(err_stream) = &self.err_stream {
+            cmd.add_err_stream(err_stream.clone());
+        }
+        if let Some(input_stream) = input_stream {
+            cmd.add_input_stream(input_stream);
+        }
+
+        let status = cmd.execute();
+        session::set_last_status(status);
+
+        status
+    }
+}
+
+impl Command for ListOperator {
+    // The exit status of the list is the one of the last command that ran.
+    fn execute(&self) -> i32 {
+        let status = self.run(self.source.as_ref(), self.input_stream.clone());
+
+        let run_destination = match self.operator {
+            TokenType::And => status == 0,
+            TokenType::Or => status != 0,
+            _ => true,
+        };
+
+        if run_destination {
+            self.run(self.destination.as_ref(), None)
+        } else {
+            status
+        }
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.input_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for ListOperator {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        Self::new(
+            cmd.token_type().clone(),
+            cmd.get_source().unwrap(),
+            cmd.get_destination().unwrap(),
+        )
+    }
+}
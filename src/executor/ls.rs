@@ -3,7 +3,7 @@ use std::{
     fmt::Debug,
     fs,
     os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
@@ -11,7 +11,7 @@ use colored::{ColoredString, Colorize};
 
 use crate::parser::ast_node_trait::CommandAstNode;
 use crate::{
-    executor::Command,
+    executor::{error::ShellError, error_stream, Command},
     stream::{self, Stream},
 };
 
@@ -85,6 +85,7 @@ pub struct LsCmd {
     status: u8,
 
     stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
 }
 
 impl LsCmd {
@@ -101,6 +102,7 @@ impl LsCmd {
             paths: Vec::new(),
             status: 0,
             stream: None,
+            err_stream: None,
         }
     }
 
@@ -131,7 +133,7 @@ impl LsCmd {
 
     // If don't get any option or use other options that don't define,
     // just show non-hidden files name.
-    fn show_names(&self, files: &Vec<FileInfo>) {
+    fn show_names(&self, files: &[FileInfo]) {
         let mut files_str = String::new();
 
         for file in files.iter() {
@@ -139,7 +141,7 @@ impl LsCmd {
                 continue;
             }
 
-            files_str.push_str(format!("{:<20}", self.color_file_names(&file)).as_str());
+            files_str.push_str(format!("{:<20}", self.color_file_names(file)).as_str());
         }
 
         self.stream
@@ -149,7 +151,7 @@ impl LsCmd {
     }
 
     // Show details of files and directories
-    fn show_infos(&self, files: &Vec<FileInfo>) {
+    fn show_infos(&self, files: &[FileInfo]) {
         for file in files.iter() {
             if !self.all && file.is_hidden {
                 continue;
@@ -161,7 +163,7 @@ impl LsCmd {
                 file.size.to_string()
             };
 
-            let file_name_with_color = self.color_file_names(&file);
+            let file_name_with_color = self.color_file_names(file);
 
             self.stream.as_ref().unwrap().input(format!(
                 "{} {:>3} {:>8} {:>8} {:>8} {:>20} {}",
@@ -199,7 +201,18 @@ impl LsCmd {
         }
 
         // Get file info.
-        let file_info = self.get_file_info(path);
+        let file_info = match self.get_file_info(path) {
+            Ok(file_info) => file_info,
+            Err(e) => {
+                self.stream.as_ref().unwrap().input(format!(
+                    "{:indent$}| - {}",
+                    "",
+                    e.to_string().red(),
+                    indent = (depth * 5) as usize
+                ));
+                return;
+            }
+        };
 
         // Get file name with color.
         let file_name_with_color = self.color_file_names(&file_info);
@@ -226,9 +239,9 @@ impl LsCmd {
                     return;
                 }
             };
-            for path in paths {
-                let path = path.unwrap().path();
-                self.show_as_tree_recursively(&path, depth + 1);
+            // The entries that can't be read, such as the ones removed while listing, are skipped.
+            for path in paths.flatten() {
+                self.show_as_tree_recursively(&path.path(), depth + 1);
             }
         }
     }
@@ -280,26 +293,21 @@ impl LsCmd {
 
     // Get file info, such as file size, modified time, etc.
     #[cfg(unix)]
-    fn get_file_info(&self, path_buf: &std::path::PathBuf) -> FileInfo {
+    fn get_file_info(&self, path_buf: &Path) -> Result<FileInfo, ShellError> {
         // Get file metadata, include file size, modified time, etc.
         use chrono::{DateTime, Local};
-        let metadata = match fs::symlink_metadata(path_buf) {
-            Ok(metadata) => metadata,
-            Err(_) => path_buf.metadata().unwrap(),
-        };
+        let metadata = fs::symlink_metadata(path_buf).map_err(|e| {
+            ShellError::io_action("ls", "cannot access", &path_buf.display().to_string(), e)
+        })?;
 
         // Get file basic info include: permissions, type, name and is not hidden.
         let (permission, file_type) = self.analysis_mode(&metadata);
 
         // Get file name and judge if it is hidden.
+        // The path is shown as it is if it has no file name, such as '/' or '..'.
         let file_name = match path_buf.file_name() {
-            Some(file_name) => {
-                let file_name = file_name.to_string_lossy().into_owned();
-                file_name
-            }
-            None => {
-                panic!("{}", format!("Error: Can't get file name").red());
-            }
+            Some(file_name) => file_name.to_string_lossy().into_owned(),
+            None => path_buf.display().to_string(),
         };
         let is_hidden: bool = file_name.starts_with(".");
 
@@ -307,16 +315,22 @@ impl LsCmd {
         let link_num = metadata.nlink();
 
         // Get modified time of file.
-        let modify_time: DateTime<Local> = metadata.modified().unwrap().into();
-        let modify_time = modify_time.format("%Y-%m-%d %H:%M:%S").to_string();
+        // It's left empty if the system doesn't keep it.
+        let modify_time = match metadata.modified() {
+            Ok(modify_time) => {
+                let modify_time: DateTime<Local> = modify_time.into();
+                modify_time.format("%Y-%m-%d %H:%M:%S").to_string()
+            }
+            Err(_) => String::new(),
+        };
 
         // Get owner and group name.
         let (owner_name, group_name) = self.get_owner_and_group_name(&metadata, &file_type);
 
         // Store these infos to FileInfo struct and add it to vec.
-        let fi = FileInfo {
+        Ok(FileInfo {
             permissions: permission,
-            file_type: file_type,
+            file_type,
             link: link_num,
             owner: owner_name,
             group: group_name,
@@ -324,9 +338,7 @@ impl LsCmd {
             modified_time: modify_time,
             name: file_name,
             is_hidden,
-        };
-
-        fi
+        })
     }
 
     // Get owner and group name.
@@ -341,8 +353,6 @@ impl LsCmd {
         use libc::getgrgid;
         use users::{get_group_by_gid, get_user_by_uid};
 
-        let group_name: String;
-
         let uid = metadata.uid();
         let gid = metadata.gid();
 
@@ -351,29 +361,29 @@ impl LsCmd {
         // Because The method in the 'user crate' for converting a gid to a group name
         // can cause the program to panic due to memory alignment issues.
         // So it is necessary to use libc to call the C language implementation to accomplish this functionality.
-        if file_type != &FileType::File
+        let group_name = if file_type != &FileType::File
             || file_type != &FileType::Dir
             || file_type != &FileType::Link
         {
             // 获取用户组名
             let group_info = unsafe { getgrgid(gid) };
-            group_name = if !group_info.is_null() {
+            if !group_info.is_null() {
                 let group_name_cstr = unsafe { CStr::from_ptr((*group_info).gr_name) };
                 group_name_cstr.to_string_lossy().into_owned()
             } else {
                 "".to_string()
             }
         } else {
-            group_name = get_group_by_gid(gid)
+            get_group_by_gid(gid)
                 .map(|g| g.name().to_string_lossy().into_owned())
-                .unwrap_or_else(|| "Unknown".to_string());
-        }
+                .unwrap_or_else(|| "Unknown".to_string())
+        };
 
         let owner_name = get_user_by_uid(uid)
             .map(|u| u.name().to_string_lossy().into_owned())
             .unwrap_or_else(|| "Unknown".to_string());
 
-        return (owner_name, group_name);
+        (owner_name, group_name)
     }
 
     #[cfg(unix)]
@@ -412,53 +422,66 @@ impl LsCmd {
         let mut result = String::from("");
 
         if num & 4 == 4 {
-            result.push_str("r");
+            result.push('r');
         } else {
-            result.push_str("-");
+            result.push('-');
         }
 
         if num & 2 == 2 {
-            result.push_str("w");
+            result.push('w');
         } else {
-            result.push_str("-");
+            result.push('-');
         }
 
         if num & 1 == 1 {
-            result.push_str("x");
+            result.push('x');
         } else {
-            result.push_str("-");
+            result.push('-');
         }
 
         result
     }
 
     // Just print files and dirs name in the path
+    // The entries of the directory that can't be read are put into the errors, the others are still listed.
     #[cfg(unix)]
-    fn get_files_and_dirs(&self, path_buf: &PathBuf, files: &mut Vec<FileInfo>) {
+    fn get_files_and_dirs(
+        &self,
+        path_buf: &Path,
+        files: &mut Vec<FileInfo>,
+        errors: &mut Vec<ShellError>,
+    ) -> Result<(), ShellError> {
         // Check if the path is a file.
         if !path_buf.is_dir() {
-            // If it is a file, just get file info and return.
-            files.push(self.get_file_info(path_buf));
-            return;
+            // If it is a file, just get file info and return, a broken symbolic link is still listed.
+            files.push(self.get_file_info(path_buf)?);
+            return Ok(());
         } else {
             // If it is a directory, get all files and directories in it.
             // And store them to the vec.
-            let paths = match fs::read_dir(path_buf) {
-                Ok(paths) => paths,
-                Err(_) => {
-                    let msg = format!("Error: Permission denied").red();
-                    panic!("{}", msg);
-                }
-            };
+            let paths = fs::read_dir(path_buf).map_err(|e| {
+                let path = path_buf.display().to_string();
+                ShellError::io_action("ls", "cannot open directory", &path, e)
+            })?;
             for path in paths {
-                let path = path.unwrap().path();
-                files.push(self.get_file_info(&path));
+                let path = match path {
+                    Ok(path) => path.path(),
+                    Err(e) => {
+                        let path = path_buf.display().to_string();
+                        errors.push(ShellError::io_action("ls", "reading directory", &path, e));
+                        continue;
+                    }
+                };
+                match self.get_file_info(&path) {
+                    Ok(file_info) => files.push(file_info),
+                    Err(e) => errors.push(e),
+                }
             }
         }
 
         // Sort by option
         if self.sort_by_size {
-            files.sort_by(|f1, f2| f1.size.cmp(&f2.size));
+            files.sort_by_key(|f1| f1.size);
         } else if self.sort_by_time {
             files.sort_by(|f1, f2: &FileInfo| f1.modified_time.cmp(&f2.modified_time));
         } else {
@@ -469,6 +492,8 @@ impl LsCmd {
         if self.reverse {
             files.reverse();
         }
+
+        Ok(())
     }
 }
 
@@ -547,21 +572,45 @@ impl From<Box<dyn CommandAstNode>> for LsCmd {
 }
 
 impl Command for LsCmd {
-    fn execute(&self) {
+    // Like the ls of other systems, the exit status is 2 if any path can't be listed,
+    // the other paths are still listed.
+    fn execute(&self) -> i32 {
+        let mut status = 0;
+
+        let err_stream = error_stream(&self.err_stream, &self.stream);
+
         self.paths.iter().for_each(|path| {
             let mut files = Vec::new();
-            self.get_files_and_dirs(path, &mut files);
+            let mut errors = Vec::new();
+            let result = self.get_files_and_dirs(path, &mut files, &mut errors);
+
+            // The entries that can't be read are reported, and the others are still listed.
+            for e in errors {
+                err_stream.input(e.to_string());
+                status = 1;
+            }
+            if let Err(e) = result {
+                err_stream.input(e.to_string());
+                status = 2;
+                return;
+            }
 
-            let _v = match self.status {
+            match self.status {
                 0 | 2 | 4 => self.show_names(&files),
                 1 | 3 | 5 | 7 => self.show_infos(&files),
                 8 => self.show_as_tree(path),
                 _ => self.show_names(&files),
             };
         });
+
+        status
     }
 
     fn add_stream(&mut self, stream: Rc<dyn stream::Stream>) {
         self.stream = Some(stream);
     }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn stream::Stream>) {
+        self.err_stream = Some(stream);
+    }
 }
@@ -1,76 +1,181 @@
-use std::rc::Rc;
+use std::{io, rc::Rc};
 
+use crate::builtin;
 use crate::parser::ast_node_trait::{CommandAstNode, CommandType};
 use crate::parser::Parser;
-use crate::stream::Stream;
+use crate::session;
+use crate::stream::{console_stream::ConsoleStream, Stream};
 use crate::token::token::TokenType;
 
-use self::cat::CatCmd;
-use self::grep::GrepCmd;
-use self::ls::LsCmd;
+use self::assignment::AssignmentOperator;
+use self::background::BackgroundOperator;
+use self::error::{ErrorCmd, ShellError};
+use self::external::ExternalCmd;
+use self::group::GroupOperator;
+use self::list::ListOperator;
 use self::pipeline::PipelineOperator;
+use self::redirect::RedirectOperator;
+use self::subshell::SubshellOperator;
 
+pub mod assignment;
+pub mod background;
 pub mod cat;
+pub mod cd;
+pub mod error;
+pub mod expansion;
+pub mod external;
+pub mod glob;
 pub mod grep;
+pub mod group;
+pub mod history;
+pub mod job;
+pub mod list;
 pub mod ls;
 pub mod pipeline;
+pub mod redirect;
+pub mod shopt;
+pub mod subshell;
+pub mod variable;
 
 // Every commands that implement this trait has a 'status' field to represent
 // the status of the command after it has been parsed.
 // The value of status is derived from a combination of one or more options,
 // indication hao the command should be executed.
 pub trait Command {
-    // Execute command
-    fn execute(&self);
+    // Execute command, and return the exit status.
+    // Like other shells, 0 means success, and the other values mean failure.
+    fn execute(&self) -> i32;
 
     // Add stream to the command
     fn add_stream(&mut self, stream: Rc<dyn Stream>);
+
+    // Add the stream for error messages to the command, it's kept apart from the output,
+    // so that the errors don't flow into the next command of a pipeline, such as 'ls missing | grep x'.
+    // The commands write the errors to the stream added by [`add_stream`] if it hasn't been added.
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>);
+
+    // Add the stream that provides the input data to the command,
+    // such as the output of the previous command in a pipeline or a redirected file.
+    // Only the commands that read input need to implement it.
+    fn add_input_stream(&mut self, _stream: Rc<dyn Stream>) {}
 }
 
-// Execute all commands
-pub fn execute(cmd: &str, stream: Rc<dyn Stream>) {
+// Execute all commands, and return the exit status of the last one.
+// The errors go to stderr if the output goes to the console,
+// otherwise they're written to the same stream, so the caller gets both of them.
+pub fn execute(cmd: &str, stream: Rc<dyn Stream>) -> i32 {
+    let err_stream: Rc<dyn Stream> = if stream.is_console() {
+        Rc::new(ConsoleStream::stderr())
+    } else {
+        stream.clone()
+    };
+
+    execute_with_err_stream(cmd, stream, err_stream)
+}
+
+// Same as [`execute`], but the errors are written to the given stream.
+pub fn execute_with_err_stream(
+    cmd: &str,
+    stream: Rc<dyn Stream>,
+    err_stream: Rc<dyn Stream>,
+) -> i32 {
     // Create new Parser
     let parser = Parser::new(cmd);
 
+    // Nothing runs if the command line can't be parsed, because the AST may be only a part of it.
+    // The exit status is 2 like the syntax errors of other shells.
+    let errors = parser.errors();
+    if !errors.is_empty() {
+        for error in errors {
+            err_stream.input(error.to_string());
+        }
+        session::set_last_status(2);
+        return 2;
+    }
+
     // Analyze the AST and save the command into an array
+    let mut status = 0;
     for cmd in parser.iter() {
         let mut cmd = analyze_node(cmd);
 
         cmd.add_stream(stream.clone());
+        cmd.add_err_stream(err_stream.clone());
 
-        cmd.execute();
+        status = cmd.execute();
+        session::set_last_status(status);
     }
 
     // Clear the Parser data
     parser.clear();
+
+    status
 }
 
 /// Analyze the AST and return the command.
-fn analyze_node(cmd: Box<dyn CommandAstNode>) -> Box<dyn Command> {
+fn analyze_node(mut cmd: Box<dyn CommandAstNode>) -> Box<dyn Command> {
+    if let Err(msg) = expansion::expand_node(&mut cmd) {
+        return error_cmd(ShellError::new("ru_shell", msg));
+    }
+
     match cmd.cmd_type() {
-        CommandType::ExtCommand => analyze_exe_node(cmd),
-        CommandType::ChainCommand => analyze_chain_node(cmd),
+        CommandType::ExtCommand => analyze_exe_node(cmd).unwrap_or_else(error_cmd),
+        CommandType::ChainCommand => analyze_chain_node(cmd).unwrap_or_else(error_cmd),
+        CommandType::RedirectCommand => Box::new(RedirectOperator::from(cmd)),
+        CommandType::ListCommand => Box::new(ListOperator::from(cmd)),
+        CommandType::BackgroundCommand => Box::new(BackgroundOperator::from(cmd)),
+        CommandType::AssignmentCommand => Box::new(AssignmentOperator::from(cmd)),
+        CommandType::SubshellCommand => Box::new(SubshellOperator::from(cmd)),
+        CommandType::GroupCommand => Box::new(GroupOperator::from(cmd)),
     }
 }
 
 /// Analyze the AST which type is [`parser::CommandType::ExtCommand`].
-fn analyze_exe_node(cmd: Box<dyn CommandAstNode>) -> Box<dyn Command> {
-    match cmd.token_type() {
-        TokenType::Ls => Box::new(LsCmd::from(cmd)),
-        TokenType::Grep => Box::new(GrepCmd::from(cmd)),
-        TokenType::Cat => Box::new(CatCmd::from(cmd)),
-        _ => {
-            todo!()
-        }
+/// The builtin commands are built by their declarations, see [`crate::builtin`].
+/// The error is reported by the command instead, see [`error_cmd`].
+fn analyze_exe_node(cmd: Box<dyn CommandAstNode>) -> Result<Box<dyn Command>, ShellError> {
+    if *cmd.token_type() == TokenType::Literal {
+        return Ok(Box::new(ExternalCmd::from(cmd)));
+    }
+
+    match builtin::lookup(cmd.literal()) {
+        Some(builtin) if builtin.token_type() == cmd.token_type() => Ok(builtin.build(cmd)),
+        _ => Err(unsupported(cmd.token_type())),
     }
 }
 
 /// Analyze the AST which type is [`parser::CommandType::ChainCommand`].
-fn analyze_chain_node(cmd: Box<dyn CommandAstNode>) -> Box<dyn Command> {
+fn analyze_chain_node(cmd: Box<dyn CommandAstNode>) -> Result<Box<dyn Command>, ShellError> {
     match cmd.token_type() {
-        TokenType::Pipe => Box::new(PipelineOperator::from(cmd)),
-        _ => {
-            todo!()
-        }
+        TokenType::Pipe => Ok(Box::new(PipelineOperator::from(cmd))),
+        token_type => Err(unsupported(token_type)),
+    }
+}
+
+// The error of the command that the executor doesn't know how to run.
+fn unsupported(token_type: &TokenType) -> ShellError {
+    ShellError::new("ru_shell", format!("unsupported command: {:?}", token_type))
+}
+
+// The command that reports the error when it runs, so the commands after it still run.
+fn error_cmd(error: ShellError) -> Box<dyn Command> {
+    Box::new(ErrorCmd::new(error))
+}
+
+// Get the stream for the error messages of the command,
+// which is the output stream if the command hasn't been given a separate one.
+fn error_stream<'a>(
+    err_stream: &'a Option<Rc<dyn Stream>>,
+    stream: &'a Option<Rc<dyn Stream>>,
+) -> &'a Rc<dyn Stream> {
+    err_stream.as_ref().or(stream.as_ref()).unwrap()
+}
+
+// Get the message of the io error without the error code,
+// such as 'No such file or directory' instead of 'No such file or directory (os error 2)'.
+pub fn io_error_message(e: &io::Error) -> String {
+    let msg = e.to_string();
+    match msg.find(" (os error") {
+        Some(index) => msg[..index].to_string(),
+        None => msg,
     }
 }
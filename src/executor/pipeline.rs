@@ -1,65 +1,168 @@
+use std::cell::RefCell;
+use std::io;
 use std::rc::Rc;
+use std::thread;
 
+use crate::jobs;
+use crate::stream::file_stream::{InputFileStream, PipeFileStream};
+use crate::stream::pipe_stream::{pipe_stream, PipeWriter};
 use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
-use crate::stream::pipeline_stream::PipeLineStream;
 
-use super::{analyze_node, Command};
+use super::subshell::{
+    child_err_stream, copy_to_stream, pipe, raw_fds, relay_input, wait_relay, write_errors,
+};
+use super::{analyze_node, error_stream, io_error_message, Command};
 
-// The pipeline operator is used to transfer the data between commands, and to output the result of the commands.
+// The pipeline operator is used to transfer the data between commands, such as 'cat big.log | grep x'.
+// The source command runs in a child process and writes its output to a pipe while it runs,
+// another thread of the shell puts the output into the pipe stream,
+// while the destination command reads the lines as its input and outputs the result of the commands.
+// When the destination command has finished, the pipe is closed, so the source command can stop early.
+//
+// The source command runs in a copy of the shell like a subshell, so the changes it makes don't stay,
+// such as 'cd dir | cat' or 'export A=1 | cat'.
+// Its errors don't flow into the destination command, such as 'ls missing | grep x'.
+// They go to the terminal directly if the stream for the errors is the console,
+// otherwise the child writes them to another pipe after its output, and they're put into the stream.
 pub struct PipelineOperator {
     // The source command is the command that will output the data to the pipeline.
-    source_cmd: Box<dyn Command>,
+    // It's built in the child process, so the AST is kept here.
+    source: Box<dyn CommandAstNode>,
+
+    // The end of the pipe stream that the output of the source command is put into,
+    // it's moved to the thread that reads the output from the child process when the pipeline runs.
+    pipe_writer: RefCell<Option<PipeWriter>>,
+
+    // The end of the pipe that the destination command reads from.
+    pipe_reader: Rc<dyn Stream>,
+
     // The destination command is the command that will receive the data from the pipeline.
     destination_cmd: Box<dyn Command>,
 
-    // This stream is used to output the result of the commands.
+    // The stream for the output of the destination command, the error of the pipeline goes to it
+    // if the stream for the errors isn't set.
     stream: Option<Rc<dyn Stream>>,
 
-    // The data that is transferred between the source command and the destination command.
-    pipeline_stream: Rc<PipeLineStream>,
+    // The stream for the errors of both commands.
+    err_stream: Option<Rc<dyn Stream>>,
+
+    // The input of the pipeline is read by the source command.
+    input_stream: Option<Rc<dyn Stream>>,
 }
 
 impl PipelineOperator {
-    fn new(
-        source_cmd: Box<dyn Command>,
-        destination_cmd: Box<dyn Command>,
-        pipeline_stream: Rc<PipeLineStream>,
-    ) -> Self {
+    fn new(source: Box<dyn CommandAstNode>, destination: Box<dyn CommandAstNode>) -> Self {
+        let (pipe_writer, pipe_reader) = pipe_stream();
+        let pipe_reader: Rc<dyn Stream> = Rc::new(pipe_reader);
+
+        let mut destination_cmd = analyze_node(destination);
+        destination_cmd.add_input_stream(pipe_reader.clone());
+
         Self {
-            source_cmd,
+            source,
+            pipe_writer: RefCell::new(Some(pipe_writer)),
+            pipe_reader,
             destination_cmd,
             stream: None,
-            pipeline_stream,
+            err_stream: None,
+            input_stream: None,
         }
     }
 }
 
+impl PipelineOperator {
+    // Run the source command in the child process, and the destination command in the shell.
+    fn run(&self, pipe_writer: PipeWriter) -> io::Result<i32> {
+        // The input is written to another pipe while the source command reads it, see [`relay_input`].
+        let (input_reader, input_relay) = relay_input(&self.input_stream)?;
+        let (reader, writer) = pipe()?;
+
+        // The errors of the source command are kept by the child if they don't go to the terminal.
+        let (err_reader, err_writer) = match &self.err_stream {
+            Some(err_stream) if !err_stream.is_console() => Some(pipe()?),
+            _ => None,
+        }
+        .unzip();
+
+        // The child is forked before the thread below starts, so it's a copy of the shell with only one thread.
+        // Only the shell reads the pipe, so the child fails to write when the shell stops reading it.
+        let keep_fds = raw_fds(&[input_reader.as_ref(), Some(&writer), err_writer.as_ref()]);
+        let pid = jobs::fork_subshell(&keep_fds, move || {
+            let err_stream = child_err_stream(&err_writer);
+            let mut source_cmd = analyze_node(self.source.clone());
+            source_cmd.add_stream(Rc::new(PipeFileStream::new(writer)));
+            source_cmd.add_err_stream(err_stream.clone());
+            if let Some(input_reader) = input_reader {
+                source_cmd.add_input_stream(Rc::new(InputFileStream::from_file(input_reader)));
+            }
+            let status = source_cmd.execute();
+
+            // The output ends with the command, before the errors are written.
+            drop(source_cmd);
+            write_errors(err_writer, err_stream);
+
+            status
+        })?;
+
+        // The end for writing has been dropped with the closure, so reading stops when the child has finished.
+        // If the destination command stops, the pipe is dropped, and the child fails to write the rest.
+        let relay = thread::spawn(move || copy_to_stream(reader, &pipe_writer));
+
+        let status = self.destination_cmd.execute();
+
+        // The rest of the data isn't needed, such as 'cat big.log | head -1'.
+        self.pipe_reader.close();
+        let _ = relay.join();
+
+        let result = match (err_reader, &self.err_stream) {
+            (Some(err_reader), Some(err_stream)) => copy_to_stream(err_reader, err_stream.as_ref()),
+            _ => Ok(()),
+        };
+        jobs::wait_pid(pid, 0)?;
+        wait_relay(input_relay);
+        result?;
+
+        Ok(status)
+    }
+}
+
 impl Command for PipelineOperator {
-    fn execute(&self) {
-        self.source_cmd.execute();
-        self.destination_cmd.execute();
-
-        self.stream
-            .as_ref()
-            .unwrap()
-            .input(self.pipeline_stream.output());
+    // The exit status of the pipeline is the one of the destination command.
+    fn execute(&self) -> i32 {
+        let pipe_writer = match self.pipe_writer.borrow_mut().take() {
+            Some(pipe_writer) => pipe_writer,
+            None => return 1,
+        };
+
+        match self.run(pipe_writer) {
+            Ok(status) => status,
+            Err(e) => {
+                error_stream(&self.err_stream, &self.stream)
+                    .input(format!("ru_shell: fork: {}", io_error_message(&e)));
+                1
+            }
+        }
     }
 
+    // The result of the commands is the output of the destination command.
     fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.destination_cmd.add_stream(stream.clone());
         self.stream = Some(stream);
     }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.destination_cmd.add_err_stream(stream.clone());
+        self.err_stream = Some(stream);
+    }
+
+    // The input of the pipeline is read by the source command.
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.input_stream = Some(stream);
+    }
 }
 
 impl From<Box<dyn CommandAstNode>> for PipelineOperator {
     fn from(cmd: Box<dyn CommandAstNode>) -> Self {
-        let pipeline_stream = Rc::new(PipeLineStream::new());
-
-        let mut source_cmd = analyze_node(cmd.get_source().unwrap());
-        source_cmd.add_stream(pipeline_stream.clone());
-
-        let mut destination_cmd = analyze_node(cmd.get_destination().unwrap());
-        destination_cmd.add_stream(pipeline_stream.clone());
-
-        Self::new(source_cmd, destination_cmd, pipeline_stream)
+        Self::new(cmd.get_source().unwrap(), cmd.get_destination().unwrap())
     }
 }
@@ -0,0 +1,209 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::parser::ast_node_trait::CommandAstNode;
+use crate::stream::file_stream::{FileStream, InputFileStream};
+use crate::stream::{pipeline_stream::PipeLineStream, Stream};
+use crate::token::token::TokenType;
+
+use super::{analyze_node, io_error_message, Command};
+
+// The target that the file descriptor is redirected to.
+enum RedirectTarget {
+    // Write to the file, the file is truncated first if 'append' is false.
+    File { path: String, append: bool },
+
+    // Write to the same place as another file descriptor, such as '2>&1'.
+    Fd(u32),
+
+    // Read the input from the file, such as '< in.txt'.
+    InputFile(String),
+
+    // Read the input from the body of the here-document, such as '<<EOF'.
+    HereDoc(String),
+}
+
+// The redirect operator changes where the output or the errors of the source command go.
+// 'ls > out.txt'  : the output is written to 'out.txt', the file is truncated first.
+// 'ls >> out.txt' : the output is appended to 'out.txt'.
+// 'ls 2> err.txt' : the errors are written to 'err.txt'.
+// 'ls > f 2>&1'   : the errors are written to the same place as the output.
+// 'cat < in.txt'  : the input is read from 'in.txt'.
+// 'cat <<EOF'     : the input is read from the lines until 'EOF'.
+pub struct RedirectOperator {
+    // The command whose output is redirected.
+    // The streams are added to it when the operator is executed, so it's wrapped by RefCell.
+    source_cmd: RefCell<Box<dyn Command>>,
+
+    // The file descriptor to redirect, 0 is the input, 1 is the output and 2 is the errors.
+    fd: u32,
+
+    target: RedirectTarget,
+
+    // This stream is used to output the result of the commands.
+    stream: Option<Rc<dyn Stream>>,
+
+    // This stream is used to output the errors, they go to 'stream' if it's not set.
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl RedirectOperator {
+    fn new(source_cmd: Box<dyn Command>, fd: u32, target: RedirectTarget) -> Self {
+        Self {
+            source_cmd: RefCell::new(source_cmd),
+            fd,
+            target,
+            stream: None,
+            err_stream: None,
+        }
+    }
+
+    // Open the stream of the input target.
+    // The file is read bit by bit while the command runs, the lines of the here-document end with line breaks.
+    fn read_input(&self) -> Result<Rc<dyn Stream>, String> {
+        let data = match &self.target {
+            RedirectTarget::InputFile(path) => {
+                return match InputFileStream::open(path) {
+                    Ok(input_stream) => Ok(Rc::new(input_stream)),
+                    Err(e) => Err(format!("{}: {}", path, io_error_message(&e))),
+                };
+            }
+            RedirectTarget::HereDoc(body) if body.is_empty() || body.ends_with('\n') => {
+                body.clone().into_bytes()
+            }
+            RedirectTarget::HereDoc(body) => format!("{}\n", body).into_bytes(),
+            _ => return Err(String::from("0: bad redirection")),
+        };
+
+        let input_stream = PipeLineStream::new();
+        input_stream.write(&data);
+
+        Ok(Rc::new(input_stream))
+    }
+
+    // Report the error of the redirection itself, such as a file that can't be opened.
+    // The command isn't executed in that case, and the exit status is 1.
+    fn report(&self, msg: String) {
+        let stream = self.err_stream.as_ref().or(self.stream.as_ref()).unwrap();
+        stream.input(format!("ru_shell: {}", msg));
+    }
+}
+
+impl Command for RedirectOperator {
+    fn execute(&self) -> i32 {
+        let stream = self.stream.clone().unwrap();
+        let err_stream = self.err_stream.clone();
+
+        if self.fd == 0 {
+            let input_stream = match self.read_input() {
+                Ok(input_stream) => input_stream,
+                Err(msg) => {
+                    self.report(msg);
+                    return 1;
+                }
+            };
+
+            let mut source_cmd = self.source_cmd.borrow_mut();
+            source_cmd.add_stream(stream);
+            if let Some(err_stream) = err_stream {
+                source_cmd.add_err_stream(err_stream);
+            }
+            source_cmd.add_input_stream(input_stream);
+            return source_cmd.execute();
+        }
+
+        // Open the file before the command runs, so the file exists even if the command writes nothing.
+        let mut file_stream: Option<Rc<FileStream>> = None;
+        let target: Rc<dyn Stream> = match &self.target {
+            RedirectTarget::File { path, append } => match FileStream::new(path, *append) {
+                Ok(file) => {
+                    let file = Rc::new(file);
+                    file_stream = Some(file.clone());
+                    file
+                }
+                Err(e) => {
+                    self.report(format!("{}: {}", path, io_error_message(&e)));
+                    return 1;
+                }
+            },
+            RedirectTarget::Fd(1) => stream.clone(),
+            RedirectTarget::Fd(2) => err_stream.clone().unwrap_or(stream.clone()),
+            RedirectTarget::Fd(fd) => {
+                self.report(format!("{}: bad file descriptor", fd));
+                return 1;
+            }
+            RedirectTarget::InputFile(_) | RedirectTarget::HereDoc(_) => {
+                self.report(format!("{}: bad redirection", self.fd));
+                return 1;
+            }
+        };
+
+        let mut source_cmd = self.source_cmd.borrow_mut();
+        match self.fd {
+            1 => {
+                source_cmd.add_stream(target);
+                if let Some(err_stream) = err_stream {
+                    source_cmd.add_err_stream(err_stream);
+                }
+            }
+            2 => {
+                source_cmd.add_stream(stream);
+                source_cmd.add_err_stream(target);
+            }
+            fd => {
+                self.report(format!("{}: bad file descriptor", fd));
+                return 1;
+            }
+        }
+
+        let status = source_cmd.execute();
+
+        if let Some(file_stream) = file_stream {
+            file_stream.output();
+        }
+
+        status
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    // The input is passed to the source command, unless it's the one being redirected.
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        if self.fd != 0 {
+            self.source_cmd.borrow_mut().add_input_stream(stream);
+        }
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for RedirectOperator {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        let source_cmd = analyze_node(cmd.get_source().unwrap());
+
+        let fd = cmd.get_option("fd").unwrap_or("1").parse::<u32>().unwrap_or(1);
+        let value = cmd
+            .get_values()
+            .and_then(|values| values.first().cloned())
+            .unwrap_or_default();
+
+        let target = match cmd.token_type() {
+            TokenType::GreaterThanAnd => RedirectTarget::Fd(value.parse::<u32>().unwrap_or(1)),
+            TokenType::DoubleGreaterThan => RedirectTarget::File {
+                path: value,
+                append: true,
+            },
+            TokenType::LessThan => RedirectTarget::InputFile(value),
+            TokenType::DoubleLessThan => RedirectTarget::HereDoc(value),
+            _ => RedirectTarget::File {
+                path: value,
+                append: false,
+            },
+        };
+
+        Self::new(source_cmd, fd, target)
+    }
+}
@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use crate::session;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::{error_stream, Command};
+
+// The shell options that 'shopt' knows.
+//     dotglob  : the patterns match the names starting with '.'
+//     failglob : a pattern that matches nothing is an error, and the command doesn't run
+//     nullglob : a pattern that matches nothing is removed, instead of being kept as it is
+pub const SHELL_OPTIONS: [&str; 3] = ["dotglob", "failglob", "nullglob"];
+
+// The shopt command sets or shows the shell options.
+//     shopt              : show all options
+//     shopt name ...     : show the options, the exit status is 0 only if all of them are set
+//     shopt -s name ...  : set the options
+//     shopt -u name ...  : unset the options
+pub struct ShoptCmd {
+    args: Vec<String>,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl ShoptCmd {
+    fn new(args: Vec<String>) -> Self {
+        ShoptCmd {
+            args,
+            stream: None,
+            err_stream: None,
+        }
+    }
+
+    fn print(&self, msg: String) {
+        self.stream.as_ref().unwrap().input(msg);
+    }
+
+    fn print_error(&self, msg: String) {
+        error_stream(&self.err_stream, &self.stream).input(msg);
+    }
+
+    fn show(&self, name: &str) {
+        let state = if session::option(name) { "on" } else { "off" };
+        self.print(format!("{:<16}{}", name, state));
+    }
+}
+
+impl Command for ShoptCmd {
+    fn execute(&self) -> i32 {
+        let (mode, names) = match self.args.first().map(|arg| arg.as_str()) {
+            Some("-s") => (Some(true), &self.args[1..]),
+            Some("-u") => (Some(false), &self.args[1..]),
+            Some(arg) if arg.starts_with('-') => {
+                self.print_error(format!("shopt: {}: invalid option", arg));
+                self.print_error(String::from("shopt: usage: shopt [-s|-u] [optname ...]"));
+                return 2;
+            }
+            _ => (None, &self.args[..]),
+        };
+
+        if names.is_empty() {
+            for name in SHELL_OPTIONS {
+                if mode.is_none_or(|enabled| session::option(name) == enabled) {
+                    self.show(name);
+                }
+            }
+            return 0;
+        }
+
+        let mut status = 0;
+        for name in names {
+            if !SHELL_OPTIONS.contains(&name.as_str()) {
+                self.print_error(format!("shopt: {}: invalid shell option name", name));
+                status = 1;
+                continue;
+            }
+
+            match mode {
+                Some(enabled) => session::set_option(name, enabled),
+                None => {
+                    self.show(name);
+                    if !session::option(name) {
+                        status = 1;
+                    }
+                }
+            }
+        }
+
+        status
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for ShoptCmd {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        ShoptCmd::new(cmd.get_values().unwrap_or_default())
+    }
+}
@@ -0,0 +1,255 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::rc::Rc;
+use std::thread::{self, JoinHandle};
+
+use crate::jobs;
+use crate::stream::console_stream::ConsoleStream;
+use crate::stream::file_stream::{InputFileStream, PipeFileStream};
+use crate::stream::pipeline_stream::PipeLineStream;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::{analyze_node, Command};
+
+// The subshell operator runs the commands in parentheses in a child process, such as '(cd dir && ls)',
+// so the working directory, the variables and the shell options they change are dropped with the child.
+// The output goes to the terminal directly if the stream is the console,
+// otherwise the child writes it to a pipe, and it's put into the stream while the child runs.
+pub struct SubshellOperator {
+    // The commands in the parentheses.
+    // They're built in the child process, so the AST is kept here.
+    source: Box<dyn CommandAstNode>,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+
+    // The input is passed to the child process through a pipe, see [`relay_input`],
+    // because the data is put into the stream by another thread of the shell, which the child doesn't have.
+    input_stream: Option<Rc<dyn Stream>>,
+}
+
+impl SubshellOperator {
+    fn new(source: Box<dyn CommandAstNode>) -> Self {
+        Self {
+            source,
+            stream: None,
+            err_stream: None,
+            input_stream: None,
+        }
+    }
+
+    // Build the commands and run them with the streams, it's called in the child process.
+    fn run(
+        &self,
+        stream: Rc<dyn Stream>,
+        err_stream: Rc<dyn Stream>,
+        input_reader: Option<File>,
+    ) -> i32 {
+        let mut cmd = analyze_node(self.source.clone());
+        cmd.add_stream(stream);
+        cmd.add_err_stream(err_stream);
+        if let Some(input_reader) = input_reader {
+            cmd.add_input_stream(Rc::new(InputFileStream::from_file(input_reader)));
+        }
+
+        cmd.execute()
+    }
+
+    // The errors go to the terminal directly if the stream for them is the console,
+    // otherwise the child writes them to a pipe after the output, see [`write_errors`].
+    fn err_pipe(&self) -> io::Result<Option<(File, File)>> {
+        match &self.err_stream {
+            Some(err_stream) if !err_stream.is_console() => pipe().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    // Run the commands in the child process, which writes the output to the terminal.
+    fn run_in_console(&self) -> io::Result<i32> {
+        let (input_reader, input_relay) = relay_input(&self.input_stream)?;
+        let (err_reader, err_writer) = self.err_pipe()?.unzip();
+
+        let keep_fds = raw_fds(&[input_reader.as_ref(), err_writer.as_ref()]);
+        let pid = jobs::fork_subshell(&keep_fds, || {
+            let console_stream = Rc::new(ConsoleStream::new());
+            let err_stream = child_err_stream(&err_writer);
+            let status = self.run(console_stream.clone(), err_stream.clone(), input_reader);
+            console_stream.output();
+            write_errors(err_writer, err_stream);
+
+            status
+        })?;
+
+        let result = self.read_errors(err_reader);
+        let status = jobs::wait_pid(pid, 0)?;
+        wait_relay(input_relay);
+        result?;
+
+        Ok(jobs::exit_code(status))
+    }
+
+    // Run the commands in the child process, which writes the output to the pipe while they run,
+    // and the output is put into the stream as it comes until the reader of the stream stops.
+    fn run_in_pipe(&self, stream: &Rc<dyn Stream>) -> io::Result<i32> {
+        let (input_reader, input_relay) = relay_input(&self.input_stream)?;
+        let (reader, writer) = pipe()?;
+        let (err_reader, err_writer) = self.err_pipe()?.unzip();
+
+        // Only the shell reads the pipe, so the child fails to write when the shell stops reading it.
+        let keep_fds = raw_fds(&[input_reader.as_ref(), Some(&writer), err_writer.as_ref()]);
+        let pid = jobs::fork_subshell(&keep_fds, move || {
+            let pipe_stream: Rc<dyn Stream> = Rc::new(PipeFileStream::new(writer));
+            let err_stream = child_err_stream(&err_writer);
+            let status = self.run(pipe_stream.clone(), err_stream.clone(), input_reader);
+
+            // The output ends before the errors are written, so the parent won't wait for both at once.
+            drop(pipe_stream);
+            write_errors(err_writer, err_stream);
+
+            status
+        })?;
+
+        // The end for writing has been dropped with the closure, so reading stops when the child exits.
+        // If the reader of the stream stops, the pipe is dropped, and the child fails to write the rest.
+        let result =
+            copy_to_stream(reader, stream.as_ref()).and_then(|_| self.read_errors(err_reader));
+        let status = jobs::wait_pid(pid, 0)?;
+        wait_relay(input_relay);
+        result?;
+
+        Ok(jobs::exit_code(status))
+    }
+
+    // Put the errors of the child into the stream for them.
+    fn read_errors(&self, err_reader: Option<File>) -> io::Result<()> {
+        match (err_reader, &self.err_stream) {
+            (Some(err_reader), Some(err_stream)) => copy_to_stream(err_reader, err_stream.as_ref()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Command for SubshellOperator {
+    // The exit status of the subshell is the one of the last command in it.
+    fn execute(&self) -> i32 {
+        let stream = self.stream.as_ref().unwrap();
+
+        let result = if stream.is_console() {
+            // Print the data that other commands have written before the child takes over.
+            stream.output();
+            self.run_in_console()
+        } else {
+            self.run_in_pipe(stream)
+        };
+
+        match result {
+            Ok(status) => status,
+            Err(e) => {
+                super::error_stream(&self.err_stream, &self.stream)
+                    .input(format!("ru_shell: fork: {}", super::io_error_message(&e)));
+                1
+            }
+        }
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+
+    fn add_input_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.input_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for SubshellOperator {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        Self::new(cmd.get_source().unwrap())
+    }
+}
+
+// Create a pipe, which is closed when the programs run by the child start, so only the child writes to it.
+pub(super) fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+// The files that the child process keeps, see [`jobs::fork_subshell`].
+pub(super) fn raw_fds(files: &[Option<&File>]) -> Vec<RawFd> {
+    files
+        .iter()
+        .flatten()
+        .map(|file| file.as_raw_fd())
+        .collect()
+}
+
+// Pass the input to the child process through a pipe, which another thread of the shell writes the data to bit by bit,
+// so the child reads it while it's put into the input stream, such as 'yes | (head -n 1)'.
+// The thread stops at the end of the data or when the child stops reading, and the writer of the input is told by
+// [`Stream::is_closed`] then. Return the end of the pipe for the child and the thread, see [`wait_relay`].
+pub(super) fn relay_input(
+    input_stream: &Option<Rc<dyn Stream>>,
+) -> io::Result<(Option<File>, Option<JoinHandle<()>>)> {
+    let input_stream = match input_stream {
+        Some(input_stream) => input_stream,
+        None => return Ok((None, None)),
+    };
+
+    let (reader, mut writer) = pipe()?;
+    let mut input = input_stream.take_reader();
+    let relay = thread::spawn(move || {
+        let _ = io::copy(&mut input, &mut writer);
+    });
+
+    Ok((Some(reader), Some(relay)))
+}
+
+// Wait for the thread that writes the input after the child process has exited,
+// the pipe has no reader then, so the thread stops at the next write.
+pub(super) fn wait_relay(relay: Option<JoinHandle<()>>) {
+    if let Some(relay) = relay {
+        let _ = relay.join();
+    }
+}
+
+// The stream for the errors in the child process.
+// They're kept until the commands have finished if they're written to the pipe.
+pub(super) fn child_err_stream(err_writer: &Option<File>) -> Rc<dyn Stream> {
+    match err_writer {
+        Some(_) => Rc::new(PipeLineStream::new()),
+        None => Rc::new(ConsoleStream::stderr()),
+    }
+}
+
+// Write the errors kept by the child process to the pipe.
+pub(super) fn write_errors(err_writer: Option<File>, err_stream: Rc<dyn Stream>) {
+    if let Some(mut err_writer) = err_writer {
+        let _ = err_writer.write_all(&crate::stream::read_all(err_stream.as_ref()));
+    }
+}
+
+// Copy the data from the pipe to the stream until the pipe is closed or the reader of the stream stops.
+pub(super) fn copy_to_stream(mut reader: File, stream: &dyn Stream) -> io::Result<()> {
+    let mut buf = [0; 8192];
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        stream.write(&buf[..len]);
+        if stream.is_closed() {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::rc::Rc;
+
+use crate::lexer::is_variable_name;
+use crate::session;
+use crate::token::token::TokenType;
+use crate::{parser::ast_node_trait::CommandAstNode, stream::Stream};
+
+use super::{error_stream, Command};
+
+// The variable commands change the variables of the shell.
+//
+// These are the commands and the values they support:
+//     export [NAME[=value] ...] : export the variables to the child processes,
+//                                 the exported variables are listed if there is no value
+//     export -p                 : list the exported variables in a form that can be read back
+//     unset NAME ...            : remove the variables
+pub struct VariableCmd {
+    // Which variable command it is.
+    kind: TokenType,
+
+    args: Vec<String>,
+
+    stream: Option<Rc<dyn Stream>>,
+    err_stream: Option<Rc<dyn Stream>>,
+}
+
+impl VariableCmd {
+    fn new(kind: TokenType, args: Vec<String>) -> Self {
+        VariableCmd {
+            kind,
+            args,
+            stream: None,
+            err_stream: None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            TokenType::Export => "export",
+            _ => "unset",
+        }
+    }
+
+    fn print(&self, msg: String) {
+        self.stream.as_ref().unwrap().input(msg);
+    }
+
+    fn print_error(&self, msg: String) {
+        error_stream(&self.err_stream, &self.stream).input(msg);
+    }
+
+    // Check the name of the variable, and report the error if it's invalid.
+    fn check_name(&self, name: &str) -> bool {
+        if !is_variable_name(name) {
+            self.print_error(format!(
+                "{}: `{}': not a valid identifier",
+                self.name(),
+                name
+            ));
+            return false;
+        }
+
+        true
+    }
+
+    fn export(&self) -> i32 {
+        let args = match self.args.first().map(|arg| arg.as_str()) {
+            Some("-p") | Some("--") => &self.args[1..],
+            Some(arg) if arg.starts_with('-') && arg.len() > 1 => {
+                self.print_error(format!("export: {}: invalid option", arg));
+                self.print_error(String::from("export: usage: export [-p] [name[=value] ...]"));
+                return 2;
+            }
+            _ => &self.args[..],
+        };
+
+        if args.is_empty() {
+            for (name, value) in session::exported_variables() {
+                self.print(format!("export {}=\"{}\"", name, escape_double_quoted(&value)));
+            }
+            return 0;
+        }
+
+        let mut status = 0;
+        for arg in args.iter() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg.as_str(), None),
+            };
+
+            if self.check_name(name) {
+                session::export_variable(name, value);
+            } else {
+                status = 1;
+            }
+        }
+
+        status
+    }
+
+    fn unset(&self) -> i32 {
+        let mut status = 0;
+        for name in self.args.iter() {
+            if self.check_name(name) {
+                session::unset_variable(name);
+            } else {
+                status = 1;
+            }
+        }
+
+        status
+    }
+}
+
+impl Command for VariableCmd {
+    fn execute(&self) -> i32 {
+        match self.kind {
+            TokenType::Export => self.export(),
+            _ => self.unset(),
+        }
+    }
+
+    fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.stream = Some(stream);
+    }
+
+    fn add_err_stream(&mut self, stream: Rc<dyn Stream>) {
+        self.err_stream = Some(stream);
+    }
+}
+
+impl From<Box<dyn CommandAstNode>> for VariableCmd {
+    fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+        VariableCmd::new(
+            cmd.token_type().clone(),
+            cmd.get_values().unwrap_or_default(),
+        )
+    }
+}
+
+// Escape the chars that are special in double quotes, so the value is read back as it is.
+fn escape_double_quoted(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
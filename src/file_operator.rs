@@ -1,8 +1,19 @@
 use std::{
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
     io::{self, BufWriter, Write},
+    path::Path,
+    process,
 };
 
+// The max size that means no limit, such as for the files of the redirections.
+pub const UNLIMITED: u32 = u32::MAX;
+
+// The file is limited by its size and the number of its entries, which are the lines.
+// When a write would exceed a limit, the file is trimmed to its newest entries,
+// which take up to half of the limits, so the file isn't trimmed again on every write.
+// Before that the whole file is kept in the backups if there are any, such as 'x.1' and 'x.2', 'x.1' is the newest.
+// The file and the backups are written to a temporary file that is renamed to them,
+// so they're never left half written, even if the shell crashes.
 pub struct FileOperator {
     // The buffer writer for file
     writer: Option<BufWriter<File>>,
@@ -15,11 +26,21 @@ pub struct FileOperator {
     // If file exists, the flag to indicate whether the file need to be covered
     pub overwrite: bool,
 
-    // The max size of file
+    // The max size of file in bytes, see [`UNLIMITED`]
     pub max_size: u32,
 
+    // The max number of entries of file, there is no limit if it's None
+    pub max_entries: Option<usize>,
+
+    // The number of backups that are kept when the file is trimmed
+    pub backups: usize,
+
     // The path of file
     pub path: String,
+
+    // The size and the number of entries of file, they're counted when the file is opened
+    size: u64,
+    entries: usize,
 }
 
 impl FileOperator {
@@ -29,15 +50,29 @@ impl FileOperator {
             is_open: false,
             overwrite,
             max_size,
+            max_entries: None,
+            backups: 0,
             path: path.to_string(),
+            size: 0,
+            entries: 0,
         }
     }
 
-    // Because the file may be not used immediately, a field to indicate whether the file is ready.
-    // This is done to reduce memory usage when the file is not used.
-    pub fn ready(&mut self) {
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    // Open the file, it's done by the first write if it isn't called before.
+    // Because the file may be not used immediately, it isn't opened by [`new`] to reduce memory usage.
+    pub fn open(&mut self) -> io::Result<()> {
         if self.is_open {
-            return;
+            return Ok(());
         }
 
         let file = if !self.overwrite {
@@ -45,22 +80,39 @@ impl FileOperator {
             OpenOptions::new()
                 .append(true)
                 .create(true) // Create the file if it does not exist
-                .open(&self.path)
-                .expect("Unable to open or create file")
+                .open(&self.path)?
         } else {
             // If overwriting is needed, directly create the file. This will clear the file if it already exists.
-            File::create(&self.path).unwrap()
+            File::create(&self.path)?
+        };
+
+        // Count the size and the entries, the entries are read only if they're limited
+        self.size = file.metadata()?.len();
+        self.entries = match self.max_entries {
+            Some(_) if self.size > 0 => count_entries(&fs::read(&self.path)?),
+            _ => 0,
         };
 
         // Create buffer writer
         self.writer = Some(BufWriter::new(file));
         self.is_open = true;
+
+        Ok(())
+    }
+
+    // Flush the buffer writer without closing the file
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
     }
-  
+
     // Close the file and flush the buffer writer
     pub fn close(&mut self) {
+        // The data that can't be written is dropped with the writer, use [`flush`] to get the error.
         if let Some(writer) = &mut self.writer {
-            writer.flush().expect("Failed to flush")
+            let _ = writer.flush();
         }
 
         self.writer = None;
@@ -77,20 +129,124 @@ impl FileOperator {
 
     // Write byte data to file
     // This is the real write function to write data to file
-    fn write_byte(&mut self, data: &[u8]) -> io::Result<()> {
+    pub fn write_byte(&mut self, data: &[u8]) -> io::Result<()> {
         if !self.is_open {
-            self.ready();
+            self.open()?;
+        }
+
+        if self.exceeds_limits(data) {
+            return self.trim(data);
         }
 
         match &mut self.writer {
-            Some(writer) => writer.write_all(data),
+            Some(writer) => {
+                writer.write_all(data)?;
+                self.size += data.len() as u64;
+                self.entries += count_entries(data);
+                Ok(())
+            }
             None => {
                 self.close();
-                Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "The file is not ready to write",
-                ))
+                Err(io::Error::other("The file is not ready to write"))
             }
         }
     }
+
+    // Replace the content of file with the data through a temporary file, see [`FileOperator`].
+    // The data written after it is appended.
+    pub fn rewrite(&mut self, data: &[u8]) -> io::Result<()> {
+        self.close();
+        write_atomically(&self.path, data)?;
+        self.overwrite = false;
+
+        Ok(())
+    }
+
+    fn exceeds_limits(&self, data: &[u8]) -> bool {
+        let size = self.size + data.len() as u64;
+        let entries = self.entries + count_entries(data);
+
+        (self.max_size != UNLIMITED && size > self.max_size as u64)
+            || self
+                .max_entries
+                .is_some_and(|max_entries| entries > max_entries)
+    }
+
+    // Keep the file in the backups, and write the newest entries of the file and the data to it.
+    fn trim(&mut self, data: &[u8]) -> io::Result<()> {
+        self.flush()?;
+        self.close();
+
+        let mut content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        if self.backups > 0 && !content.is_empty() {
+            self.rotate_backups(&content)?;
+        }
+
+        content.extend_from_slice(data);
+        let start = self.newest_entries_start(&content);
+        self.rewrite(&content[start..])
+    }
+
+    // Shift the backups, such as 'x.1' to 'x.2', the oldest one is replaced,
+    // and write the content of file to 'x.1'.
+    fn rotate_backups(&self, content: &[u8]) -> io::Result<()> {
+        for number in (1..self.backups).rev() {
+            let backup = self.backup_path(number);
+            if Path::new(&backup).exists() {
+                fs::rename(&backup, self.backup_path(number + 1))?;
+            }
+        }
+
+        write_atomically(&self.backup_path(1), content)
+    }
+
+    pub fn backup_path(&self, number: usize) -> String {
+        format!("{}.{}", self.path, number)
+    }
+
+    // Get the start of the newest entries that take up to half of the limits, the last entry is always kept.
+    fn newest_entries_start(&self, content: &[u8]) -> usize {
+        let max_size = match self.max_size {
+            UNLIMITED => usize::MAX,
+            max_size => max_size as usize / 2,
+        };
+        let max_entries = self
+            .max_entries
+            .map_or(usize::MAX, |max_entries| max_entries / 2);
+
+        let mut start = content.len();
+        for (count, entry) in content.split_inclusive(|b| *b == b'\n').rev().enumerate() {
+            let size = content.len() - start + entry.len();
+            if count > 0 && (count >= max_entries || size > max_size) {
+                break;
+            }
+            start -= entry.len();
+        }
+
+        start
+    }
+}
+
+fn count_entries(data: &[u8]) -> usize {
+    data.iter().filter(|b| **b == b'\n').count()
+}
+
+// Write the data to a temporary file next to the file, and rename it to the file.
+fn write_atomically(path: &str, data: &[u8]) -> io::Result<()> {
+    let temp_path = format!("{}.{}.tmp", path, process::id());
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
 }
@@ -0,0 +1,289 @@
+use std::fs;
+use std::io;
+
+use crate::file_operator::FileOperator;
+
+// The limits of the history file. The history keeps the same number of lines in memory and in the file.
+// The file is trimmed to the newest lines if it gets larger than the size too,
+// and the whole file is kept in two backups then, such as '~/.rusty_shell_history.1', see [`FileOperator`].
+const FILE_MAX_SIZE: u32 = 1024 * 1024;
+const FILE_MAX_ENTRIES: usize = 1000;
+const FILE_BACKUPS: usize = 2;
+
+// The history of the command lines, they're numbered from 1 like in other shells.
+// The lines are kept in the history file if it's given, each line is appended to it when it's added,
+// and the file is written again when the lines are cleared or deleted.
+// A command line of several lines, such as the quoted text, is stored as one line in the file,
+// the line breaks are written as '\n' and the backslashes as '\\', see [`encode`].
+// Only the newest lines are kept, the file is written again with them when it has more lines than that.
+//
+// The lines can be referred to by the events, which are expanded before the command line is parsed:
+//     !!          : the last line
+//     !n          : the line n
+//     !-n         : the line n lines back, '!-1' is the last line
+//     !prefix     : the last line that starts with the prefix
+//     ^old^new^   : the last line with the first 'old' replaced by 'new', it's only at the start of the line
+// The '!' is kept if it's followed by a blank, '=' or '(', or if it's quoted by single quotes or a backslash.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    lines: Vec<String>,
+    file: Option<String>,
+
+    // The number of lines in the history file, it's counted when the file is loaded,
+    // so the file isn't read again to know when to write it again.
+    file_entries: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    // The history that is kept in the file, use [`load`] to read the lines in it.
+    pub fn with_file(path: &str) -> Self {
+        History {
+            lines: Vec::new(),
+            file: Some(path.to_string()),
+            file_entries: 0,
+        }
+    }
+
+    // Read the lines of the history file, it's fine if the file doesn't exist yet.
+    pub fn load(&mut self) -> io::Result<()> {
+        let path = match &self.file {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                self.file_entries = content.lines().count();
+                self.lines = content
+                    .lines()
+                    .filter(|line| should_record(line))
+                    .map(decode)
+                    .collect();
+                self.trim();
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    // Add the line to the history, the blank lines and 'exit' are skipped.
+    pub fn add(&mut self, line: &str) -> io::Result<()> {
+        if !should_record(line) {
+            return Ok(());
+        }
+        self.lines.push(line.to_string());
+        self.trim();
+
+        match &self.file {
+            // The file would have more lines than the history keeps, so it's written again with the kept ones.
+            Some(_) if self.file_entries >= FILE_MAX_ENTRIES => self.save(),
+            Some(path) => {
+                let mut file = file_operator(path);
+                file.write(&format!("{}\n", encode(line)))?;
+                file.flush()?;
+                self.file_entries += 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.lines.clear();
+        self.save()
+    }
+
+    // Delete the line by its number, the lines after it are numbered again.
+    pub fn delete(&mut self, number: usize) -> Result<(), String> {
+        if number == 0 || number > self.lines.len() {
+            return Err(format!("{}: history position out of range", number));
+        }
+        self.lines.remove(number - 1);
+        self.save().map_err(|e| e.to_string())
+    }
+
+    // Drop the oldest lines that are more than the history keeps.
+    fn trim(&mut self) {
+        let excess = self.lines.len().saturating_sub(FILE_MAX_ENTRIES);
+        self.lines.drain(..excess);
+    }
+
+    // Write all the lines to the history file again.
+    fn save(&mut self) -> io::Result<()> {
+        match &self.file {
+            Some(path) => {
+                let content: String = self
+                    .lines
+                    .iter()
+                    .map(|line| format!("{}\n", encode(line)))
+                    .collect();
+                file_operator(path).rewrite(content.as_bytes())?;
+                self.file_entries = self.lines.len();
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Expand the events of the line, see [`History`].
+    // None is returned if there is no event, and the error tells the event that can't be found, such as '!x: event not found'.
+    pub fn expand(&self, line: &str) -> Result<Option<String>, String> {
+        if let Some(rest) = line.strip_prefix('^') {
+            return self.substitute(line, rest).map(Some);
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let mut expanded = String::new();
+        let mut found = false;
+        let mut in_single_quote = false;
+        let mut in_double_quote = false;
+
+        let mut index = 0;
+        while index < chars.len() {
+            let c = chars[index];
+            match c {
+                '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+                '"' if !in_single_quote => in_double_quote = !in_double_quote,
+                '\\' if !in_single_quote => {
+                    expanded.push(c);
+                    if let Some(&next) = chars.get(index + 1) {
+                        expanded.push(next);
+                    }
+                    index += 2;
+                    continue;
+                }
+                '!' if !in_single_quote => {
+                    if let Some((text, end)) = self.event(&chars, index + 1)? {
+                        expanded.push_str(&text);
+                        found = true;
+                        index = end;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            expanded.push(c);
+            index += 1;
+        }
+
+        Ok(if found { Some(expanded) } else { None })
+    }
+
+    // Get the line that the event after '!' refers to, and the index after the event.
+    fn event(&self, chars: &[char], start: usize) -> Result<Option<(String, usize)>, String> {
+        let (line, end) = match chars.get(start) {
+            None | Some(' ' | '\t' | '\n' | '=' | '(' | '"') => return Ok(None),
+            Some('!') => (self.lines.last(), start + 1),
+            Some('-') => {
+                let (number, end) = parse_number(chars, start + 1);
+                let index = number.and_then(|n| self.lines.len().checked_sub(n));
+                (index.and_then(|i| self.lines.get(i)), end)
+            }
+            Some('0'..='9') => {
+                let (number, end) = parse_number(chars, start);
+                let index = number.and_then(|n| n.checked_sub(1));
+                (index.and_then(|i| self.lines.get(i)), end)
+            }
+            Some(_) => {
+                let mut end = start;
+                while end < chars.len() && !is_event_end(chars[end]) {
+                    end += 1;
+                }
+                let prefix: String = chars[start..end].iter().collect();
+                let line = self
+                    .lines
+                    .iter()
+                    .rev()
+                    .find(|line| line.starts_with(&prefix));
+                (line, end)
+            }
+        };
+
+        match line {
+            Some(line) => Ok(Some((line.clone(), end))),
+            None => {
+                let event: String = chars[start..end].iter().collect();
+                Err(format!("!{}: event not found", event))
+            }
+        }
+    }
+
+    // Replace the first 'old' of the last line by 'new', the text after the last '^' is appended.
+    fn substitute(&self, line: &str, rest: &str) -> Result<String, String> {
+        let mut parts = rest.splitn(3, '^');
+        let old = parts.next().unwrap_or_default();
+        let new = parts.next().unwrap_or_default();
+        let tail = parts.next().unwrap_or_default();
+
+        match self.lines.last() {
+            Some(last) if !old.is_empty() && last.contains(old) => {
+                Ok(format!("{}{}", last.replacen(old, new, 1), tail))
+            }
+            _ => Err(format!("{}: substitution failed", line)),
+        }
+    }
+}
+
+// The number of lines is limited by the history itself, see [`History::add`].
+fn file_operator(path: &str) -> FileOperator {
+    FileOperator::new(path, false, FILE_MAX_SIZE).with_backups(FILE_BACKUPS)
+}
+
+// Write the line as one line of the file, the line breaks are written as '\n' and the backslashes as '\\'.
+fn encode(line: &str) -> String {
+    line.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+// Read the line written by [`encode`], the other backslashes are kept as they are.
+fn decode(line: &str) -> String {
+    let mut decoded = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => decoded.push('\n'),
+            Some('\\') => decoded.push('\\'),
+            Some(c) => {
+                decoded.push('\\');
+                decoded.push(c);
+            }
+            None => decoded.push('\\'),
+        }
+    }
+
+    decoded
+}
+
+// Whether the line is kept in the history, the blank lines and 'exit' aren't.
+pub fn should_record(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && line != "exit"
+}
+
+// Parse the digits from the start, the index after them is returned too.
+fn parse_number(chars: &[char], start: usize) -> (Option<usize>, usize) {
+    let mut end = start;
+    while end < chars.len() && chars[end].is_ascii_digit() {
+        end += 1;
+    }
+    let number: String = chars[start..end].iter().collect();
+    (number.parse().ok(), end)
+}
+
+// The prefix of '!prefix' goes on until a blank or an operator.
+fn is_event_end(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ';' | '|' | '&' | '(' | ')' | '<' | '>' | '"' | '\'')
+}
@@ -0,0 +1,425 @@
+use std::fs;
+use std::io::{self, Write};
+use std::os::fd::RawFd;
+
+use libc::{c_int, c_uint, pid_t};
+
+use crate::session;
+
+// The state of a job in the job table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Stopped,
+    // The job has finished with the exit status.
+    Done(i32),
+}
+
+// A job is a command that runs in its own process group,
+// either started in the background with '&' or suspended by Ctrl-Z.
+#[derive(Debug, Clone)]
+pub struct Job {
+    // The number of the job, such as 1 in '%1'.
+    pub id: usize,
+
+    // The id of the process group, which is also the pid of its first process.
+    pub pgid: pid_t,
+
+    // The command line of the job.
+    pub command: String,
+
+    pub state: JobState,
+}
+
+impl Job {
+    // Describe the job like other shells do, such as '[1]+  Running                 sleep 10 &'.
+    // The marker is '+' for the current job, '-' for the previous job and ' ' for the others.
+    pub fn describe(&self, marker: char) -> String {
+        let state = match self.state {
+            JobState::Running => String::from("Running"),
+            JobState::Stopped => String::from("Stopped"),
+            JobState::Done(0) => String::from("Done"),
+            JobState::Done(status) => format!("Exit {}", status),
+        };
+        let background = if self.state == JobState::Running { " &" } else { "" };
+
+        format!(
+            "[{}]{}  {:<24}{}{}",
+            self.id, marker, state, self.command, background
+        )
+    }
+}
+
+// The jobs of the shell, the last one is the current job and the one before it is the previous job.
+#[derive(Debug, Default, Clone)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+}
+
+impl JobTable {
+    // Add the job and return its number, the number is one more than the largest one in use.
+    pub fn add(&mut self, pgid: pid_t, command: &str, state: JobState) -> usize {
+        let id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        self.jobs.push(Job {
+            id,
+            pgid,
+            command: command.to_string(),
+            state,
+        });
+
+        id
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    // Get the marker of the job, see [`Job::describe`].
+    pub fn marker(&self, id: usize) -> char {
+        let mut ids = self.jobs.iter().rev().map(|job| job.id);
+        if ids.next() == Some(id) {
+            '+'
+        } else if ids.next() == Some(id) {
+            '-'
+        } else {
+            ' '
+        }
+    }
+
+    // Find the job by the job spec, the current job is used if the spec is None.
+    // '%n' or 'n' : the job n
+    // '%+' or '%%': the current job
+    // '%-'        : the previous job
+    pub fn find(&self, spec: Option<&str>) -> Result<Job, String> {
+        let spec = spec.unwrap_or("%+");
+        let job = match spec {
+            "%+" | "%%" | "%" => self.jobs.last(),
+            "%-" => self.jobs.iter().rev().nth(1),
+            _ => match spec.trim_start_matches('%').parse::<usize>() {
+                Ok(id) => self.jobs.iter().find(|job| job.id == id),
+                Err(_) => None,
+            },
+        };
+
+        match (job, spec) {
+            (Some(job), _) => Ok(job.clone()),
+            (None, "%+") => Err(String::from("current: no such job")),
+            (None, _) => Err(format!("{}: no such job", spec)),
+        }
+    }
+
+    // Find the job by the pid of its first process.
+    pub fn find_by_pid(&self, pid: pid_t) -> Option<Job> {
+        self.jobs.iter().find(|job| job.pgid == pid).cloned()
+    }
+
+    // Change the state of the job.
+    // A job that has been stopped becomes the current job.
+    pub fn set_state(&mut self, id: usize, state: JobState) {
+        if let Some(index) = self.jobs.iter().position(|job| job.id == id) {
+            let mut job = self.jobs.remove(index);
+            job.state = state;
+            if state == JobState::Stopped {
+                self.jobs.push(job);
+            } else {
+                self.jobs.insert(index, job);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        Some(self.jobs.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.jobs.clear();
+    }
+
+    // Check the state of every job without blocking.
+    pub fn update(&mut self) {
+        for job in self.jobs.iter_mut() {
+            if let JobState::Done(_) = job.state {
+                continue;
+            }
+
+            let mut status: c_int = 0;
+            let flags = libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED;
+            let pid = unsafe { libc::waitpid(job.pgid, &mut status, flags) };
+            if pid == job.pgid {
+                job.state = if libc::WIFSTOPPED(status) {
+                    JobState::Stopped
+                } else if libc::WIFCONTINUED(status) {
+                    JobState::Running
+                } else {
+                    JobState::Done(exit_code(status))
+                };
+            } else if pid < 0 {
+                // The process has been reaped by someone else, it won't be seen again.
+                job.state = JobState::Done(0);
+            }
+        }
+    }
+
+    // Remove the jobs that have finished, and return their descriptions.
+    pub fn take_done(&mut self) -> Vec<String> {
+        let mut done = Vec::new();
+
+        for job in self.jobs.iter() {
+            if let JobState::Done(_) = job.state {
+                done.push(job.describe(self.marker(job.id)));
+            }
+        }
+        self.jobs.retain(|job| !matches!(job.state, JobState::Done(_)));
+
+        done
+    }
+}
+
+// Turn the status reported by waitpid into an exit status,
+// 128 + N is used for a process that was terminated or stopped by the signal N.
+pub fn exit_code(status: c_int) -> i32 {
+    if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        128 + libc::WTERMSIG(status)
+    } else if libc::WIFSTOPPED(status) {
+        128 + libc::WSTOPSIG(status)
+    } else {
+        1
+    }
+}
+
+// Enable job control if the shell reads from a terminal.
+// The shell puts itself in its own process group, takes the terminal,
+// and ignores the signals sent by the terminal, which are meant for the foreground job.
+pub fn init_job_control() {
+    unsafe {
+        if libc::isatty(libc::STDIN_FILENO) == 0 {
+            return;
+        }
+
+        // Wait until the shell has been moved to the foreground.
+        loop {
+            let pgrp = libc::getpgrp();
+            if libc::tcgetpgrp(libc::STDIN_FILENO) == pgrp {
+                break;
+            }
+            libc::kill(-pgrp, libc::SIGTTIN);
+        }
+
+        for signal in [
+            libc::SIGINT,
+            libc::SIGQUIT,
+            libc::SIGTSTP,
+            libc::SIGTTIN,
+            libc::SIGTTOU,
+        ] {
+            libc::signal(signal, libc::SIG_IGN);
+        }
+
+        // It fails if the shell is a session leader, which already owns its process group.
+        let pid = libc::getpid();
+        libc::setpgid(pid, pid);
+        libc::tcsetpgrp(libc::STDIN_FILENO, pid);
+    }
+
+    session::set_job_control(true);
+}
+
+// Restore the signals that the shell ignores, it's called in the child process before running a command.
+// The signals of job control are restored only if the child runs in its own process group,
+// otherwise Ctrl-Z would stop the child while the shell is still waiting for it.
+// It doesn't allocate memory, because it's also called between fork and exec.
+pub fn reset_signals(job_control: bool) {
+    let signals: &[c_int] = if job_control {
+        &[
+            libc::SIGINT,
+            libc::SIGQUIT,
+            libc::SIGTSTP,
+            libc::SIGTTIN,
+            libc::SIGTTOU,
+        ]
+    } else {
+        &[libc::SIGINT, libc::SIGQUIT]
+    };
+
+    for &signal in signals {
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+        }
+    }
+}
+
+// Give the terminal to the process group, it does nothing without job control.
+pub fn give_terminal(pgid: pid_t) {
+    if session::job_control() {
+        unsafe {
+            libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+        }
+    }
+}
+
+// Take the terminal back to the shell, it does nothing without job control.
+pub fn take_terminal() {
+    if session::job_control() {
+        unsafe {
+            libc::tcsetpgrp(libc::STDIN_FILENO, libc::getpgrp());
+        }
+    }
+}
+
+// Run the process group in the foreground, and wait until its first process exits or stops.
+// The process group is continued first if 'resume' is true.
+// Return the status reported by waitpid.
+pub fn wait_in_foreground(pgid: pid_t, resume: bool) -> io::Result<c_int> {
+    give_terminal(pgid);
+    if resume {
+        unsafe {
+            libc::kill(-pgid, libc::SIGCONT);
+        }
+    }
+
+    let result = wait_pid(pgid, libc::WUNTRACED);
+    take_terminal();
+
+    result
+}
+
+// Wait for the process, waitpid is called again if it's interrupted by a signal.
+pub fn wait_pid(pid: pid_t, flags: c_int) -> io::Result<c_int> {
+    let mut status: c_int = 0;
+
+    loop {
+        if unsafe { libc::waitpid(pid, &mut status, flags) } >= 0 {
+            return Ok(status);
+        }
+
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+}
+
+// Run the function in a child process in the background, and add the child to the job table.
+// The child is a copy of the shell, so builtin commands and pipelines can run in the background too.
+// The exit status of the function is the exit status of the child.
+// Return the number of the job and the pid of the child.
+pub fn spawn_background<F>(command: &str, run: F) -> io::Result<(usize, pid_t)>
+where
+    F: FnOnce() -> i32,
+{
+    // The data in the buffer would be printed twice if the child inherits it.
+    io::stdout().flush()?;
+
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            // The child has its own process group, so the signals of the terminal don't reach it.
+            unsafe {
+                libc::setpgid(0, 0);
+            }
+            reset_signals(true);
+            close_inherited_fds(&[]);
+            session::set_job_control(false);
+            session::with_jobs(|jobs| jobs.clear());
+
+            let status = run();
+            let _ = io::stdout().flush();
+
+            // Exit without running the cleanup of the shell, which belongs to the parent.
+            unsafe { libc::_exit(status) }
+        }
+        pid => {
+            // Set the process group in the parent too, so it's ready whichever process runs first.
+            unsafe {
+                libc::setpgid(pid, pid);
+            }
+            let id = session::with_jobs(|jobs| jobs.add(pid, command, JobState::Running));
+
+            Ok((id, pid))
+        }
+    }
+}
+
+// Run the function in a child process in the foreground, such as the subshell '(cd dir && ls)'.
+// The child is a copy of the shell, so the changes it makes don't affect the shell.
+// It stays in the process group of the shell, so Ctrl-C stops it, and the caller waits for it with [`wait_pid`].
+// It's called only by the thread that runs the commands, the other threads of the shell just copy the data,
+// so the child doesn't miss a thread that was running the commands, see [`crate::executor::pipeline`].
+// The child keeps only the standard streams and the files in 'keep_fds', such as the pipes it talks to the shell through.
+// Return the pid of the child.
+pub fn fork_subshell<F>(keep_fds: &[RawFd], run: F) -> io::Result<pid_t>
+where
+    F: FnOnce() -> i32,
+{
+    // The data in the buffer would be printed twice if the child inherits it.
+    io::stdout().flush()?;
+
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            reset_signals(false);
+            close_inherited_fds(keep_fds);
+            session::set_job_control(false);
+            session::with_jobs(|jobs| jobs.clear());
+
+            let status = run();
+            let _ = io::stdout().flush();
+
+            // Exit without running the cleanup of the shell, which belongs to the parent.
+            unsafe { libc::_exit(status) }
+        }
+        pid => Ok(pid),
+    }
+}
+
+// Close the files that the child process has inherited from the shell, except the standard streams and 'keep_fds'.
+// The other threads of the shell write to pipes that the child doesn't use, such as the input of another subshell,
+// and the reader of such a pipe only gets to the end of the data when every copy of the end for writing is closed.
+fn close_inherited_fds(keep_fds: &[RawFd]) {
+    let mut keep_fds: Vec<c_uint> = keep_fds.iter().map(|&fd| fd as c_uint).collect();
+    keep_fds.sort_unstable();
+
+    let mut first: c_uint = 3;
+    for fd in keep_fds {
+        if fd > first {
+            close_range(first, fd - 1);
+        }
+        first = first.max(fd + 1);
+    }
+    close_range(first, c_uint::MAX);
+}
+
+// Close the files from 'first' to 'last', the open files are looked up if the kernel has no close_range.
+fn close_range(first: c_uint, last: c_uint) {
+    if unsafe { libc::syscall(libc::SYS_close_range, first, last, 0) } == 0 {
+        return;
+    }
+
+    let fds: Vec<c_uint> = match fs::read_dir("/proc/self/fd") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .filter(|fd| (first..=last).contains(fd))
+            .collect(),
+        Err(_) => return,
+    };
+    for fd in fds {
+        unsafe {
+            libc::close(fd as c_int);
+        }
+    }
+}
+
+// Check the jobs, and return the descriptions of the ones that have finished since the last check.
+pub fn notify_done() -> Vec<String> {
+    session::with_jobs(|jobs| {
+        jobs.update();
+        jobs.take_done()
+    })
+}
@@ -1,8 +1,10 @@
 use std::cell::RefCell;
 
+use crate::builtin;
 use crate::lexer::state::State;
-use crate::token::token::{Token, TokenType};
+use crate::token::token::{Span, Token, TokenType};
 
+pub mod quote;
 pub mod state;
 
 // This lexer is designed based on the concept of FA (Finite Automata).
@@ -24,17 +26,53 @@ pub struct Lexer {
     // Current index of token vector.
     // This field is used to iterate the tokens.
     position: RefCell<usize>,
+
+    // The quotation mark that isn't closed at the end of the command, such as '"' in 'echo "a',
+    // or the ')' of the command substitution that isn't closed, such as the one in 'echo $(pwd'.
+    unclosed_quote: RefCell<Option<char>>,
+
+    // The command substitution that is being skipped.
+    substitution: RefCell<Substitution>,
+}
+
+// The command substitution in the word, its chars are skipped because they're run as the commands later.
+#[derive(Debug, Clone, Copy)]
+struct Substitution {
+    // The index of the char that closes it.
+    end: usize,
+    // The char that closes it, ')' or '`'.
+    closing: char,
+    // The state to go back to after it, which is different in double quotes.
+    outer: State,
 }
 
 impl Lexer {
     /// Creates a new [`Lexer`].
     pub fn new(command: &str) -> Lexer {
+        // The bodies of here-documents are not a part of the command,
+        // so they are cut out before analyzing, and attached to the tokens afterwards.
+        let (command, bodies) = Self::split_heredoc_bodies(command);
+
+        let l = Self::analyze(&command);
+        l.attach_heredoc_bodies(bodies);
+
+        l
+    }
+
+    // Create a lexer and analyze the command without handling here-documents.
+    fn analyze(command: &str) -> Lexer {
         let l = Lexer {
             command: command.chars().collect(),
             start_index: RefCell::new(0),
             tokens: RefCell::new(Vec::new()),
             cur_state: RefCell::new(State::Start),
             position: RefCell::new(0),
+            unclosed_quote: RefCell::new(None),
+            substitution: RefCell::new(Substitution {
+                end: 0,
+                closing: ')',
+                outer: State::Literal,
+            }),
         };
 
         l.analyze_command();
@@ -42,6 +80,88 @@ impl Lexer {
         l
     }
 
+    // Get the delimiters of the here-documents in the command, such as 'EOF' in 'cat <<EOF'.
+    // The REPL uses them to know how many lines should be read as the bodies.
+    pub fn heredoc_delimiters(&self) -> Vec<String> {
+        let tokens = self.tokens.borrow();
+
+        tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| *token.token_type() == TokenType::DoubleLessThan)
+            .filter_map(|(index, _)| Self::read_heredoc_delimiter(&tokens, index + 1))
+            .map(|(delimiter, _)| delimiter)
+            .collect()
+    }
+
+    // Read the delimiter at the index of tokens.
+    // The delimiter may be quoted, such as <<'EOF', the quotation marks are not a part of it.
+    // Return the delimiter and the index of the token after it.
+    fn read_heredoc_delimiter(tokens: &[Token], index: usize) -> Option<(String, usize)> {
+        match tokens.get(index)?.token_type() {
+            TokenType::Literal | TokenType::Variable | TokenType::Num => {
+                let delimiter = quote::unescape(&quote::unquote(tokens[index].literal()));
+                Some((delimiter, index + 1))
+            }
+            _ => None,
+        }
+    }
+
+    // Get the quotation mark that isn't closed at the end of the command.
+    // The REPL reads more lines until it's closed, like the other shells.
+    pub fn unclosed_quote(&self) -> Option<char> {
+        *self.unclosed_quote.borrow()
+    }
+
+    // Cut the bodies of the here-documents out of the command.
+    // Each body starts from the line after the command that uses it,
+    // and ends with a line that is the same as its delimiter.
+    fn split_heredoc_bodies(input: &str) -> (String, Vec<String>) {
+        let mut command = String::new();
+        let mut bodies: Vec<String> = Vec::new();
+
+        let mut lines = input.split_inclusive('\n');
+        while let Some(line) = lines.next() {
+            command.push_str(line);
+
+            for delimiter in Self::analyze(line).heredoc_delimiters() {
+                let mut body = String::new();
+                for line in lines.by_ref() {
+                    if line.trim_end_matches(['\n', '\r']) == delimiter {
+                        break;
+                    }
+                    body.push_str(line);
+                }
+                bodies.push(body);
+            }
+        }
+
+        (command, bodies)
+    }
+
+    // Insert the bodies as the tokens after the delimiters of the here-documents.
+    fn attach_heredoc_bodies(&self, bodies: Vec<String>) {
+        let mut tokens = self.tokens.borrow_mut();
+        let mut bodies = bodies.into_iter();
+
+        let mut index = 0;
+        while index < tokens.len() {
+            if *tokens[index].token_type() == TokenType::DoubleLessThan {
+                if let Some((_, next)) = Self::read_heredoc_delimiter(&tokens, index + 1) {
+                    // The body isn't in the command, so it's put at the end of the delimiter.
+                    let body = bodies.next().unwrap_or_default();
+                    let end = tokens[next - 1].span().end;
+                    tokens.insert(
+                        next,
+                        Token::with_span(TokenType::HereDoc, &body, Span::new(end, end)),
+                    );
+                    index = next;
+                }
+            }
+            index += 1;
+        }
+    }
+
     // Get the tokens by range.
     pub fn joint_tokens_to_str_by_range(&self, start: u32, end: u32) -> String {
         let tokens = self.tokens.borrow();
@@ -58,6 +178,31 @@ impl Lexer {
         result
     }
 
+    // Get the text of the command in the span, such as the command line of a background job.
+    pub fn text(&self, span: Span) -> String {
+        let end = span.end.min(self.command.len());
+        self.command[span.start.min(end)..end].iter().collect()
+    }
+
+    // Get the line of the command that the span starts in, and the column where it starts in the line.
+    // The parser uses it to show the line and point at the token that causes an error.
+    pub fn line_of(&self, span: Span) -> (String, usize) {
+        let start = span.start.min(self.command.len());
+        let line_start = self.command[..start]
+            .iter()
+            .rposition(|c| *c == '\n')
+            .map_or(0, |index| index + 1);
+        let line_end = self.command[start..]
+            .iter()
+            .position(|c| *c == '\n')
+            .map_or(self.command.len(), |index| start + index);
+
+        (
+            self.command[line_start..line_end].iter().collect(),
+            start - line_start,
+        )
+    }
+
     // Clear the lexer data.
     pub fn clear(&self) {
         self.tokens.borrow_mut().clear();
@@ -95,128 +240,144 @@ impl Lexer {
     fn analyze_command(&self) {
         // Iterate the command char by char.
         for (index, c) in self.command.iter().enumerate() {
-            let state = self.cur_state.borrow().clone();
+            let state = *self.cur_state.borrow();
             match state {
                 State::Start => self.trans_state(c),
 
-                // =============== ls command ===============
-                State::LsCommandState1 => {
-                    if c.eq(&'s') {
-                        *(self.cur_state.borrow_mut()) = State::LsCommandState;
+                // =============== number ===============
+                State::NumState => {
+                    if c.is_numeric() || (state == State::NumState && c.eq(&'_')) {
+                        *(self.cur_state.borrow_mut()) = State::NumState;
+                    } else if c.eq(&'>') {
+                        // The number before '>' is the file descriptor to redirect, such as '2>'.
+                        *(self.cur_state.borrow_mut()) = State::GreaterThanState;
+                    } else if c.is_alphabetic() || Self::quoting_state(c).is_some() {
+                        self.trans_to_literal(index, c);
                     } else {
-                        self.trans_state(c);
+                        self.store_token_and_trans_state(index, c);
                     }
                 }
 
-                State::LsCommandState => {
-                    self.store_token_and_trans_state(index, c);
-                }
-
-                // =============== cd command ===============
-                State::CdCommandState1 => {
-                    if c.eq(&'d') {
-                        *(self.cur_state.borrow_mut()) = State::CdCommandState;
-                    } else if c.eq(&'a') {
-                        *(self.cur_state.borrow_mut()) = State::CatCommandState2;
+                // =============== parameter ===============
+                // The options are words too, such as '-la', '-1' and '--line-number'.
+                State::ParamState => {
+                    if c.eq(&'-') {
+                        *(self.cur_state.borrow_mut()) = State::LongParamState1;
+                    } else if Self::is_literal_char(c) {
+                        *(self.cur_state.borrow_mut()) = State::ShortParamState;
                     } else {
-                        self.trans_state(c);
+                        // A single '-' such as the one in 'cd -'.
+                        self.store_token_and_trans_state(index, c);
                     }
                 }
 
-                State::CdCommandState => {
-                    self.store_token_and_trans_state(index, c);
+                State::ShortParamState => {
+                    if !Self::is_literal_char(c) {
+                        self.store_token_and_trans_state(index, c);
+                    }
                 }
 
-                // =============== cat command ===============
-                State::CatCommandState2 => {
-                    if c.eq(&'t') {
-                        *(self.cur_state.borrow_mut()) = State::CatCommandState;
+                // The reason of long parameter is divided into two states is that
+                // the long parameter requires at least two letters.
+                State::LongParamState1 => {
+                    if c.is_alphabetic() {
+                        *(self.cur_state.borrow_mut()) = State::LongParamState;
                     } else {
-                        self.trans_state(c);
+                        // A single '--' which means the end of options.
+                        self.store_token_and_trans_state(index, c);
                     }
                 }
 
-                State::CatCommandState => {
-                    self.store_token_and_trans_state(index, c);
+                // The value after '=' is another token, such as '3' in '--depth=3'.
+                State::LongParamState => {
+                    if !Self::is_literal_char(c) {
+                        self.store_token_and_trans_state(index, c);
+                    }
                 }
 
-                // =============== grep command ===============
-                State::GrepCommandState1 => {
-                    if c.eq(&'r') {
-                        *(self.cur_state.borrow_mut()) = State::GrepCommandState2;
+                // =============== Literal ===============
+                State::Literal => {
+                    self.trans_to_literal(index, c);
+                }
+
+                // =============== variable ===============
+                State::DollarState => {
+                    if c.eq(&'(') {
+                        self.skip_substitution(
+                            index,
+                            quote::substitution_end(&self.command, index + 1),
+                            ')',
+                        );
+                    } else if c.eq(&'?') {
+                        *(self.cur_state.borrow_mut()) = State::SpecialVariableState;
+                    } else if c.eq(&'{') {
+                        *(self.cur_state.borrow_mut()) = State::BraceVariableState;
+                    } else if c.is_alphabetic() || c.eq(&'_') {
+                        *(self.cur_state.borrow_mut()) = State::VariableState;
                     } else {
-                        self.trans_state(c);
+                        // A '$' that isn't followed by a variable name is a literal.
+                        self.trans_to_literal(index, c);
                     }
                 }
 
-                State::GrepCommandState2 => {
-                    if c.eq(&'e') {
-                        *(self.cur_state.borrow_mut()) = State::GrepCommandState3;
-                    } else {
-                        self.trans_state(c);
+                State::VariableState => {
+                    if !(c.is_alphanumeric() || c.eq(&'_')) {
+                        self.trans_to_literal(index, c);
                     }
                 }
 
-                State::GrepCommandState3 => {
-                    if c.eq(&'p') {
-                        *(self.cur_state.borrow_mut()) = State::GrepCommandState;
-                    } else {
-                        self.trans_state(c);
+                State::BraceVariableState => {
+                    if c.eq(&'}') {
+                        *(self.cur_state.borrow_mut()) = State::SpecialVariableState;
                     }
                 }
 
-                State::GrepCommandState => {
-                    self.store_token_and_trans_state(index, c);
+                State::SpecialVariableState => {
+                    self.trans_to_literal(index, c);
                 }
 
-                // =============== number ===============
-                State::NumState => {
-                    if c.is_numeric() || (state == State::NumState && c.eq(&'_')) {
-                        *(self.cur_state.borrow_mut()) = State::NumState;
-                    } else if c.is_alphabetic() {
-                        *(self.cur_state.borrow_mut()) = State::Literal;
-                    } else {
-                        self.store_token_and_trans_state(index, c);
-                    }
+                // =============== command substitution ===============
+                State::BackquoteState => {
+                    self.skip_substitution(index, quote::backquote_end(&self.command, index), '`');
                 }
 
-                // =============== parameter ===============
-                State::ParamState => {
-                    if c.is_alphabetic() {
-                        *(self.cur_state.borrow_mut()) = State::ShortParamState;
-                    } else if c.eq(&'-') {
-                        *(self.cur_state.borrow_mut()) = State::LongParamState1;
+                State::SubstitutionState => {
+                    if index == self.substitution.borrow().end {
+                        self.end_substitution();
                     }
                 }
 
-                State::ShortParamState => {
-                    self.store_token_and_trans_state(index, c);
+                // =============== quoting ===============
+                State::QuoteState => {
+                    if c.eq(&'"') {
+                        *(self.cur_state.borrow_mut()) = State::Literal;
+                    } else if c.eq(&'\\') {
+                        *(self.cur_state.borrow_mut()) = State::QuoteEscapeState;
+                    } else if c.eq(&'`') {
+                        self.substitution.borrow_mut().outer = State::QuoteState;
+                        *(self.cur_state.borrow_mut()) = State::BackquoteState;
+                    } else if c.eq(&'$') && self.command.get(index + 1) == Some(&'(') {
+                        self.substitution.borrow_mut().outer = State::QuoteState;
+                        self.skip_substitution(
+                            index,
+                            quote::substitution_end(&self.command, index + 2),
+                            ')',
+                        );
+                    }
                 }
 
-                // The reason of long parameter is divided into two states is that
-                // the long parameter requires at least two letters.
-                State::LongParamState1 => {
-                    if c.is_alphabetic() {
-                        *(self.cur_state.borrow_mut()) = State::LongParamState;
-                    }
+                State::QuoteEscapeState => {
+                    *(self.cur_state.borrow_mut()) = State::QuoteState;
                 }
 
-                State::LongParamState => {
-                    if !c.is_alphanumeric() {
-                        self.store_token_and_trans_state(index, c);
+                State::SingleQuoteState => {
+                    if c.eq(&'\'') {
+                        *(self.cur_state.borrow_mut()) = State::Literal;
                     }
                 }
 
-                // =============== Literal ===============
-                State::Literal => {
-                    if !(c.is_alphanumeric()
-                        || c.eq(&'_')
-                        || c.eq(&'-')
-                        || c.eq(&'/')
-                        || c.eq(&'.'))
-                    {
-                        self.store_token_and_trans_state(index, c);
-                    }
+                State::EscapeState => {
+                    *(self.cur_state.borrow_mut()) = State::Literal;
                 }
 
                 // =============== white space ===============
@@ -227,19 +388,35 @@ impl Lexer {
                 // =============== single symbols ===============
                 State::CommaState
                 | State::SemicolonState
-                | State::GreaterThanState
-                | State::LessThanState
                 | State::DotState
                 | State::ColonState
                 | State::AssignmentState
                 | State::SlashState
-                | State::StarState
                 | State::TildeState
-                | State::QuoteState
-                | State::SingleQuoteState => {
+                | State::LeftParenState
+                | State::RightParenState => {
                     self.store_token_and_trans_state(index, c);
                 }
 
+                // The braces of a group are words by themselves, such as '{ ls; }',
+                // they are a part of the word if other chars follow them, such as '{a'.
+                State::LeftBraceState | State::RightBraceState => {
+                    if Self::is_literal_char(c) || Self::quoting_state(c).is_some() || c.eq(&'$') {
+                        self.trans_to_literal(index, c);
+                    } else {
+                        self.store_token_and_trans_state(index, c);
+                    }
+                }
+
+                // A '*' followed by other chars of the word is a pattern, such as '*.rs'.
+                State::StarState => {
+                    if Self::is_literal_char(c) || Self::quoting_state(c).is_some() || c.eq(&'$') {
+                        self.trans_to_literal(index, c);
+                    } else {
+                        self.store_token_and_trans_state(index, c);
+                    }
+                }
+
                 // =============== combined symbols ===============
                 State::BackgroundState => {
                     if c.eq(&'&') {
@@ -257,7 +434,29 @@ impl Lexer {
                     }
                 }
 
-                State::AndState | State::OrState => {
+                State::GreaterThanState => {
+                    if c.eq(&'>') {
+                        *self.cur_state.borrow_mut() = State::DoubleGreaterThanState;
+                    } else if c.eq(&'&') {
+                        *self.cur_state.borrow_mut() = State::GreaterThanAndState;
+                    } else {
+                        self.store_token_and_trans_state(index, c);
+                    }
+                }
+
+                State::LessThanState => {
+                    if c.eq(&'<') {
+                        *self.cur_state.borrow_mut() = State::DoubleLessThanState;
+                    } else {
+                        self.store_token_and_trans_state(index, c);
+                    }
+                }
+
+                State::DoubleGreaterThanState
+                | State::GreaterThanAndState
+                | State::DoubleLessThanState
+                | State::AndState
+                | State::OrState => {
                     self.store_token_and_trans_state(index, c);
                 }
 
@@ -267,44 +466,63 @@ impl Lexer {
         }
 
         // If the lexer's state is not end, we need to store the last token.
-        let state = self.cur_state.borrow().clone();
+        let state = *self.cur_state.borrow();
+        *self.unclosed_quote.borrow_mut() = match state {
+            State::QuoteState | State::QuoteEscapeState => Some('"'),
+            State::SingleQuoteState => Some('\''),
+            State::BackquoteState => Some('`'),
+            State::SubstitutionState => Some(self.substitution.borrow().closing),
+            _ => None,
+        };
+        // Store the last token, the state is still 'start' only if the command is empty,
+        // and then there is only the EOF token.
         if state != State::End {
-            // Determine if the state is 'start' to ensure completion of the last token parsing.
-            // If it's 'start', change the state accordingly;
-            // otherwise, store the last token with the current state.
-            //
-            // For the string "&&&", the first token is "&&" and the second is "&".
-            // When parsing the last "&" token, the state reverts to 'start' before "&&" is stored.
-            // Therefore, it's necessary to adjust the state appropriately and store the "&" token.
-            if state == State::Start {
-                self.trans_state(&self.command[self.start_index.borrow().clone()]);
-            }
             self.store_token_and_trans_state(self.command.len(), &' ');
         }
+
+        self.classify_keywords();
+    }
+
+    // Turn the words at the position of a command into the token types of the commands, such as 'ls' in 'a; ls'.
+    // The other words stay literals, such as 'ls' in 'echo ls', and so do the quoted words, such as '"ls"'.
+    // The names of the commands are looked up in the registry of the builtin commands, see [`crate::builtin`].
+    fn classify_keywords(&self) {
+        let mut tokens = self.tokens.borrow_mut();
+
+        let mut command_position = true;
+        for token in tokens.iter_mut() {
+            let token_type = token.token_type().clone();
+            if command_position && token_type == TokenType::Literal {
+                if let Some(builtin) = builtin::lookup(token.literal()) {
+                    let token_type = builtin.token_type().clone();
+                    *token = Token::with_span(token_type, token.literal(), token.span());
+                }
+            }
+
+            command_position = is_command_position_after(command_position, &token_type);
+        }
     }
 
     // Store token and transform state.
     fn store_token_and_trans_state(&self, cur_index: usize, cur_char: &char) {
-        let state = self.cur_state.borrow().clone();
+        let state = *self.cur_state.borrow();
         let mut start_index = self.start_index.borrow_mut();
 
         // Move start index to end index for ready to read next token.
         *start_index = self.move_index_to_next_non_blank_char(*start_index);
 
         // Get the literal of token from char vector.
+        let span = Span::new(*start_index, cur_index);
         let literal: String = self.command[*start_index..cur_index].iter().collect();
         *start_index = cur_index;
 
-        if !(state == State::WhiteSpace) {
+        // There is no token in the blanks, or in the empty command.
+        if !matches!(state, State::WhiteSpace | State::Start | State::End) {
             // Match the state to get the token type.
             let token_type = match state {
-                // =============== command ===============
-                State::LsCommandState => TokenType::Ls,
-                State::CdCommandState => TokenType::Cd,
-                State::GrepCommandState => TokenType::Grep,
-                State::CatCommandState => TokenType::Cat,
-
                 // =============== parameter ===============
+                State::ParamState => TokenType::Minus,
+                State::LongParamState1 => TokenType::DoubleMinus,
                 State::ShortParamState => TokenType::ShortParam,
                 State::LongParamState => TokenType::LongParam,
 
@@ -320,25 +538,41 @@ impl Lexer {
                 State::StarState => TokenType::Star,
                 State::BackgroundState => TokenType::Background,
                 State::TildeState => TokenType::Tilde,
-                State::QuoteState => TokenType::Quote,
-                State::SingleQuoteState => TokenType::SingleQuote,
                 State::PipeState => TokenType::Pipe,
+                State::LeftParenState => TokenType::LeftParen,
+                State::RightParenState => TokenType::RightParen,
+                State::LeftBraceState => TokenType::LeftBrace,
+                State::RightBraceState => TokenType::RightBrace,
 
                 // =============== combined symbols ===============
+                State::DoubleGreaterThanState => TokenType::DoubleGreaterThan,
+                State::GreaterThanAndState => TokenType::GreaterThanAnd,
+                State::DoubleLessThanState => TokenType::DoubleLessThan,
                 State::AndState => TokenType::And,
                 State::OrState => TokenType::Or,
 
                 // =============== literal ===============
-                State::Literal => TokenType::Literal,
+                State::Literal
+                | State::QuoteState
+                | State::QuoteEscapeState
+                | State::SingleQuoteState
+                | State::EscapeState
+                | State::DollarState
+                | State::VariableState
+                | State::BraceVariableState
+                | State::SpecialVariableState
+                | State::BackquoteState
+                | State::SubstitutionState => Self::classify_word(&literal),
 
                 // =============== number ===============
                 State::NumState => TokenType::Num,
-                _ => todo!(),
+
+                State::WhiteSpace | State::Start | State::End => unreachable!(),
             };
 
             self.tokens
                 .borrow_mut()
-                .push(Token::new(token_type, &literal));
+                .push(Token::with_span(token_type, &literal, span));
         }
 
         // Judge whether the state should be reset or be end.
@@ -347,13 +581,101 @@ impl Lexer {
             // *state = State::Start;
             self.trans_state(cur_char);
         } else {
-            *start_index = self.command.len() - 1;
+            *start_index = self.command.len().saturating_sub(1);
             *self.cur_state.borrow_mut() = State::End;
 
             // Add a EOF token to the end for the parser to determine the end of the command.
-            self.tokens
-                .borrow_mut()
-                .push(Token::new(TokenType::Eof, ""));
+            // It's put right after the last word, so an error about the missing word points there.
+            let end = self
+                .command
+                .iter()
+                .rposition(|c| !c.is_whitespace())
+                .map_or(0, |index| index + 1);
+            self.tokens.borrow_mut().push(Token::with_span(
+                TokenType::Eof,
+                "",
+                Span::new(end, end),
+            ));
+        }
+    }
+
+    // The chars read so far don't match a command, such as 'c' or 'gr', so they are a literal.
+    // A variable can be a part of the word, such as 'bin:$PATH', and so can the quoted chars, such as 'a"b c"'.
+    // A word such as 'NAME=value' is an assignment, the value is a part of it.
+    fn trans_to_literal(&self, cur_index: usize, cur_char: &char) {
+        *self.cur_state.borrow_mut() = State::Literal;
+
+        if let Some(state) = Self::quoting_state(cur_char) {
+            *self.cur_state.borrow_mut() = state;
+        } else if cur_char.eq(&'$') {
+            *self.cur_state.borrow_mut() = State::DollarState;
+        } else if cur_char.eq(&'=') && self.is_name_before(cur_index) {
+            // Keep the '=' in the word.
+        } else if !Self::is_literal_char(cur_char) {
+            self.store_token_and_trans_state(cur_index, cur_char);
+        }
+    }
+
+    // Get the state that the char starts quoting with, the command substitution '`...`' is quoted alike.
+    fn quoting_state(c: &char) -> Option<State> {
+        match c {
+            '"' => Some(State::QuoteState),
+            '\'' => Some(State::SingleQuoteState),
+            '\\' => Some(State::EscapeState),
+            '`' => Some(State::BackquoteState),
+            _ => None,
+        }
+    }
+
+    // Skip the command substitution that starts at the index, the end is None if it isn't closed.
+    fn skip_substitution(&self, index: usize, end: Option<usize>, closing: char) {
+        let mut substitution = self.substitution.borrow_mut();
+        substitution.closing = closing;
+        substitution.end = end.unwrap_or(usize::MAX);
+        drop(substitution);
+
+        if end == Some(index) {
+            self.end_substitution();
+        } else {
+            *self.cur_state.borrow_mut() = State::SubstitutionState;
+        }
+    }
+
+    // Go back to the state before the command substitution, the word goes on after it.
+    fn end_substitution(&self) {
+        let mut substitution = self.substitution.borrow_mut();
+        *self.cur_state.borrow_mut() = substitution.outer;
+        substitution.outer = State::Literal;
+    }
+
+    // Whether the char can be a part of a literal.
+    // The chars of the patterns are a part of it too, such as 'src/*.rs' and 'file[!0-9]?'.
+    fn is_literal_char(c: &char) -> bool {
+        c.is_alphanumeric()
+            || matches!(
+                c,
+                '_' | '-' | '/' | '.' | ':' | '~' | '*' | '?' | '[' | ']' | '!' | '^'
+            )
+    }
+
+    // Whether the current word before the index is a variable name or an assignment,
+    // which means the '=' at the index is a part of the word.
+    fn is_name_before(&self, index: usize) -> bool {
+        let start = self.move_index_to_next_non_blank_char(*self.start_index.borrow());
+        let word: String = self.command[start.min(index)..index].iter().collect();
+
+        // The value after the first '=' may contain '=' too.
+        is_variable_name(word.split('=').next().unwrap_or_default())
+    }
+
+    // Get the token type of the word.
+    // 'NAME=value' is an assignment, and a word starting with a variable such as '$HOME/bin' is a variable,
+    // so is the one starting with a command substitution such as '$(pwd)/bin' and '`pwd`/bin'.
+    fn classify_word(word: &str) -> TokenType {
+        match word.split_once('=') {
+            Some((name, _)) if is_variable_name(name) => TokenType::AssignmentWord,
+            _ if word.starts_with(['$', '`']) && word.len() > 1 => TokenType::Variable,
+            _ => TokenType::Literal,
         }
     }
 
@@ -388,10 +710,9 @@ impl Lexer {
             return;
         }
 
+        // The words start as literals whatever they are, the command names among them are found afterwards,
+        // see [`classify_keywords`].
         match c {
-            'l' => *state = State::LsCommandState1,
-            'c' => *state = State::CdCommandState1,
-            'g' => *state = State::GrepCommandState1,
             '0'..='9' => *state = State::NumState,
             '-' => {
                 if *state == State::Start || *state == State::WhiteSpace {
@@ -412,14 +733,70 @@ impl Lexer {
             '*' => *state = State::StarState,
             '&' => *state = State::BackgroundState,
             '~' => *state = State::TildeState,
-            '"' => *state = State::QuoteState,
-            '\'' => *state = State::SingleQuoteState,
-            '_' => {
-                if *state == State::StarState || *state == State::WhiteSpace {
-                    *state = State::Literal;
+            '(' => *state = State::LeftParenState,
+            ')' => *state = State::RightParenState,
+            // The braces only start a group at the start of a word, such as '{ ls; }',
+            // otherwise they are literals, such as 'a}'.
+            '{' | '}' => {
+                *state = match (*state, c) {
+                    (
+                        State::Start
+                        | State::WhiteSpace
+                        | State::SemicolonState
+                        | State::BackgroundState
+                        | State::AndState
+                        | State::OrState
+                        | State::PipeState
+                        | State::LeftParenState
+                        | State::RightParenState,
+                        '{',
+                    ) => State::LeftBraceState,
+                    (
+                        State::Start
+                        | State::WhiteSpace
+                        | State::SemicolonState
+                        | State::BackgroundState
+                        | State::RightParenState
+                        | State::RightBraceState,
+                        '}',
+                    ) => State::RightBraceState,
+                    _ => State::Literal,
                 }
             }
+            '"' => *state = State::QuoteState,
+            '\'' => *state = State::SingleQuoteState,
+            '\\' => *state = State::EscapeState,
+            '`' => *state = State::BackquoteState,
+            '$' => *state = State::DollarState,
             _ => *state = State::Literal,
         }
     }
 }
+
+// Whether a command can be at the position after the token, the position of the token is given.
+// A command follows the operators, and the assignments before it, such as 'A=1 ls'.
+pub fn is_command_position_after(command_position: bool, token_type: &TokenType) -> bool {
+    match token_type {
+        TokenType::Pipe
+        | TokenType::Semicolon
+        | TokenType::Background
+        | TokenType::And
+        | TokenType::Or
+        | TokenType::LeftParen
+        | TokenType::LeftBrace => true,
+        TokenType::AssignmentWord => command_position,
+        _ => false,
+    }
+}
+
+// Whether the name is a valid variable name,
+// which consists of letters, digits and underscores, and doesn't start with a digit.
+pub fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
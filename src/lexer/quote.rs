@@ -0,0 +1,246 @@
+// The quoting of the words.
+//
+// The lexer keeps the quotes in the words, such as '"hello world"', and the parser removes them
+// with [`unquote`] when it stores the words in the AST.
+// The quotes are replaced by backslashes before the chars that would be special otherwise,
+// so the later steps only need to know one way of quoting:
+//     '...' : all chars are literal
+//     "..." : all chars are literal except '$', '`' and '\', which escapes '$', '`', '"', '\' and newline
+//     \c    : the char c is literal, and a backslash before a newline joins the lines
+//
+// For example, 'a"$HOME *"' becomes 'a"$HOME \*"', where '$HOME' is still expanded and '*' isn't a pattern.
+// The double quotes are kept around the text that has expansions, so that their results aren't split into words,
+// and they're removed with the backslashes after the expansions, see [`unescape`].
+// The command substitutions '$(...)' and '`...`' are kept as they are, they're run as the commands later.
+
+// Whether the char has a special meaning when it isn't quoted, so it's escaped when it's quoted.
+fn is_special_char(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '"' | '$' | '`' | '*' | '?' | '[' | ']' | '~' | '='
+    )
+}
+
+// Remove the quotes of the word, and escape the chars that are quoted.
+pub fn unquote(word: &str) -> String {
+    let chars: Vec<char> = word.chars().collect();
+    let mut unquoted = String::new();
+
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => {
+                index += 1;
+                match chars.get(index) {
+                    Some('\n') => {}
+                    Some(c) => escape_char(&mut unquoted, *c),
+                    None => unquoted.push('\\'),
+                }
+            }
+            '\'' => {
+                index += 1;
+                while index < chars.len() && chars[index] != '\'' {
+                    escape_char(&mut unquoted, chars[index]);
+                    index += 1;
+                }
+            }
+            '"' => {
+                // The text is put back in double quotes if it has any expansion.
+                let mut quoted = String::new();
+                let mut has_expansion = false;
+                index += 1;
+                while index < chars.len() && chars[index] != '"' {
+                    match chars[index] {
+                        '\\' => {
+                            index += 1;
+                            match chars.get(index) {
+                                Some('\n') => {}
+                                Some(c) if matches!(c, '$' | '`' | '"' | '\\') => {
+                                    escape_char(&mut quoted, *c)
+                                }
+                                Some(c) => {
+                                    escape_char(&mut quoted, '\\');
+                                    escape_char(&mut quoted, *c);
+                                }
+                                None => escape_char(&mut quoted, '\\'),
+                            }
+                        }
+                        '$' | '`' => {
+                            has_expansion = true;
+                            index = push_substitution(&mut quoted, &chars, index);
+                        }
+                        c => escape_char(&mut quoted, c),
+                    }
+                    index += 1;
+                }
+
+                if has_expansion {
+                    unquoted.push('"');
+                    unquoted.push_str(&quoted);
+                    unquoted.push('"');
+                } else {
+                    unquoted.push_str(&quoted);
+                }
+            }
+            '$' | '`' => index = push_substitution(&mut unquoted, &chars, index),
+            c => unquoted.push(c),
+        }
+        index += 1;
+    }
+
+    unquoted
+}
+
+// Escape the text of a here-document, whose chars are literal except '$', '`' and '\',
+// just like the ones in double quotes, but the quotation marks are literal too.
+pub fn escape_heredoc(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::new();
+
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => {
+                index += 1;
+                match chars.get(index) {
+                    Some('\n') => {}
+                    Some(c) if matches!(c, '$' | '`' | '\\') => escape_char(&mut escaped, *c),
+                    Some(c) => {
+                        escape_char(&mut escaped, '\\');
+                        escape_char(&mut escaped, *c);
+                    }
+                    None => escape_char(&mut escaped, '\\'),
+                }
+            }
+            '$' | '`' => index = push_substitution(&mut escaped, &chars, index),
+            c => escape_char(&mut escaped, c),
+        }
+        index += 1;
+    }
+
+    escaped
+}
+
+// Push the '$' or '`' at the index, the whole command substitution is pushed as it is if it starts there.
+// Return the index of the last char that is pushed.
+fn push_substitution(s: &mut String, chars: &[char], index: usize) -> usize {
+    let end = match chars[index] {
+        '`' => backquote_end(chars, index + 1),
+        _ if chars.get(index + 1) == Some(&'(') => substitution_end(chars, index + 2),
+        _ => None,
+    };
+
+    match end {
+        Some(end) => {
+            s.extend(&chars[index..=end]);
+            end
+        }
+        None if chars[index] == '`' => {
+            escape_char(s, '`');
+            index
+        }
+        None => {
+            s.push('$');
+            index
+        }
+    }
+}
+
+// Find the ')' that closes the command substitution '$(...)', the search starts after the '('.
+// The parentheses in the quotes and the nested substitutions are skipped.
+pub fn substitution_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 1;
+
+    let mut index = start;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '\'' => index += chars[index + 1..].iter().position(|c| *c == '\'')? + 1,
+            '"' => index = double_quote_end(chars, index + 1)?,
+            '`' => index = backquote_end(chars, index + 1)?,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    None
+}
+
+// Find the '`' that closes the command substitution '`...`', the search starts after the opening one.
+pub fn backquote_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut index = start;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '`' => return Some(index),
+            _ => {}
+        }
+        index += 1;
+    }
+
+    None
+}
+
+// Find the '"' that closes the double quotes, the search starts after the opening one.
+fn double_quote_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut index = start;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '"' => return Some(index),
+            '`' => index = backquote_end(chars, index + 1)?,
+            '$' if chars.get(index + 1) == Some(&'(') => {
+                index = substitution_end(chars, index + 2)?
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    None
+}
+
+// Escape the special chars of the text, so that all of them are literal.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        escape_char(&mut escaped, c);
+    }
+
+    escaped
+}
+
+// Remove the backslashes that escape the chars, and the double quotes around the expansions.
+pub fn unescape(word: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = word.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next().unwrap_or('\\')),
+            '"' => {}
+            _ => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+// Whether any part of the word is quoted or escaped.
+pub fn is_quoted(word: &str) -> bool {
+    word.contains(['\'', '"', '\\'])
+}
+
+fn escape_char(s: &mut String, c: char) {
+    if is_special_char(c) {
+        s.push('\\');
+    }
+    s.push(c);
+}
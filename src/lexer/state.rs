@@ -5,25 +5,6 @@ pub enum State {
 
     Literal,
 
-    // =============== command ===============
-    // ls command
-    LsCommandState1,
-    LsCommandState,
-
-    // cd command
-    CdCommandState1,
-    CdCommandState,
-
-    // grep command
-    GrepCommandState1,
-    GrepCommandState2,
-    GrepCommandState3,
-    GrepCommandState,
-
-    // cat command
-    CatCommandState2,
-    CatCommandState,
-
     // number
     NumState,
 
@@ -35,6 +16,32 @@ pub enum State {
     LongParamState1,
     LongParamState,
 
+    // Variable: '$' followed by the name of the variable.
+    // The variable is a part of the word, such as '$HOME/bin', so the word goes on after it.
+    DollarState,
+    // variable name ($NAME)
+    VariableState,
+    // variable name in braces (${NAME})
+    BraceVariableState,
+    // special variable ($?), or the end of the variable in braces
+    SpecialVariableState,
+
+    // Command substitution: the output of the commands is a part of the word, such as '$(pwd)/bin'.
+    // the char after the opening backquote (`)
+    BackquoteState,
+    // the chars of the substitution ($(...) or `...`) until its end
+    SubstitutionState,
+
+    // Quoting: the quoted chars are a part of the word, such as '"hello world"' and 'a\ b'.
+    // double quotes ("...")
+    QuoteState,
+    // backslash in double quotes ("\)
+    QuoteEscapeState,
+    // single quotes ('...')
+    SingleQuoteState,
+    // backslash (\)
+    EscapeState,
+
     // Single Symbols
     PipeState,        // |
     CommaState,       // ,
@@ -48,10 +55,15 @@ pub enum State {
     StarState,        // *
     BackgroundState,  // &
     TildeState,       // ~
-    QuoteState,       // "
-    SingleQuoteState, // '
+    LeftParenState,   // (
+    RightParenState,  // )
+    LeftBraceState,   // {
+    RightBraceState,  // }
 
     // Combined Symbols
+    DoubleGreaterThanState, // >>
+    GreaterThanAndState,    // >&
+    DoubleLessThanState,    // <<
     AndState,
     // &&
     OrState, // ||
@@ -1,7 +1,13 @@
+pub mod builtin;
+pub mod editor;
 pub mod executor;
 pub mod file_operator;
+pub mod history;
+pub mod jobs;
 pub mod lexer;
 pub mod parser;
+pub mod prompt;
 pub mod repl;
+pub mod session;
 pub mod stream;
 pub mod token;
@@ -4,19 +4,29 @@ use crate::token::token::TokenType;
 pub enum CommandType {
     ExtCommand,
     ChainCommand,
+    RedirectCommand,
+    ListCommand,
+    BackgroundCommand,
+    AssignmentCommand,
+    SubshellCommand,
+    GroupCommand,
 }
 
 // This trait is used to define the command,
-pub trait CommandAstNode: std::fmt::Debug {
+// the nodes can be sent to other threads, such as the commands of a pipeline.
+pub trait CommandAstNode: std::fmt::Debug + Send {
     // Get the command token type.
     fn token_type(&self) -> &TokenType;
 
+    // Get the literal of the command token, such as the name of the builtin command.
+    fn literal(&self) -> &str;
+
     // Get Command type.
     fn cmd_type(&self) -> &CommandType;
 
-    /// Only commands of the [`ExtCommand`] type have options and values.
-    /// The following functions: [`set_options`], [`get_option`], [`set_values`], and [`clone_ext_cmd`]
-    /// are used to set and retrieve the options and values for an execute command.
+    // Only commands of the [`ExtCommand`] type have options and values.
+    // The following functions: [`set_options`], [`get_option`], [`set_values`], and [`clone_ext_cmd`]
+    // are used to set and retrieve the options and values for an execute command.
 
     // Set the command option.
     fn set_options(&mut self, options: Vec<(String, String)>);
@@ -33,9 +43,22 @@ pub trait CommandAstNode: std::fmt::Debug {
     // Get the command values.
     fn get_values(&self) -> Option<Vec<String>>;
 
-    /// Only commands of the [`ChainCommand`] type have a data source and a data destination.
-    /// The following functions: [`set_source`] and [`set_destination`]
-    /// are used to set the data source and data destination for a chain command.
+    // Remove the command values, so that they can be replaced after being expanded.
+    fn clear_values(&mut self);
+
+    // Only commands of the [`ChainCommand`] type have a data source and a data destination.
+    // The following functions: [`set_source`] and [`set_destination`]
+    // are used to set the data source and data destination for a chain command.
+    // Commands of the [`RedirectCommand`] type use the data source as the redirected command,
+    // the file descriptor is stored as the option 'fd' and the target is stored as the value.
+    // Commands of the [`ListCommand`] type run the data destination after the data source,
+    // depending on whether the data source succeeded, such as 'a && b' and 'a || b'.
+    // Commands of the [`BackgroundCommand`] type run the data source in the background,
+    // the command line of the data source is stored as the value.
+    // Commands of the [`AssignmentCommand`] type store the assignments as the values,
+    // and the command that runs with the variables as the data source, which is optional.
+    // Commands of the [`SubshellCommand`] and [`GroupCommand`] types use the data source as the commands inside,
+    // such as 'cd dir && ls' in '(cd dir && ls)' and 'ls; cat f' in '{ ls; cat f; }'.
 
     /// Set the data source from the command whose type is [`ExtCommand`].
     fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>);
@@ -57,4 +80,4 @@ impl Clone for Box<dyn CommandAstNode> {
     fn clone(&self) -> Box<dyn CommandAstNode> {
         self.clone_cmd()
     }
-}
\ No newline at end of file
+}
@@ -29,6 +29,10 @@ impl CommandAstNode for ExeCommandAstNode {
         self.token.token_type()
     }
 
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
     fn cmd_type(&self) -> &CommandType {
         &self.command_type
     }
@@ -63,6 +67,10 @@ impl CommandAstNode for ExeCommandAstNode {
         self.values.clone()
     }
 
+    fn clear_values(&mut self) {
+        self.values = None;
+    }
+
     fn set_source(&mut self, _values: Option<Box<dyn CommandAstNode>>) {}
 
     fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
@@ -115,6 +123,197 @@ impl CommandAstNode for ChainCommandAstNode {
         self.token.token_type()
     }
 
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
+    fn cmd_type(&self) -> &CommandType {
+        &self.command_type
+    }
+
+    fn set_options(&mut self, _options: Vec<(String, String)>) {}
+
+    fn get_option(&self, _option: &str) -> Option<&str> {
+        None
+    }
+
+    fn add_value(&mut self, _value: String) {}
+
+    fn set_values(&mut self, _values: Vec<String>) {}
+
+    fn get_values(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn clear_values(&mut self) {}
+
+    fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_source = values;
+    }
+
+    fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_source.clone()
+    }
+
+    fn set_destination(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_destination = values;
+    }
+
+    fn get_destination(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_destination.clone()
+    }
+
+    fn clone_cmd(&self) -> Box<dyn CommandAstNode> {
+        Box::new(self.clone())
+    }
+}
+
+// The redirect command changes where the output of its data source goes,
+// such as 'ls > out.txt', 'ls >> out.txt', 'ls 2> err.txt' and 'ls 2>&1'.
+// Each node holds one redirection, a command with several redirections is nested,
+// the first redirection is the outermost node because redirections are applied from left to right.
+#[derive(Debug)]
+pub struct RedirectCommandAstNode {
+    command_type: CommandType,
+    token: Token,
+    option: HashMap<String, String>,
+    values: Option<Vec<String>>,
+    data_source: Option<Box<dyn CommandAstNode>>,
+}
+
+impl RedirectCommandAstNode {
+    pub fn new(token: Token) -> Self {
+        RedirectCommandAstNode {
+            token,
+            command_type: CommandType::RedirectCommand,
+            option: HashMap::new(),
+            values: None,
+            data_source: None,
+        }
+    }
+}
+
+impl Clone for RedirectCommandAstNode {
+    fn clone(&self) -> Self {
+        Self {
+            command_type: self.command_type.clone(),
+            token: self.token.clone(),
+            option: self.option.clone(),
+            values: self.values.clone(),
+            data_source: self.data_source.clone(),
+        }
+    }
+}
+
+impl CommandAstNode for RedirectCommandAstNode {
+    fn token_type(&self) -> &TokenType {
+        self.token.token_type()
+    }
+
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
+    fn cmd_type(&self) -> &CommandType {
+        &self.command_type
+    }
+
+    fn set_options(&mut self, options: Vec<(String, String)>) {
+        for (option, value) in options {
+            self.option.insert(option, value);
+        }
+    }
+
+    fn get_option(&self, option: &str) -> Option<&str> {
+        self.option.get(option).map(|s| s.as_str())
+    }
+
+    fn add_value(&mut self, value: String) {
+        if let Some(values) = &mut self.values {
+            values.push(value);
+        } else {
+            self.values = Some(vec![value]);
+        }
+    }
+
+    fn set_values(&mut self, values: Vec<String>) {
+        if let Some(self_value) = &mut self.values {
+            self_value.extend(values);
+        } else {
+            self.values = Some(values);
+        }
+    }
+
+    fn get_values(&self) -> Option<Vec<String>> {
+        self.values.clone()
+    }
+
+    fn clear_values(&mut self) {
+        self.values = None;
+    }
+
+    fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_source = values;
+    }
+
+    fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_source.clone()
+    }
+
+    fn set_destination(&mut self, _values: Option<Box<dyn CommandAstNode>>) {}
+
+    fn get_destination(&self) -> Option<Box<dyn CommandAstNode>> {
+        None
+    }
+
+    fn clone_cmd(&self) -> Box<dyn CommandAstNode> {
+        Box::new(self.clone())
+    }
+}
+
+// The list command runs two commands one after the other, such as 'a && b' and 'a || b',
+// and 'a; b' in a subshell or a group.
+// The data source is the command on the left and the data destination is the one on the right,
+// but unlike the chain command, no data is transferred between them.
+#[derive(Debug)]
+pub struct ListCommandAstNode {
+    command_type: CommandType,
+    token: Token,
+    data_source: Option<Box<dyn CommandAstNode>>,
+    data_destination: Option<Box<dyn CommandAstNode>>,
+}
+
+impl ListCommandAstNode {
+    pub fn new(token: Token) -> Self {
+        ListCommandAstNode {
+            token,
+            command_type: CommandType::ListCommand,
+            data_source: None,
+            data_destination: None,
+        }
+    }
+}
+
+impl Clone for ListCommandAstNode {
+    fn clone(&self) -> Self {
+        Self {
+            command_type: self.command_type.clone(),
+            token: self.token.clone(),
+            data_source: self.data_source.clone(),
+            data_destination: self.data_destination.clone(),
+        }
+    }
+}
+
+impl CommandAstNode for ListCommandAstNode {
+    fn token_type(&self) -> &TokenType {
+        self.token.token_type()
+    }
+
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
     fn cmd_type(&self) -> &CommandType {
         &self.command_type
     }
@@ -133,6 +332,8 @@ impl CommandAstNode for ChainCommandAstNode {
         None
     }
 
+    fn clear_values(&mut self) {}
+
     fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
         self.data_source = values;
     }
@@ -153,3 +354,348 @@ impl CommandAstNode for ChainCommandAstNode {
         Box::new(self.clone())
     }
 }
+
+// The background command runs its data source in the background, such as 'sleep 10 &'.
+// The command line of the data source is stored as the value, it's shown in the job table.
+#[derive(Debug)]
+pub struct BackgroundCommandAstNode {
+    command_type: CommandType,
+    token: Token,
+    values: Option<Vec<String>>,
+    data_source: Option<Box<dyn CommandAstNode>>,
+}
+
+impl BackgroundCommandAstNode {
+    pub fn new(token: Token) -> Self {
+        BackgroundCommandAstNode {
+            token,
+            command_type: CommandType::BackgroundCommand,
+            values: None,
+            data_source: None,
+        }
+    }
+}
+
+impl Clone for BackgroundCommandAstNode {
+    fn clone(&self) -> Self {
+        Self {
+            command_type: self.command_type.clone(),
+            token: self.token.clone(),
+            values: self.values.clone(),
+            data_source: self.data_source.clone(),
+        }
+    }
+}
+
+impl CommandAstNode for BackgroundCommandAstNode {
+    fn token_type(&self) -> &TokenType {
+        self.token.token_type()
+    }
+
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
+    fn cmd_type(&self) -> &CommandType {
+        &self.command_type
+    }
+
+    fn set_options(&mut self, _options: Vec<(String, String)>) {}
+
+    fn get_option(&self, _option: &str) -> Option<&str> {
+        None
+    }
+
+    fn add_value(&mut self, value: String) {
+        if let Some(values) = &mut self.values {
+            values.push(value);
+        } else {
+            self.values = Some(vec![value]);
+        }
+    }
+
+    fn set_values(&mut self, values: Vec<String>) {
+        if let Some(self_value) = &mut self.values {
+            self_value.extend(values);
+        } else {
+            self.values = Some(values);
+        }
+    }
+
+    fn get_values(&self) -> Option<Vec<String>> {
+        self.values.clone()
+    }
+
+    fn clear_values(&mut self) {
+        self.values = None;
+    }
+
+    fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_source = values;
+    }
+
+    fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_source.clone()
+    }
+
+    fn set_destination(&mut self, _values: Option<Box<dyn CommandAstNode>>) {}
+
+    fn get_destination(&self) -> Option<Box<dyn CommandAstNode>> {
+        None
+    }
+
+    fn clone_cmd(&self) -> Box<dyn CommandAstNode> {
+        Box::new(self.clone())
+    }
+}
+
+// The assignment command sets the variables, such as 'A=1 B=2'.
+// The assignments are stored as the values, and the command after them is stored as the data source,
+// such as 'FOO=1 env', in which case the variables are only set for that command.
+#[derive(Debug)]
+pub struct AssignmentCommandAstNode {
+    command_type: CommandType,
+    token: Token,
+    values: Option<Vec<String>>,
+    data_source: Option<Box<dyn CommandAstNode>>,
+}
+
+impl AssignmentCommandAstNode {
+    pub fn new(token: Token) -> Self {
+        AssignmentCommandAstNode {
+            token,
+            command_type: CommandType::AssignmentCommand,
+            values: None,
+            data_source: None,
+        }
+    }
+}
+
+impl Clone for AssignmentCommandAstNode {
+    fn clone(&self) -> Self {
+        Self {
+            command_type: self.command_type.clone(),
+            token: self.token.clone(),
+            values: self.values.clone(),
+            data_source: self.data_source.clone(),
+        }
+    }
+}
+
+impl CommandAstNode for AssignmentCommandAstNode {
+    fn token_type(&self) -> &TokenType {
+        self.token.token_type()
+    }
+
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
+    fn cmd_type(&self) -> &CommandType {
+        &self.command_type
+    }
+
+    fn set_options(&mut self, _options: Vec<(String, String)>) {}
+
+    fn get_option(&self, _option: &str) -> Option<&str> {
+        None
+    }
+
+    fn add_value(&mut self, value: String) {
+        if let Some(values) = &mut self.values {
+            values.push(value);
+        } else {
+            self.values = Some(vec![value]);
+        }
+    }
+
+    fn set_values(&mut self, values: Vec<String>) {
+        if let Some(self_value) = &mut self.values {
+            self_value.extend(values);
+        } else {
+            self.values = Some(values);
+        }
+    }
+
+    fn get_values(&self) -> Option<Vec<String>> {
+        self.values.clone()
+    }
+
+    fn clear_values(&mut self) {
+        self.values = None;
+    }
+
+    fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_source = values;
+    }
+
+    fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_source.clone()
+    }
+
+    fn set_destination(&mut self, _values: Option<Box<dyn CommandAstNode>>) {}
+
+    fn get_destination(&self) -> Option<Box<dyn CommandAstNode>> {
+        None
+    }
+
+    fn clone_cmd(&self) -> Box<dyn CommandAstNode> {
+        Box::new(self.clone())
+    }
+}
+
+// The subshell command runs the commands in parentheses in a copy of the shell, such as '(cd dir && ls)',
+// so the changes they make, such as the working directory and the variables, don't affect the shell.
+// The commands are stored as the data source, they're joined by ';' lists if there are more than one.
+#[derive(Debug)]
+pub struct SubshellCommandAstNode {
+    command_type: CommandType,
+    token: Token,
+    data_source: Option<Box<dyn CommandAstNode>>,
+}
+
+impl SubshellCommandAstNode {
+    pub fn new(token: Token) -> Self {
+        SubshellCommandAstNode {
+            token,
+            command_type: CommandType::SubshellCommand,
+            data_source: None,
+        }
+    }
+}
+
+impl Clone for SubshellCommandAstNode {
+    fn clone(&self) -> Self {
+        Self {
+            command_type: self.command_type.clone(),
+            token: self.token.clone(),
+            data_source: self.data_source.clone(),
+        }
+    }
+}
+
+impl CommandAstNode for SubshellCommandAstNode {
+    fn token_type(&self) -> &TokenType {
+        self.token.token_type()
+    }
+
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
+    fn cmd_type(&self) -> &CommandType {
+        &self.command_type
+    }
+
+    fn set_options(&mut self, _options: Vec<(String, String)>) {}
+
+    fn get_option(&self, _option: &str) -> Option<&str> {
+        None
+    }
+
+    fn add_value(&mut self, _value: String) {}
+
+    fn set_values(&mut self, _values: Vec<String>) {}
+
+    fn get_values(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn clear_values(&mut self) {}
+
+    fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_source = values;
+    }
+
+    fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_source.clone()
+    }
+
+    fn set_destination(&mut self, _values: Option<Box<dyn CommandAstNode>>) {}
+
+    fn get_destination(&self) -> Option<Box<dyn CommandAstNode>> {
+        None
+    }
+
+    fn clone_cmd(&self) -> Box<dyn CommandAstNode> {
+        Box::new(self.clone())
+    }
+}
+
+// The group command runs the commands in braces in the shell as one command, such as '{ ls; cat f; } | grep x',
+// so their output goes to the same place.
+// The commands are stored as the data source, they're joined by ';' lists if there are more than one.
+#[derive(Debug)]
+pub struct GroupCommandAstNode {
+    command_type: CommandType,
+    token: Token,
+    data_source: Option<Box<dyn CommandAstNode>>,
+}
+
+impl GroupCommandAstNode {
+    pub fn new(token: Token) -> Self {
+        GroupCommandAstNode {
+            token,
+            command_type: CommandType::GroupCommand,
+            data_source: None,
+        }
+    }
+}
+
+impl Clone for GroupCommandAstNode {
+    fn clone(&self) -> Self {
+        Self {
+            command_type: self.command_type.clone(),
+            token: self.token.clone(),
+            data_source: self.data_source.clone(),
+        }
+    }
+}
+
+impl CommandAstNode for GroupCommandAstNode {
+    fn token_type(&self) -> &TokenType {
+        self.token.token_type()
+    }
+
+    fn literal(&self) -> &str {
+        self.token.literal()
+    }
+
+    fn cmd_type(&self) -> &CommandType {
+        &self.command_type
+    }
+
+    fn set_options(&mut self, _options: Vec<(String, String)>) {}
+
+    fn get_option(&self, _option: &str) -> Option<&str> {
+        None
+    }
+
+    fn add_value(&mut self, _value: String) {}
+
+    fn set_values(&mut self, _values: Vec<String>) {}
+
+    fn get_values(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn clear_values(&mut self) {}
+
+    fn set_source(&mut self, values: Option<Box<dyn CommandAstNode>>) {
+        self.data_source = values;
+    }
+
+    fn get_source(&self) -> Option<Box<dyn CommandAstNode>> {
+        self.data_source.clone()
+    }
+
+    fn set_destination(&mut self, _values: Option<Box<dyn CommandAstNode>>) {}
+
+    fn get_destination(&self) -> Option<Box<dyn CommandAstNode>> {
+        None
+    }
+
+    fn clone_cmd(&self) -> Box<dyn CommandAstNode> {
+        Box::new(self.clone())
+    }
+}
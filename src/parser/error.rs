@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::token::token::Span;
+
+// The error found while parsing the command line, such as the missing pattern of 'grep'.
+// It keeps the line where the error is, so it's shown under the line with a caret that points at the token:
+//
+//     ru_shell: parse error: Missing pattern
+//       grep -i -n
+//                 ^
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+
+    // The span of the token in the whole command line.
+    span: Span,
+
+    // The line where the token is, and the column where it starts in the line.
+    line: String,
+    column: usize,
+}
+
+impl ParseError {
+    pub fn new(message: &str, span: Span, line: String, column: usize) -> Self {
+        ParseError {
+            message: message.to_string(),
+            span,
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The tabs before the token are kept, so the caret is right under it.
+        let indent: String = self
+            .line
+            .chars()
+            .take(self.column)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        // The caret is as wide as the token, but the token may go on to the next lines.
+        let rest = self.line.chars().count().saturating_sub(self.column);
+        let len = self.span.end.saturating_sub(self.span.start);
+        let width = len.min(rest).max(1);
+
+        writeln!(f, "ru_shell: parse error: {}", self.message)?;
+        writeln!(f, "  {}", self.line)?;
+        write!(f, "  {}{}", indent, "^".repeat(width))
+    }
+}
@@ -1,15 +1,21 @@
-use std::cell::{Cell, RefCell};
+use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::lexer::Lexer;
+use crate::builtin::{self, Builtin};
+use crate::lexer::{quote, Lexer};
 use crate::parser::ast_node_trait::CommandAstNode;
-use crate::parser::cmds_ast_node::ChainCommandAstNode;
+use crate::parser::cmds_ast_node::{
+    BackgroundCommandAstNode, ChainCommandAstNode, GroupCommandAstNode, ListCommandAstNode,
+    RedirectCommandAstNode, SubshellCommandAstNode,
+};
+use crate::parser::error::ParseError;
 
-use crate::token::token::Token;
 use crate::token::token::TokenType;
+use crate::token::token::{Span, Token};
 
 pub mod ast_node_trait;
 pub mod cmds_ast_node;
+pub mod error;
 mod parsing_func;
 
 // Since the syntax of command-line interfaces is simpler than that of programming languages,
@@ -17,6 +23,11 @@ mod parsing_func;
 // The only distinction to be made is between execution commands and chain commands.
 // Execution commands have corresponding execution results, such as ls, cd, cat, etc.
 // Chain commands are represented by pipe symbols and the like.
+// List commands are represented by '&&' and '||', which bind looser than pipe symbols,
+// and the commands separated by ';' or '&' are stored one by one.
+// The command before '&' is wrapped by a background command, which runs it in the background.
+// The commands in '( ... )' and '{ ...; }' are parsed like a command line of their own,
+// and they're wrapped by a subshell command or a group command, which can be a part of a pipeline too.
 // Depending on the type of command, the AST generated after parsing varies.
 //
 //     Pipe
@@ -34,17 +45,12 @@ pub struct Parser {
     // The current token that the parser is looking at.
     cur_token: RefCell<Token>,
 
-    // Use to get command and its parameter from lexer.
-    cmd_start_index: Cell<u32>,
-    cmd_end_index: Cell<u32>,
-
     // The command AST that the parser will build.
     command_ast: RefCell<Vec<Box<dyn CommandAstNode>>>,
 
     // Collect errors that occur during parsing.
-    // 0: the error cmd
-    // 1: the error message
-    errors: Rc<RefCell<Vec<String>>>,
+    // The AST is incomplete if there is any, so the executor doesn't run it.
+    errors: Rc<RefCell<Vec<ParseError>>>,
 }
 
 pub struct ParserIterator<'a> {
@@ -76,8 +82,6 @@ impl Parser {
             lexer: Lexer::new(input),
             command_ast: RefCell::new(Vec::new()),
             cur_token: RefCell::new(Token::new(TokenType::Eof, "")),
-            cmd_start_index: Cell::new(0),
-            cmd_end_index: Cell::new(0),
             errors: Rc::new(RefCell::new(Vec::new())),
         };
 
@@ -92,7 +96,7 @@ impl Parser {
     }
 
     // Get the iterator of the command AST.
-    pub fn iter(&self) -> ParserIterator {
+    pub fn iter(&self) -> ParserIterator<'_> {
         ParserIterator::new(self)
     }
 
@@ -103,6 +107,24 @@ impl Parser {
 
     // Parse the command and return the AST.
     fn parse(&self) {
+        let msg = match self.lexer.unclosed_quote() {
+            Some(')') => Some(String::from("Missing the closing parenthesis `)`")),
+            Some(quote) => Some(format!("Missing the closing quotation mark `{}`", quote)),
+            None => None,
+        };
+        if let Some(msg) = msg {
+            // The quotation mark isn't closed in the last word, so the error points at it.
+            while self
+                .lexer
+                .peek_token()
+                .is_some_and(|token| *token.token_type() != TokenType::Eof)
+            {
+                self.next_token();
+            }
+            self.collect_error(&msg);
+            return;
+        }
+
         loop {
             // Check if the current token is the end of the file.
             if self.cur_token.borrow().clone().token_type() == &TokenType::Eof {
@@ -115,35 +137,45 @@ impl Parser {
                 break;
             }
 
+            // The span of the first token of the command, it's used to get the command line.
+            let start = self.cur_token.borrow().span();
+
             // Parse the corresponding command based on the token type
             // and return the parsed AST (Abstract Syntax Tree) node.
-            let ast_node: Box<dyn CommandAstNode> = match self.parse_cmds() {
-                Some(ext_cmd) => ext_cmd,
+            let ast_node: Box<dyn CommandAstNode> = match self.parse_list_cmds() {
+                Some(ext_cmd) => self.parse_background_cmd(ext_cmd, start),
                 None => break,
             };
-            // Store the AST node and move to next token.
+            // The command ends with ';', '&' or the end of the command line.
+            if !self.check_is_end_of_cmd() {
+                self.collect_unexpected_token_error();
+                break;
+            }
+
+            // Store the AST node and move to next token, which skips the ';' or '&' between commands.
             self.store_ast_node(ast_node);
             self.next_token();
         }
     }
 
-    // Collect the errors that occur during parsing.
+    // Collect the errors that occur during parsing, the error points at the current token.
     fn collect_error(&self, err_msg: &str) {
-        // Get the error command and build error message.
-        let err_tok_str = self
-            .lexer
-            .joint_tokens_to_str_by_range(self.cmd_start_index.get(), self.cmd_end_index.get());
-        let msg = format!("{}: {}", err_tok_str, err_msg);
+        let span = self.cur_token.borrow().span();
+        let (line, column) = self.lexer.line_of(span);
 
-        // Store the error message.
-        self.errors.borrow_mut().push(msg);
+        self.errors
+            .borrow_mut()
+            .push(ParseError::new(err_msg, span, line, column));
+    }
 
-        // Update the start index of the command.
-        self.cmd_start_index.set(self.cmd_end_index.get());
+    // Collect the error about the token that can't be at the current position, such as ')' in 'ls )'.
+    fn collect_unexpected_token_error(&self) {
+        let literal = self.cur_token.borrow().literal().to_string();
+        self.collect_error(&format!("Unexpected token `{}`", literal));
     }
 
     // Get the errors that were collected.
-    pub fn errors(&self) -> Vec<String> {
+    pub fn errors(&self) -> Vec<ParseError> {
         self.errors.borrow().clone()
     }
 
@@ -151,26 +183,98 @@ impl Parser {
     fn check_is_exe_command(&self) -> bool {
         let cur_token = self.cur_token.borrow().clone();
         match cur_token.token_type() {
-            TokenType::Ls | TokenType::Cd | TokenType::Grep | TokenType::Cat => true,
+            // The builtin commands, such as 'ls' or 'cd'.
+            _ if self.cur_builtin().is_some() => true,
+            // Any other word is treated as an external program, it will be looked up in $PATH.
+            TokenType::Literal
+            | TokenType::Dot
+            | TokenType::Slash
+            | TokenType::Tilde
+            | TokenType::Variable => true,
+            // The assignments before the command, or the assignments alone.
+            TokenType::AssignmentWord => true,
+            // The subshell and the group.
+            TokenType::LeftParen | TokenType::LeftBrace => true,
             // This means the end of the command.
             TokenType::Eof => false,
             _ => false,
         }
     }
 
+    // Get the declaration of the builtin command if the current token is one.
+    // The lexer has classified the names at the position of a command, so the literals are other words.
+    fn cur_builtin(&self) -> Option<Builtin> {
+        let cur_token = self.cur_token.borrow().clone();
+        if *cur_token.token_type() == TokenType::Literal {
+            return None;
+        }
+
+        builtin::lookup(cur_token.literal()).filter(|b| b.token_type() == cur_token.token_type())
+    }
+
     // Store the AST node.
     fn store_ast_node(&self, ast_node: Box<dyn CommandAstNode>) {
         self.command_ast.borrow_mut().push(ast_node);
     }
 
+    // Parse the commands joined by '&&' and '||', they are evaluated from left to right,
+    // so 'a && b || c' is parsed as '(a && b) || c'.
+    fn parse_list_cmds(&self) -> Option<Box<dyn CommandAstNode>> {
+        let mut cmd = self.parse_cmds()?;
+
+        while self.check_is_list_token() {
+            let cur_token = self.cur_token.borrow().clone();
+            let mut list_cmd = ListCommandAstNode::new(cur_token);
+
+            // Move to next Token to parse
+            self.next_token();
+            let destination = if self.check_is_exe_command() {
+                self.parse_cmds()
+            } else {
+                None
+            };
+
+            if destination.is_none() {
+                self.collect_error("Missing the command after the operator");
+                return None;
+            }
+
+            list_cmd.set_source(Some(cmd));
+            list_cmd.set_destination(destination);
+            cmd = Box::new(list_cmd);
+        }
+
+        Some(cmd)
+    }
+
+    // Wrap the command with a background command if it's followed by '&'.
+    // The command line is the text before '&', it's shown by 'jobs'.
+    fn parse_background_cmd(
+        &self,
+        cmd: Box<dyn CommandAstNode>,
+        start: Span,
+    ) -> Box<dyn CommandAstNode> {
+        let cur_token = self.cur_token.borrow().clone();
+        if *cur_token.token_type() != TokenType::Background {
+            return cmd;
+        }
+
+        let span = Span::new(start.start, cur_token.span().start);
+        let text = self.lexer.text(span);
+        let mut background_cmd = BackgroundCommandAstNode::new(cur_token);
+        background_cmd.add_value(text.trim_end().to_string());
+        background_cmd.set_source(Some(cmd));
+
+        Box::new(background_cmd)
+    }
+
     fn parse_cmds(&self) -> Option<Box<dyn CommandAstNode>> {
         let mut ext_cmd = self.parse_exe_cmd();
 
         while self.check_is_chain_token() {
-            if let Some(mut chain_cmd) = self.parse_chain_cmd() {
-                chain_cmd.set_source(ext_cmd);
-                ext_cmd = Some(chain_cmd);
-            }
+            let mut chain_cmd = self.parse_chain_cmd()?;
+            chain_cmd.set_source(ext_cmd);
+            ext_cmd = Some(chain_cmd);
         }
 
         ext_cmd
@@ -178,19 +282,178 @@ impl Parser {
 
     // Parse the executive command whose type is execute command.
     fn parse_exe_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
+        // The builtin commands are parsed by their declarations.
+        if let Some(builtin) = self.cur_builtin() {
+            return self.parse_builtin_cmd(&builtin);
+        }
+
         let cur_token = self.cur_token.borrow().clone();
         // Parse the corresponding command based on the token type
         // and return the parsed AST (Abstract Syntax Tree) node.
         let ext_cmd: Option<Box<dyn CommandAstNode>> = match cur_token.token_type() {
-            TokenType::Ls => self.parse_ls_cmd(),
-            TokenType::Cat => self.parse_cat_cmd(),
-            TokenType::Grep => self.parse_grep_cmd(),
+            TokenType::Literal => self.parse_external_cmd(),
+            TokenType::AssignmentWord => self.parse_assignment_cmd(),
+            TokenType::Dot | TokenType::Slash | TokenType::Tilde | TokenType::Variable => {
+                self.parse_external_cmd()
+            }
+            TokenType::LeftParen => {
+                let mut subshell_cmd = SubshellCommandAstNode::new(cur_token);
+                subshell_cmd.set_source(Some(self.parse_compound_cmds(TokenType::RightParen)?));
+                Some(Box::new(subshell_cmd))
+            }
+            TokenType::LeftBrace => {
+                let mut group_cmd = GroupCommandAstNode::new(cur_token);
+                group_cmd.set_source(Some(self.parse_compound_cmds(TokenType::RightBrace)?));
+                Some(Box::new(group_cmd))
+            }
             _ => None,
         };
 
-        ext_cmd
+        // The redirections after the subshell or the group,
+        // the ones of the other commands are parsed with their arguments, such as 'echo a > out.txt b'.
+        self.parse_redirect_cmd(ext_cmd?)
     }
 
+    // Parse the commands in the subshell or the group until the closing token, the opening one is skipped.
+    // The commands are separated by ';' or '&' just like a command line, and they're joined by ';' lists,
+    // so 'a; b & c' is parsed as '(a ; b &) ; c'.
+    fn parse_compound_cmds(&self, closing: TokenType) -> Option<Box<dyn CommandAstNode>> {
+        self.next_token();
+
+        let mut cmds: Option<Box<dyn CommandAstNode>> = None;
+        loop {
+            if *self.cur_token.borrow().token_type() == closing {
+                self.next_token();
+                break;
+            }
+            if !self.check_is_exe_command() {
+                match closing {
+                    TokenType::RightParen => self.collect_error("Missing the closing `)`"),
+                    _ => self.collect_error("Missing the closing `}`"),
+                }
+                return None;
+            }
+
+            let start = self.cur_token.borrow().span();
+            let cmd = self.parse_list_cmds()?;
+            let cmd = self.parse_background_cmd(cmd, start);
+
+            // Skip the ';' or '&' after the command, the closing token may follow the command directly.
+            if matches!(
+                self.cur_token.borrow().token_type(),
+                TokenType::Semicolon | TokenType::Background
+            ) {
+                self.next_token();
+            } else if *self.cur_token.borrow().token_type() != closing {
+                self.collect_unexpected_token_error();
+                return None;
+            }
+
+            cmds = match cmds {
+                Some(source) => {
+                    let mut list_cmd =
+                        ListCommandAstNode::new(Token::new(TokenType::Semicolon, ";"));
+                    list_cmd.set_source(Some(source));
+                    list_cmd.set_destination(Some(cmd));
+                    Some(Box::new(list_cmd))
+                }
+                None => Some(cmd),
+            };
+        }
+
+        if cmds.is_none() {
+            match closing {
+                TokenType::RightParen => self.collect_error("Missing the command in the subshell"),
+                _ => self.collect_error("Missing the command in the group"),
+            }
+        }
+
+        cmds
+    }
+
+    // Parse the redirections after the command, and wrap the command with them, see [`redirect`].
+    fn parse_redirect_cmd(&self, cmd: Box<dyn CommandAstNode>) -> Option<Box<dyn CommandAstNode>> {
+        let mut redirections: Vec<RedirectCommandAstNode> = Vec::new();
+        while self.check_is_redirect_token() {
+            redirections.push(self.parse_redirection()?);
+        }
+
+        Some(redirect(cmd, redirections))
+    }
+
+    // Parse one redirection, such as '> out.txt' or '2>&1', the target is stored as the value.
+    fn parse_redirection(&self) -> Option<RedirectCommandAstNode> {
+        let cur_token = self.cur_token.borrow().clone();
+        let mut redirect_cmd = RedirectCommandAstNode::new(cur_token.clone());
+
+        // The file descriptor is the number before the operator, such as '2' in '2>'.
+        // The input is redirected by default for '<' and '<<', otherwise the output.
+        let fd = cur_token
+            .literal()
+            .trim_end_matches(['>', '<', '&'])
+            .replace('_', "");
+        let fd = match (fd.is_empty(), cur_token.token_type()) {
+            (false, _) => fd,
+            (true, TokenType::LessThan | TokenType::DoubleLessThan) => String::from("0"),
+            (true, _) => String::from("1"),
+        };
+        redirect_cmd.set_options(vec![(String::from("fd"), fd)]);
+
+        self.next_token();
+
+        let target = match cur_token.token_type() {
+            // The target of '>&' is a file descriptor.
+            TokenType::GreaterThanAnd => {
+                let target = self.cur_token.borrow().clone();
+                if *target.token_type() == TokenType::Num {
+                    self.next_token();
+                    Some(target.literal().to_string())
+                } else {
+                    None
+                }
+            }
+            // The target of '<<' is the body of the here-document.
+            // The body isn't expanded if the delimiter is quoted, it's stored as the option 'quoted'.
+            TokenType::DoubleLessThan => self.parse_heredoc().map(|(body, quoted)| {
+                if quoted {
+                    redirect_cmd.set_options(vec![(String::from("quoted"), String::new())]);
+                }
+                body
+            }),
+            // The others are files.
+            _ => self.parse_word(),
+        };
+
+        match target {
+            Some(target) => redirect_cmd.add_value(target),
+            None => {
+                self.collect_error("Missing the target of redirection");
+                return None;
+            }
+        }
+
+        Some(redirect_cmd)
+    }
+
+    // Parse the here-document after '<<'.
+    // The lexer puts the body after the delimiter, so the delimiter itself is skipped.
+    // Return the body and whether the delimiter is quoted.
+    fn parse_heredoc(&self) -> Option<(String, bool)> {
+        let quoted = quote::is_quoted(self.cur_token.borrow().literal());
+
+        // Skip the delimiter until meeting the body.
+        loop {
+            let cur_token = self.cur_token.borrow().clone();
+            match cur_token.token_type() {
+                TokenType::HereDoc => {
+                    self.next_token();
+                    return Some((cur_token.literal().to_string(), quoted));
+                }
+                TokenType::Eof => return None,
+                _ => self.next_token(),
+            }
+        }
+    }
 
     // Parse the command whose type is chain command.
     pub fn parse_chain_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
@@ -201,8 +464,13 @@ impl Parser {
             // Move to next Token to parse
             self.next_token();
             // Set data destination of chain command.
-            let destination = self.parse_exe_cmd();
-            cmd.set_destination(destination);
+            let destination = if self.check_is_exe_command() {
+                self.parse_exe_cmd()
+            } else {
+                self.collect_error("Missing the command after the pipe");
+                None
+            };
+            cmd.set_destination(Some(destination?));
 
             return Some(Box::new(cmd));
         }
@@ -210,6 +478,34 @@ impl Parser {
         None
     }
 
+    // Judge current token if is redirect token.
+    // '>', '>>' and '>&' redirect the output, '<' and '<<' redirect the input.
+    fn check_is_redirect_token(&self) -> bool {
+        matches!(
+            self.cur_token.borrow().token_type(),
+            TokenType::GreaterThan
+                | TokenType::DoubleGreaterThan
+                | TokenType::GreaterThanAnd
+                | TokenType::LessThan
+                | TokenType::DoubleLessThan
+        )
+    }
+
+    // Judge current token if ends the command, the commands are separated by ';' or '&'.
+    fn check_is_end_of_cmd(&self) -> bool {
+        matches!(
+            self.cur_token.borrow().token_type(),
+            TokenType::Semicolon | TokenType::Background | TokenType::Eof
+        )
+    }
+
+    // Judge current token if is list token.
+    fn check_is_list_token(&self) -> bool {
+        matches!(
+            self.cur_token.borrow().token_type(),
+            TokenType::And | TokenType::Or
+        )
+    }
 
     // Judge current token if is chain token.
     fn check_is_chain_token(&self) -> bool {
@@ -224,12 +520,25 @@ impl Parser {
         let token = self.lexer.next_token();
         let mut cur_token = self.cur_token.borrow_mut();
 
+        // The end stays where the last token is.
         match token {
             Some(t) => *cur_token = t,
-            None => *cur_token = Token::new(TokenType::Eof, ""),
+            None => *cur_token = Token::with_span(TokenType::Eof, "", cur_token.span()),
         }
+    }
+}
 
-        let end_index = self.cmd_end_index.get();
-        self.cmd_end_index.set(end_index + 1);
+// Wrap the command with the redirections.
+// Redirections are applied from left to right, so the first one becomes the outermost node.
+fn redirect(
+    cmd: Box<dyn CommandAstNode>,
+    redirections: Vec<RedirectCommandAstNode>,
+) -> Box<dyn CommandAstNode> {
+    let mut cmd = cmd;
+    for mut redirect_cmd in redirections.into_iter().rev() {
+        redirect_cmd.set_source(Some(cmd));
+        cmd = Box::new(redirect_cmd);
     }
+
+    cmd
 }
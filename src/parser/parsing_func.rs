@@ -1,316 +1,240 @@
-use crate::token::token::TokenType;
+use crate::builtin::{Builtin, Operands};
+use crate::lexer::quote;
+use crate::token::token::{Token, TokenType};
 
 use super::{
     ast_node_trait::CommandAstNode,
-    cmds_ast_node::ExeCommandAstNode,
-    Parser,
+    cmds_ast_node::{AssignmentCommandAstNode, ExeCommandAstNode, RedirectCommandAstNode},
+    redirect, Parser,
 };
 
 // Here are the parsing functions for parsing each command type
 // Due to the different nature of each command, they are separated into different functions
 impl Parser {
-    // Parse the ls command.
-    // The ls command has the following options:
-    // -l, --long: show the long format
-    // -a, --all: do not ignore entries starting with .
-    // -h, --human-readable: with -l and/or -s, print human readable sizes
-    // -r, --reverse: reverse order while sorting
-    // -t, --time: sort by modification time, newest first
-    // -s, --size: sort by file size, largest first
-    // --tree: show the directory tree
-    // --depth: show the directory tree with the specified depth
-    pub fn parse_ls_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
+    // Parse the builtin command by its declaration, see [`crate::builtin`].
+    // The options may be anywhere before '--', such as 'ls src -l', and the other words are the values,
+    // so the values of 'grep' are the pattern and the paths.
+    // The options aren't parsed if the command has none, such as '-' in 'cd -' or '-p' in 'export -p'.
+    // The redirections may be among the words too, and the command is wrapped with them.
+    pub fn parse_builtin_cmd(&self, builtin: &Builtin) -> Option<Box<dyn CommandAstNode>> {
         // Build the exe command node.
-        let mut ls_cmd = ExeCommandAstNode::new(self.cur_token.borrow().clone());
+        let mut builtin_cmd = ExeCommandAstNode::new(self.cur_token.borrow().clone());
 
         self.next_token();
 
-        // Parse the parameters of the command.
         let mut options: Vec<(String, String)> = Vec::new();
-        loop {
-            if self.check_weather_is_eof() || !self.check_weather_is_param() {
-                break;
+        let mut values: Vec<String> = Vec::new();
+        let mut redirections: Vec<RedirectCommandAstNode> = Vec::new();
+        let mut parsing_options = !builtin.options().is_empty();
+        while !self.check_is_end_of_words() {
+            let token_type = self.cur_token.borrow().token_type().clone();
+            match token_type {
+                _ if self.check_is_redirect_token() => redirections.push(self.parse_redirection()?),
+                TokenType::ShortParam | TokenType::LongParam if parsing_options => {
+                    options.extend(self.parse_options(builtin));
+                }
+                TokenType::DoubleMinus if parsing_options => {
+                    parsing_options = false;
+                    self.next_token();
+                }
+                // The paths are separated by blanks or commas.
+                TokenType::Comma if builtin.operands() == Operands::Paths => self.next_token(),
+                _ if builtin.operands() == Operands::Paths => {
+                    values.push(self.parse_arg_until(Some(TokenType::Comma)))
+                }
+                _ => values.push(self.parse_arg()),
             }
+        }
 
-            let cur_token = self.cur_token.borrow().clone();
-            match cur_token.literal() {
-                "-l" | "--long" => {
-                    options.push(self.parse_option(false));
-                }
-                "-a" | "--all" => {
-                    options.push(self.parse_option(false));
-                }
-                "-h" | "--human-readable" => {
-                    options.push(self.parse_option(false));
-                }
-                "-r" | "--reverse" => {
-                    options.push(self.parse_option(false));
-                }
-                "-t" | "--time" => {
-                    options.push(self.parse_option(false));
-                }
-                "-s" | "--size" => {
-                    options.push(self.parse_option(false));
-                }
-                "--tree" => {
-                    options.push(self.parse_option(false));
-                }
-                "--depth" => {
-                    options.push(self.parse_option(true));
-                }
-                _ => {
-                    options.push(self.parse_option(false));
-                }
-            }
+        if let Some(name) = builtin.required().get(values.len()) {
+            self.collect_error(&format!("Missing {}", name));
+            return None;
         }
-        ls_cmd.set_options(options);
 
-        // Parse the paths of the ls command.
-        match self.parse_paths() {
-            Some(paths) => ls_cmd.set_values(paths),
-            None => (),
-        };
+        builtin_cmd.set_options(options);
+        if !values.is_empty() {
+            builtin_cmd.set_values(values);
+        }
 
-        Some(Box::new(ls_cmd))
+        Some(redirect(Box::new(builtin_cmd), redirections))
     }
 
-    // Parse the grep command.
-    // The grep command has the following options:
-    // -i, --ignore-case: ignore case distinctions
-    // -v, --invert-match: select non-matching lines
-    // -c, --count: print only a count of matching lines per FILE
-    // -n, --line-number: print line number with output lines
-    pub fn parse_grep_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
-        // Build the exe command node.
-        let mut grep_cmd: ExeCommandAstNode =
-            ExeCommandAstNode::new(self.cur_token.borrow().clone());
-
-        self.next_token();
+    // Parse the assignments, such as 'A=1 B=2', and the command after them, such as 'FOO=1 env'.
+    // The assignments are stored as values in order, and the command is stored as the data source.
+    pub fn parse_assignment_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
+        let mut assignment_cmd = AssignmentCommandAstNode::new(self.cur_token.borrow().clone());
 
-        // Parse the parameters of the command.
-        let mut options: Vec<(String, String)> = Vec::new();
-        loop {
-            if self.check_weather_is_eof() || !self.check_weather_is_param() {
-                break;
-            }
+        while *self.cur_token.borrow().token_type() == TokenType::AssignmentWord {
+            assignment_cmd.add_value(self.parse_arg());
+        }
 
-            let cur_token = self.cur_token.borrow().clone();
-            match cur_token.literal() {
-                "-i" | "--ignore-case" => {
-                    options.push(self.parse_option(false));
-                }
-                "-v" | "--invert-match" => {
-                    options.push(self.parse_option(false));
-                }
-                "-c" | "--count" => {
-                    options.push(self.parse_option(false));
-                }
-                "-n" | "--line-number" => {
-                    options.push(self.parse_option(false));
-                }
-                _ => {
-                    options.push(self.parse_option(false));
-                }
-            }
+        // The command is parsed like any other command, so it may be a builtin command too.
+        if self.check_is_exe_command() {
+            assignment_cmd.set_source(Some(self.parse_exe_cmd()?));
         }
-        grep_cmd.set_options(options);
-
-        // set the pattern of the grep command.
-        let pattern = match self.parse_pattern() {
-            Some(pattern) => pattern,
-            None => return None,
-        };
-        grep_cmd.add_value(pattern);
-
-        // Parse the paths of the ls command.
-        match self.parse_paths() {
-            Some(paths) => grep_cmd.set_values(paths),
-            None => {
-                self.collect_error("Grep command needs a path");
-            }
-        };
 
-        Some(Box::new(grep_cmd))
+        Some(Box::new(assignment_cmd))
     }
 
-    // Parse the cat command.
-    // The cat command has the following options:
-    // -n, --number: number all output lines
-    // -b, --number-nonblank: number nonempty output lines, overrides -n
-    // -s, --squeeze-blank: suppress repeated empty output lines
-    // -E, --show-ends: display $ at end of each line
-    pub fn parse_cat_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
+    // Parse the external command, which is any program that can be found in $PATH.
+    // The program and its arguments are stored as values in order, just like 'argv',
+    // so the first value is the program and the rest are its arguments.
+    // Options are not parsed here, because only the program knows what they mean.
+    // The redirections may be among the arguments, such as 'echo a > out.txt b', and the command is wrapped with them.
+    pub fn parse_external_cmd(&self) -> Option<Box<dyn CommandAstNode>> {
+        let span = self.cur_token.borrow().span();
+        let program = self.parse_arg();
+
         // Build the exe command node.
-        let mut cat_cmd: ExeCommandAstNode =
-            ExeCommandAstNode::new(self.cur_token.borrow().clone());
+        let mut external_cmd =
+            ExeCommandAstNode::new(Token::with_span(TokenType::Literal, &program, span));
+        external_cmd.add_value(program);
 
-        self.next_token();
+        let mut redirections: Vec<RedirectCommandAstNode> = Vec::new();
+        external_cmd.set_values(self.parse_args(&mut redirections)?);
 
-        // Parse the parameters of the command.
-        let mut options: Vec<(String, String)> = Vec::new();
-        loop {
-            if self.check_weather_is_eof() || !self.check_weather_is_param() {
-                break;
-            }
+        Some(redirect(Box::new(external_cmd), redirections))
+    }
 
-            let cur_token = self.cur_token.borrow().clone();
-            match cur_token.literal() {
-                "-n" | "--number" => {
-                    options.push(self.parse_option(false));
-                }
-                "-b" | "--number-nonblank" => {
-                    options.push(self.parse_option(false));
-                }
-                "-s" | "--squeeze-blank" => {
-                    options.push(self.parse_option(false));
-                }
-                "-E" | "--show-ends" => {
-                    options.push(self.parse_option(false));
-                }
-                _ => {
-                    options.push(self.parse_option(false));
-                }
+    // Parse the arguments of the external command until meeting a token that ends the command,
+    // the redirections among them are put into 'redirections'.
+    fn parse_args(&self, redirections: &mut Vec<RedirectCommandAstNode>) -> Option<Vec<String>> {
+        let mut args: Vec<String> = Vec::new();
+
+        while !self.check_is_end_of_words() {
+            if self.check_is_redirect_token() {
+                redirections.push(self.parse_redirection()?);
+            } else {
+                args.push(self.parse_arg());
             }
         }
-        cat_cmd.set_options(options);
 
-        // Parse the paths of the ls command.
-        match self.parse_paths() {
-            Some(paths) => cat_cmd.set_values(paths),
-            None => (),
-        };
-
-        Some(Box::new(cat_cmd))
+        Some(args)
     }
 
-    // Parse the parameters of the command.
-    // @param whether_parsing_value: whether the parser is parsing the value of the option.
-    pub fn parse_option(&self, whether_parsing_value: bool) -> (String, String) {
-        let option = self.cur_token.borrow().literal().to_string();
-        self.next_token();
+    // Check if the current token ends the words of the command, the redirections may be among the words.
+    fn check_is_end_of_words(&self) -> bool {
+        self.check_is_end_of_args() && !self.check_is_redirect_token()
+    }
 
-        // Parse the value of the option.
-        let mut value = String::from("");
-        if whether_parsing_value {
-            // Skip the assignment operator.
-            if *self.cur_token.borrow().token_type() == TokenType::Assignment {
-                self.next_token();
-            }
+    // Check if the current token ends the arguments of the command,
+    // such as the operators between the commands and the redirections.
+    fn check_is_end_of_args(&self) -> bool {
+        matches!(
+            self.cur_token.borrow().token_type(),
+            TokenType::Eof
+                | TokenType::Pipe
+                | TokenType::Semicolon
+                | TokenType::Background
+                | TokenType::And
+                | TokenType::Or
+                | TokenType::GreaterThan
+                | TokenType::DoubleGreaterThan
+                | TokenType::GreaterThanAnd
+                | TokenType::LessThan
+                | TokenType::DoubleLessThan
+                | TokenType::LeftParen
+                | TokenType::RightParen
+                | TokenType::RightBrace
+        )
+    }
 
-            if *self.cur_token.borrow().token_type() == TokenType::Literal
-                || *self.cur_token.borrow().token_type() == TokenType::Num
-            {
-                value = self.cur_token.borrow().literal().to_string();
-                self.next_token();
-            }
+    // Parse one word, such as the target of a redirection.
+    // None is returned if the current token can't start a word.
+    pub fn parse_word(&self) -> Option<String> {
+        let cur_tok = self.cur_token.borrow().clone();
+        match cur_tok.token_type() {
+            TokenType::Tilde
+            | TokenType::Literal
+            | TokenType::Variable
+            | TokenType::AssignmentWord
+            | TokenType::Num
+            | TokenType::Slash
+            | TokenType::Dot
+            | TokenType::Star => Some(self.parse_arg()),
+            _ => None,
         }
+    }
 
-        (option, value)
+    // Parse one argument of the command.
+    // The lexer splits a word at the symbols in it, such as 'user@host', '1.txt' or '10:30',
+    // so the tokens that touch each other are joined to restore the word.
+    // The quotes are removed, see [`quote::unquote`].
+    fn parse_arg(&self) -> String {
+        self.parse_arg_until(None)
     }
 
-    // Parse the paths of the command.
-    pub fn parse_paths(&self) -> Option<Vec<String>> {
-        let mut paths: Vec<String> = Vec::new();
+    // Parse one argument that ends at the separator too, such as ',' between the paths in 'ls a,b'.
+    fn parse_arg_until(&self, separator: Option<TokenType>) -> String {
+        let mut arg = String::new();
 
         loop {
             let cur_tok = self.cur_token.borrow().clone();
-            match *cur_tok.token_type() {
-                TokenType::Tilde
-                | TokenType::Literal
-                | TokenType::Num
-                | TokenType::Slash
-                | TokenType::Dot => {
-                    paths.push(self.parse_path().unwrap());
-                }
-                _ => break,
-            };
+            arg.push_str(&quote::unquote(cur_tok.literal()));
+            self.next_token();
 
-            // Skip the comma and get next path.
-            // If the current token isn't comma, then break the loop.
-            if self.cur_token.borrow().clone().token_type() != &TokenType::Comma {
+            let next_tok = self.cur_token.borrow().clone();
+            let is_glued = next_tok.span().start == cur_tok.span().end
+                && !self.check_is_end_of_args()
+                && *next_tok.token_type() != TokenType::HereDoc
+                && Some(next_tok.token_type()) != separator.as_ref();
+            if !is_glued {
                 break;
             }
-            self.next_token();
         }
 
-        if paths.is_empty() {
-            None
-        } else {
-            Some(paths)
-        }
+        arg
     }
 
-    // Parse the path of the command.
-    fn parse_path(&self) -> Option<String> {
-        let mut path = String::from(self.cur_token.borrow().literal());
+    // Parse the option word of the builtin command.
+    // The short options can be combined, such as '-la' for '-l -a',
+    // and the value of the one that takes a value is the rest of the word or the next word, such as '-n3' or '-n 3'.
+    // The value of the long option is the next word, such as '--depth 3' or '--depth=3'.
+    // The unknown options are kept without values, the command decides what to do with them.
+    fn parse_options(&self, builtin: &Builtin) -> Vec<(String, String)> {
+        let word = self.cur_token.borrow().literal().to_string();
         self.next_token();
 
-        loop {
-            if self.lexer.peek_token().is_none() {
-                break;
-            }
+        if let Some(long) = word.strip_prefix("--") {
+            let value = match builtin.long_option(long) {
+                Some(option) if option.takes_value() => self.parse_option_value(),
+                _ => String::new(),
+            };
+            return vec![(word, value)];
+        }
 
-            if *self.cur_token.borrow().token_type() == TokenType::Literal
-                || *self.cur_token.borrow().token_type() == TokenType::Num
-                || *self.cur_token.borrow().token_type() == TokenType::Slash
-                || *self.cur_token.borrow().token_type() == TokenType::Dot
-                || *self.cur_token.borrow().token_type() == TokenType::Tilde
-            {
-                path.push_str(self.cur_token.borrow().literal());
-                self.next_token();
-            } else {
-                break;
+        let mut options: Vec<(String, String)> = Vec::new();
+        let shorts: Vec<char> = word.chars().skip(1).collect();
+        for (i, short) in shorts.iter().enumerate() {
+            let name = format!("-{}", short);
+            match builtin.short_option(*short) {
+                Some(option) if option.takes_value() => {
+                    let rest: String = shorts[i + 1..].iter().collect();
+                    let value = if rest.is_empty() {
+                        self.parse_option_value()
+                    } else {
+                        rest
+                    };
+                    options.push((name, value));
+                    break;
+                }
+                _ => options.push((name, String::new())),
             }
         }
 
-        Some(path)
+        options
     }
 
-    // Parse the matching rules of the 'Pattern matching' command.
-    fn parse_pattern(&self) -> Option<String> {
-        // If the current token is a double quotation mark, then the pattern is complete.
-        if *self.cur_token.borrow().token_type() == TokenType::Quote {
+    // Parse the value of the option, the assignment operator before it is skipped.
+    fn parse_option_value(&self) -> String {
+        if *self.cur_token.borrow().token_type() == TokenType::Assignment {
             self.next_token();
-        } else {
-            self.collect_error("Missing pattern. You can use `\"` to quote the pattern.");
-            return None;
         }
 
-        // Iterate through the subsequent tokens and add them to the current pattern
-        // until the condition is not met.
-        let mut pattern = String::from("");
-        loop {
-            if *self.cur_token.borrow().token_type() == TokenType::Literal
-                || *self.cur_token.borrow().token_type() == TokenType::Num
-                || *self.cur_token.borrow().token_type() == TokenType::Slash
-                || *self.cur_token.borrow().token_type() == TokenType::Dot
-                || *self.cur_token.borrow().token_type() == TokenType::Tilde
-                || *self.cur_token.borrow().token_type() == TokenType::Star
-            {
-                pattern.push_str(self.cur_token.borrow().literal());
-                self.next_token();
-            } else {
-                break;
-            }
+        let token_type = self.cur_token.borrow().token_type().clone();
+        match token_type {
+            TokenType::Literal | TokenType::Num => quote::unescape(&self.parse_arg()),
+            _ => String::new(),
         }
-
-        // If the current token is a double quotation mark, then the pattern is complete.
-        if *self.cur_token.borrow().token_type() == TokenType::Quote {
-            self.next_token();
-        } else {
-            self.collect_error("Invalid pattern, missing right quotation mark.");
-            return None;
-        }
-
-        Some(pattern)
-    }
-
-    fn check_weather_is_eof(&self) -> bool {
-        *self.cur_token.borrow().token_type() == TokenType::Eof
-    }
-
-    fn check_weather_is_param(&self) -> bool {
-        *self.cur_token.borrow().token_type() == TokenType::ShortParam
-            || *self.cur_token.borrow().token_type() == TokenType::LongParam
     }
 }
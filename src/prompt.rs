@@ -0,0 +1,185 @@
+use std::env;
+use std::ffi::CStr;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use colored::{ColoredString, Colorize};
+
+use crate::executor::expansion::home_dir;
+use crate::session;
+
+// The prompt when 'PS1' isn't set.
+pub const DEFAULT_PS1: &str = "> ";
+
+// Get the prompt that is shown before each command line, it's made from the template in the variable 'PS1'.
+pub fn prompt(color: bool) -> String {
+    let template = session::variable("PS1").unwrap_or_else(|| DEFAULT_PS1.to_string());
+    render(&template, color)
+}
+
+// Replace the escapes of the template by the segments, the other chars are shown as they are:
+//     \u : the user name
+//     \h : the host name up to the first '.'
+//     \H : the host name
+//     \w : the current directory, the home directory is shown as '~'
+//     \W : the last part of the current directory
+//     \t : the time as 'HH:MM:SS'
+//     \A : the time as 'HH:MM'
+//     \? : the exit status of the last command
+//     \j : the number of jobs
+//     \g : the git branch as ' (main)', or the commit if HEAD is detached, it's empty outside a repository
+//     \$ : '#' for root, '$' for the other users
+//     \n : newline
+//     \\ : backslash
+// The segments are colored if 'color' is true, such as the exit status, which is red if the command failed.
+// The unknown escapes are kept as they are.
+pub fn render(template: &str, color: bool) -> String {
+    let paint = |text: String, style: fn(&str) -> ColoredString| {
+        if color {
+            style(&text).to_string()
+        } else {
+            text
+        }
+    };
+
+    let mut prompt = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            prompt.push(c);
+            continue;
+        }
+
+        let segment = match chars.next() {
+            Some('u') => paint(user_name(), |s| s.green().bold()),
+            Some('h') => {
+                let host = host_name();
+                let short = host.split('.').next().unwrap_or_default().to_string();
+                paint(short, |s| s.green())
+            }
+            Some('H') => paint(host_name(), |s| s.green()),
+            Some('w') => paint(current_dir(), |s| s.blue().bold()),
+            Some('W') => {
+                let dir = current_dir();
+                let name = match dir.rsplit_once('/') {
+                    Some((_, name)) if !name.is_empty() => name.to_string(),
+                    _ => dir,
+                };
+                paint(name, |s| s.blue().bold())
+            }
+            Some('t') => paint(Local::now().format("%H:%M:%S").to_string(), |s| s.cyan()),
+            Some('A') => paint(Local::now().format("%H:%M").to_string(), |s| s.cyan()),
+            Some('?') => match session::last_status() {
+                0 => paint(String::from("0"), |s| s.green()),
+                status => paint(status.to_string(), |s| s.red().bold()),
+            },
+            Some('j') => {
+                let count = session::with_jobs(|table| table.jobs().len());
+                paint(count.to_string(), |s| s.magenta())
+            }
+            Some('g') => match env::current_dir().ok().and_then(|dir| git_branch(&dir)) {
+                Some(branch) => format!(" ({})", paint(branch, |s| s.yellow())),
+                None => String::new(),
+            },
+            Some('$') => {
+                let root = unsafe { libc::geteuid() } == 0;
+                String::from(if root { "#" } else { "$" })
+            }
+            Some('n') => String::from("\n"),
+            Some('\\') => String::from("\\"),
+            Some(c) => format!("\\{}", c),
+            None => String::from("\\"),
+        };
+        prompt.push_str(&segment);
+    }
+
+    prompt
+}
+
+// Show the path with '~' for the home directory, such as '~/src' for '/home/ru/src'.
+pub fn abbreviate_home(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => String::from("~"),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+// Get the git branch of the directory from '.git/HEAD' in it or in a parent directory,
+// or the short commit if HEAD is detached.
+// '.git' may be a file that tells where the git directory is, such as in a worktree.
+pub fn git_branch(dir: &Path) -> Option<String> {
+    let (root, git) = dir
+        .ancestors()
+        .map(|dir| (dir, dir.join(".git")))
+        .find(|(_, git)| git.exists())?;
+
+    let git_dir = if git.is_file() {
+        let content = fs::read_to_string(&git).ok()?;
+        let path = PathBuf::from(content.trim().strip_prefix("gitdir:")?.trim());
+        root.join(path)
+    } else {
+        git
+    };
+
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(
+                reference
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(reference)
+                    .to_string(),
+            )
+        }
+        None if !head.is_empty() => Some(head.chars().take(7).collect()),
+        None => None,
+    }
+}
+
+fn user_name() -> String {
+    users::get_current_username()
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+fn host_name() -> String {
+    let mut buffer = [0u8; 256];
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return String::new();
+    }
+
+    CStr::from_bytes_until_nul(&buffer)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// The working directory is $PWD, which keeps the path that 'cd' went through, such as a symbolic link.
+// The real path is used if $PWD isn't set or it's another directory, such as the one the shell was started from.
+fn current_dir() -> String {
+    let dir = match env::var("PWD") {
+        Ok(dir) if is_current_dir(Path::new(&dir)) => PathBuf::from(dir),
+        _ => match env::current_dir() {
+            Ok(dir) => dir,
+            Err(_) => return String::new(),
+        },
+    };
+
+    abbreviate_home(&dir, home_dir().as_deref())
+}
+
+// Whether the absolute path is the working directory, the links in it are followed.
+fn is_current_dir(path: &Path) -> bool {
+    match (fs::metadata(path), fs::metadata(".")) {
+        (Ok(dir), Ok(current)) => {
+            path.is_absolute() && dir.dev() == current.dev() && dir.ino() == current.ino()
+        }
+        _ => false,
+    }
+}
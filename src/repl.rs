@@ -1,53 +1,97 @@
 use std::{
     fs::File,
-    io::{self, BufReader, Read, Write},
+    io::{BufReader, Read},
     rc::Rc,
 };
 
 use dirs_next::home_dir;
 
-use crate::{executor, file_operator::FileOperator, stream::{console_stream::ConsoleStream, Stream}};
+use crate::{
+    editor::{terminal, LineEditor},
+    executor,
+    history::History,
+    jobs,
+    lexer::Lexer,
+    prompt, session,
+    stream::{console_stream::ConsoleStream, Stream},
+};
 
 pub fn run() {
-    // Create history file
+    // Load the history of the last sessions
     let user_home_path = match home_dir() {
-        Some(path) => path.to_str().unwrap().to_string(),
+        Some(path) => path.to_string_lossy().into_owned(),
         None => {
-            println!("Unable to get home directory");
+            eprintln!("ru_shell: unable to get home directory");
             return;
         }
     };
-    let mut history_file = FileOperator::new(
-        &format!("{}/.rusty_shell_history", user_home_path),
-        false,
-        1024,
-    );
-
-    // Print the logo
-    let file = File::open("asset/logo.txt").unwrap();
-    let mut buf_reader = BufReader::new(file);
+    let mut history = History::with_file(&format!("{}/.rusty_shell_history", user_home_path));
+    if let Err(e) = history.load() {
+        eprintln!(
+            "ru_shell: unable to read history file: {}",
+            executor::io_error_message(&e)
+        );
+    }
+    session::with_history(|h| *h = history);
+
+    // Print the logo, it's skipped if the file can't be read, such as when the shell runs in another directory.
     let mut logo = String::new();
-    buf_reader.read_to_string(&mut logo).unwrap();
-    println!("{}", logo);
+    if let Ok(file) = File::open("asset/logo.txt") {
+        if BufReader::new(file).read_to_string(&mut logo).is_ok() {
+            println!("{}", logo);
+        }
+    }
     println!("Welcome to the Rusty Shell!");
 
     // Create a stream for the console
     let console_stream = Rc::new(ConsoleStream::new());
 
-    loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
+    jobs::init_job_control();
 
-        // Get input from user
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+    let mut editor = LineEditor::new();
 
-        // Write the input to history file
-        match history_file.write(&input) {
-            Ok(_) => {}
+    loop {
+        // Report the background jobs that have finished since the last prompt.
+        for line in jobs::notify_done() {
+            println!("{}", line);
+        }
+
+        // Get input from user, the shell exits at the end of the input, such as when Ctrl-D is pressed.
+        editor.set_history(&session::with_history(|history| history.lines().to_vec()));
+        // The prompt is colored only in a terminal, otherwise it's printed as plain text.
+        let prompt = prompt::prompt(terminal::is_terminal());
+        let line = match editor.read_line(&prompt) {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
             Err(e) => {
-                println!("Unable to write to history file: {}", e);
+                eprintln!("ru_shell: {}", executor::io_error_message(&e));
+                break;
+            }
+        };
+
+        // Expand the events of the history, such as '!!', the expanded line is shown before it runs.
+        let mut input = match session::with_history(|history| history.expand(&line)) {
+            Ok(Some(expanded)) => {
+                println!("{}", expanded);
+                expanded + "\n"
+            }
+            Ok(None) => line + "\n",
+            Err(msg) => {
+                eprintln!("ru_shell: {}", msg);
+                continue;
             }
+        };
+
+        // Read the rest of the quoted text, and the bodies of the here-documents that follow the command line.
+        read_quoted_lines(&mut editor, &mut input);
+        read_heredoc_bodies(&mut editor, &mut input);
+
+        // Add the input to the history, which appends it to the history file
+        if let Err(e) = session::with_history(|history| history.add(input.trim_end_matches('\n'))) {
+            eprintln!(
+                "ru_shell: unable to write to history file: {}",
+                executor::io_error_message(&e)
+            );
         }
 
         if input.trim() == "exit" {
@@ -59,3 +103,41 @@ pub fn run() {
         console_stream.output();
     }
 }
+
+// Read more lines until the quotation marks in the input are closed,
+// the newlines are a part of the quoted text.
+// A line that ends with a backslash is continued by the next line too.
+fn read_quoted_lines(editor: &mut LineEditor, input: &mut String) {
+    while Lexer::new(input).unclosed_quote().is_some() || ends_with_escaped_newline(input) {
+        match editor.read_line("quote> ") {
+            Ok(Some(line)) => input.push_str(&(line + "\n")),
+            _ => return,
+        }
+    }
+}
+
+// Read the lines of every here-document in the input until its delimiter,
+// and append them to the input, so that the lexer can find the bodies.
+fn read_heredoc_bodies(editor: &mut LineEditor, input: &mut String) {
+    for delimiter in Lexer::new(input).heredoc_delimiters() {
+        loop {
+            // Stop at the end of the input, even if the delimiter hasn't been found.
+            let line = match editor.read_line("heredoc> ") {
+                Ok(Some(line)) => line,
+                _ => return,
+            };
+            input.push_str(&line);
+            input.push('\n');
+
+            if line.trim_end_matches('\r') == delimiter {
+                break;
+            }
+        }
+    }
+}
+
+// Whether the input ends with a backslash before the newline, which isn't escaped by another backslash.
+fn ends_with_escaped_newline(input: &str) -> bool {
+    let line = input.trim_end_matches(['\n', '\r']);
+    line.len() < input.len() && (line.len() - line.trim_end_matches('\\').len()) % 2 == 1
+}
@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use crate::history::History;
+use crate::jobs::JobTable;
+
+// The session keeps the state of the shell that outlives a single command line,
+// such as the exit status of the last command, the variables, the shell options, the jobs and the history.
+//
+// The commands share the session through the functions below instead of passing it around,
+// because they are built from the AST one by one in [`crate::executor`].
+// Each thread has its own session, so the tests running in parallel don't affect each other.
+struct Session {
+    // The exit status of the last command, which is the value of '$?'.
+    last_status: i32,
+
+    // Whether the shell controls the jobs, which is true only if it reads from a terminal.
+    job_control: bool,
+
+    jobs: JobTable,
+
+    // The variables that haven't been exported.
+    // The exported variables are kept in the environment of the process, so the child processes inherit them,
+    // and a variable is never in both places.
+    variables: HashMap<String, String>,
+
+    // The shell options that are set by 'shopt -s', such as 'nullglob'.
+    options: HashSet<String>,
+
+    // The command lines that have been read, see [`History`].
+    history: History,
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            last_status: 0,
+            job_control: false,
+            jobs: JobTable::default(),
+            variables: HashMap::new(),
+            options: HashSet::new(),
+            history: History::new(),
+        }
+    }
+}
+
+thread_local! {
+    static SESSION: RefCell<Session> = RefCell::new(Session::new());
+}
+
+// Get the exit status of the last command.
+pub fn last_status() -> i32 {
+    SESSION.with(|session| session.borrow().last_status)
+}
+
+// Set the exit status of the last command.
+pub fn set_last_status(status: i32) {
+    SESSION.with(|session| session.borrow_mut().last_status = status);
+}
+
+// Whether the shell controls the jobs.
+pub fn job_control() -> bool {
+    SESSION.with(|session| session.borrow().job_control)
+}
+
+pub fn set_job_control(enabled: bool) {
+    SESSION.with(|session| session.borrow_mut().job_control = enabled);
+}
+
+// Run the function with the job table.
+pub fn with_jobs<R>(f: impl FnOnce(&mut JobTable) -> R) -> R {
+    SESSION.with(|session| f(&mut session.borrow_mut().jobs))
+}
+
+// Run the function with the history of the command lines.
+pub fn with_history<R>(f: impl FnOnce(&mut History) -> R) -> R {
+    SESSION.with(|session| f(&mut session.borrow_mut().history))
+}
+
+// Get the value of the variable, the environment is searched first,
+// because the assignments before a command, such as 'FOO=1 cmd', are put into it for a while.
+pub fn variable(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .or_else(|| SESSION.with(|session| session.borrow().variables.get(name).cloned()))
+}
+
+// Set the variable, it stays exported if it has been exported.
+pub fn set_variable(name: &str, value: &str) {
+    if env::var_os(name).is_some() {
+        env::set_var(name, value);
+    } else {
+        SESSION.with(|session| {
+            session
+                .borrow_mut()
+                .variables
+                .insert(name.to_string(), value.to_string())
+        });
+    }
+}
+
+// Export the variable, so that the child processes inherit it.
+// The value is kept if it's None, and an unset variable is exported with an empty value.
+pub fn export_variable(name: &str, value: Option<&str>) {
+    let old_value = SESSION.with(|session| session.borrow_mut().variables.remove(name));
+    let value = value
+        .map(|value| value.to_string())
+        .or(old_value)
+        .or_else(|| env::var(name).ok())
+        .unwrap_or_default();
+
+    env::set_var(name, value);
+}
+
+// Remove the variable, whether it has been exported or not.
+pub fn unset_variable(name: &str) {
+    SESSION.with(|session| session.borrow_mut().variables.remove(name));
+    env::remove_var(name);
+}
+
+// Get the exported variables sorted by name.
+pub fn exported_variables() -> Vec<(String, String)> {
+    let mut variables: Vec<(String, String)> = env::vars().collect();
+    variables.sort();
+
+    variables
+}
+
+// Whether the shell option is set.
+pub fn option(name: &str) -> bool {
+    SESSION.with(|session| session.borrow().options.contains(name))
+}
+
+pub fn set_option(name: &str, enabled: bool) {
+    SESSION.with(|session| {
+        let options = &mut session.borrow_mut().options;
+        if enabled {
+            options.insert(name.to_string());
+        } else {
+            options.remove(name);
+        }
+    });
+}
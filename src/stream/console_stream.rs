@@ -1,32 +1,49 @@
-use std::cell::RefCell;
+use std::io::{self, Write};
 
 use super::Stream;
 
 // Output the result of the code execution to the console.
-// This is a simple implementation of the Stream trait.
+// The data is printed as soon as it's written, so the output of a long command shows up while it runs.
+// The errors are printed to stderr by another console stream, see [`ConsoleStream::stderr`],
+// so they stay on the terminal when the output is redirected by the caller of the shell.
 pub struct ConsoleStream {
-    data: RefCell<Vec<String>>,
+    // Whether the data is printed to stderr instead of stdout.
+    err: bool,
+}
+
+impl Default for ConsoleStream {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ConsoleStream {
     pub fn new() -> Self {
-        Self {
-            data: RefCell::new(Vec::new()),
-        }
+        Self { err: false }
+    }
+
+    // The console stream for the error messages.
+    pub fn stderr() -> Self {
+        Self { err: true }
     }
 }
 
 impl Stream for ConsoleStream {
-    fn input(&self, msg: String) {
-        self.data.borrow_mut().push(msg);
+    // The data is written as it is, so binary data and CRLF line breaks reach the terminal unchanged.
+    fn write(&self, data: &[u8]) {
+        if self.err {
+            // The output printed before the error is flushed first, so they show up in order.
+            let _ = io::stdout().flush();
+            let _ = io::stderr().write_all(data);
+        } else {
+            let _ = io::stdout().write_all(data);
+        }
     }
 
+    // The data has been printed, so this method just flushes the console.
     fn output(&self) -> String {
-        for msg in self.data.borrow().iter() {
-            println!("{}", msg);
-        }
-
-        self.data.borrow_mut().clear();
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
 
         "".to_string()
     }
@@ -35,4 +52,8 @@ impl Stream for ConsoleStream {
     fn is_empty(&self) -> bool {
         true
     }
+
+    fn is_console(&self) -> bool {
+        true
+    }
 }
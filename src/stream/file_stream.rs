@@ -0,0 +1,208 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use crate::{
+    file_operator::{FileOperator, UNLIMITED},
+    stream::Stream,
+};
+
+// Output the result of the code execution to a file.
+// It's used by the redirections, such as 'ls > out.txt' and 'ls >> out.txt'.
+pub struct FileStream {
+    file: RefCell<FileOperator>,
+}
+
+impl FileStream {
+    // Open the file for writing, the file will be created if it doesn't exist.
+    // If 'append' is false, the file will be truncated.
+    pub fn new(path: &str, append: bool) -> io::Result<Self> {
+        let mut file = FileOperator::new(path, !append, UNLIMITED);
+        file.open()?;
+
+        Ok(Self {
+            file: RefCell::new(file),
+        })
+    }
+}
+
+impl Stream for FileStream {
+    fn write(&self, data: &[u8]) {
+        // The stream has no way to report the error, so the data is dropped when writing fails.
+        let _ = self.file.borrow_mut().write_byte(data);
+    }
+
+    // The data has been written to the file, so this method just flushes the file.
+    fn output(&self) -> String {
+        let _ = self.file.borrow_mut().flush();
+        "".to_string()
+    }
+
+    // The file stream doesn't provide data to other commands, so the result is always true.
+    fn is_empty(&self) -> bool {
+        true
+    }
+
+    fn is_console(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for FileStream {
+    fn drop(&mut self) {
+        self.file.borrow_mut().close();
+    }
+}
+
+// The input of a command that is read from a file bit by bit while the command runs,
+// such as the file of the redirection in 'wc -l < big.log', or the pipe that a child process reads its input from.
+pub struct InputFileStream {
+    // It's None after the end of the file or after the stream has been closed.
+    file: RefCell<Option<File>>,
+
+    // The data that has been read from the file but not taken yet, such as the rest of a block after a line.
+    buffer: RefCell<VecDeque<u8>>,
+}
+
+impl InputFileStream {
+    // Open the file for reading.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        if file.metadata()?.is_dir() {
+            return Err(io::Error::from_raw_os_error(libc::EISDIR));
+        }
+
+        Ok(Self::from_file(file))
+    }
+
+    pub fn from_file(file: File) -> Self {
+        Self {
+            file: RefCell::new(Some(file)),
+            buffer: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    // Read the next block of the file into the buffer, false is returned at the end of the file.
+    // The stream has no way to report the error, so reading stops when it fails.
+    fn fill(&self) -> bool {
+        let mut file = self.file.borrow_mut();
+        let mut buf = [0; 8192];
+        loop {
+            let result = match file.as_mut() {
+                Some(file) => file.read(&mut buf),
+                None => return false,
+            };
+
+            match result {
+                Ok(len) if len > 0 => {
+                    self.buffer.borrow_mut().extend(&buf[..len]);
+                    return true;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                _ => {
+                    file.take();
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+impl Stream for InputFileStream {
+    // The data comes from the file.
+    fn write(&self, _data: &[u8]) {}
+
+    // Read the rest of the file as text.
+    fn output(&self) -> String {
+        super::to_text(&super::read_all(self))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buffer.borrow().is_empty() && !self.fill()
+    }
+
+    fn is_console(&self) -> bool {
+        false
+    }
+
+    fn read(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.buffer.borrow_mut().drain(..).collect())
+    }
+
+    fn read_line(&self) -> Option<String> {
+        // Read until the buffer has a whole line or the file has ended.
+        let mut at_end = false;
+        while !at_end && !self.buffer.borrow().contains(&b'\n') {
+            at_end = !self.fill();
+        }
+
+        super::take_line(&mut self.buffer.borrow_mut(), at_end)
+    }
+
+    // Close the file, so a child process that writes to the pipe knows that it can stop.
+    fn close(&self) {
+        self.file.borrow_mut().take();
+        self.buffer.borrow_mut().clear();
+    }
+
+    // The file is moved to the reader after the data that has been read into the buffer.
+    fn take_reader(&self) -> Box<dyn io::Read + Send> {
+        let buffer: Vec<u8> = self.buffer.borrow_mut().drain(..).collect();
+        match self.file.borrow_mut().take() {
+            Some(file) => Box::new(io::Cursor::new(buffer).chain(file)),
+            None => Box::new(io::Cursor::new(buffer)),
+        }
+    }
+}
+
+// The stream that a child process of the shell writes its output to, such as the source command of a pipeline,
+// the data is written to the pipe as it is while the commands run, so the shell reads it at the same time.
+pub struct PipeFileStream {
+    file: RefCell<File>,
+
+    // Whether the shell has stopped reading the pipe, the data written after it is dropped.
+    closed: Cell<bool>,
+}
+
+impl PipeFileStream {
+    pub fn new(file: File) -> Self {
+        Self {
+            file: RefCell::new(file),
+            closed: Cell::new(false),
+        }
+    }
+}
+
+impl Stream for PipeFileStream {
+    fn write(&self, data: &[u8]) {
+        if self.closed.get() {
+            return;
+        }
+
+        if self.file.borrow_mut().write_all(data).is_err() {
+            self.closed.set(true);
+        }
+    }
+
+    // The data has been written to the pipe, so there is nothing to output.
+    fn output(&self) -> String {
+        String::new()
+    }
+
+    fn is_empty(&self) -> bool {
+        true
+    }
+
+    fn is_console(&self) -> bool {
+        false
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+}
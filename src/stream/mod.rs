@@ -1,4 +1,9 @@
+use std::collections::VecDeque;
+use std::io;
+
 pub mod console_stream;
+pub mod file_stream;
+pub mod pipe_stream;
 pub mod pipeline_stream;
 
 // This stream is used to output the result of the execution of the code.
@@ -8,12 +13,153 @@ pub mod pipeline_stream;
 // The file stream is used to output the result of the code execution to a file,
 // the type of the stream is determined by the way that the executor is called.
 // If use the REPL, it will create a console stream and transfer it to the executor,
-// but in pipeline command, it will create a pipeline stream for data source and data destination.
+// but in pipeline command, it will create a pipe stream for data source and data destination,
+// so that the data flows from one command to the next one while both of them are running.
 //
 // In conclusion, each 'exe' command has a stream for outputting result, and the stream is passed in when creating the 'exe' command.
 // As for the result type of output and the implementation method, it's determined by the caller.
 pub trait Stream {
-    fn input(&self, msg: String);
+    // Write the data as it is, such as the output of a program or the content of a binary file.
+    fn write(&self, data: &[u8]);
+
+    // Write a line of text, the line break is added at the end.
+    fn input(&self, msg: String) {
+        let mut data = msg.into_bytes();
+        data.push(b'\n');
+        self.write(&data);
+    }
+
+    // Take the rest of the data as text, the line break at the end of the last line is dropped.
     fn output(&self) -> String;
+
     fn is_empty(&self) -> bool;
+
+    // Whether the data will be printed to the console directly.
+    // External programs inherit the terminal when they write to the console,
+    // so that interactive programs and colored output keep working.
+    fn is_console(&self) -> bool;
+
+    // Read the next chunk of the data as it is, None is returned at the end of the data.
+    // The streams that don't provide data to other commands always return None.
+    fn read(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    // Read the next line of the data without the line break, None is returned at the end of the data.
+    // Only '\n' ends a line, so the '\r' of a CRLF line break is kept and written back with the line.
+    fn read_line(&self) -> Option<String> {
+        None
+    }
+
+    // Whether the reader of the data has stopped, such as 'head' in 'cat big.log | head -1',
+    // so the command that writes to the stream can stop early.
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    // Stop reading the data, the writer is told by [`is_closed`] that the data isn't needed anymore.
+    fn close(&self) {}
+
+    // Take the rest of the data as a reader that can be moved to another thread,
+    // such as the thread that writes the input of a child process of the shell bit by bit.
+    // The streams that get their data while it's read hand over their source,
+    // and the writer is told that the data isn't needed anymore when the reader is dropped.
+    fn take_reader(&self) -> Box<dyn io::Read + Send> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.read() {
+            data.extend(chunk);
+        }
+
+        Box::new(io::Cursor::new(data))
+    }
+}
+
+// Read the data of the stream line by line.
+pub fn lines(stream: &dyn Stream) -> Lines<'_> {
+    Lines { stream }
+}
+
+// The iterator over the lines of a stream, see [`lines`].
+pub struct Lines<'a> {
+    stream: &'a dyn Stream,
+}
+
+impl Iterator for Lines<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.stream.read_line()
+    }
+}
+
+// Read the rest of the data of the stream as it is.
+pub fn read_all(stream: &dyn Stream) -> Vec<u8> {
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.read() {
+        data.extend(chunk);
+    }
+
+    data
+}
+
+// Read the data of the stream as it is through [`io::Read`],
+// so it can be used like a file, such as by [`io::BufReader`].
+pub fn reader(stream: &dyn Stream) -> Reader<'_> {
+    Reader {
+        stream,
+        chunk: Vec::new(),
+        pos: 0,
+    }
+}
+
+// The reader over the data of a stream, see [`reader`].
+pub struct Reader<'a> {
+    stream: &'a dyn Stream,
+
+    // The chunk that has been read from the stream, and the position of the data that hasn't been read.
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl io::Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.chunk.len() {
+            match self.stream.read() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+// Take the next line from the buffered data of a stream, the line break isn't included.
+// The last line may have no line break, it's taken only at the end of the data.
+// The bytes that aren't valid UTF-8 are replaced, the data is kept as it is by [`Stream::read`] only.
+fn take_line(buffer: &mut VecDeque<u8>, at_end: bool) -> Option<String> {
+    let line: Vec<u8> = match buffer.iter().position(|&byte| byte == b'\n') {
+        Some(index) => {
+            let mut line: Vec<u8> = buffer.drain(..=index).collect();
+            line.pop();
+            line
+        }
+        None if at_end && !buffer.is_empty() => buffer.drain(..).collect(),
+        None => return None,
+    };
+
+    Some(String::from_utf8_lossy(&line).into_owned())
+}
+
+// Convert the data to text, the line break at the end of the last line is dropped.
+fn to_text(data: &[u8]) -> String {
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    String::from_utf8_lossy(data).into_owned()
 }
@@ -0,0 +1,200 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+
+use crate::stream::Stream;
+
+// The number of chunks that the pipe holds before the writer has to wait for the reader.
+// A chunk is a line written by a builtin command, or a block of the output of a program.
+const PIPE_CAPACITY: usize = 1024;
+
+// The pipe connects two commands of a pipeline that run at the same time, such as 'cat big.log | grep x'.
+// The data flows through a bounded buffer, so the reader gets it as soon as it's written,
+// and the writer waits when the reader falls behind, instead of holding all of the data in memory.
+// When the reader stops, the writer is told by [`Stream::is_closed`], so it can stop early.
+pub fn pipe_stream() -> (PipeWriter, PipeReader) {
+    let (sender, receiver) = mpsc::sync_channel(PIPE_CAPACITY);
+    new_pipe(Sender::Bounded(sender), receiver)
+}
+
+// The pipe that holds all of the data, so the writer never waits for the reader.
+// It's used when the reader reads the data only after the writer has finished,
+// such as the errors of the source command of a pipeline.
+pub fn unbounded_pipe_stream() -> (PipeWriter, PipeReader) {
+    let (sender, receiver) = mpsc::channel();
+    new_pipe(Sender::Unbounded(sender), receiver)
+}
+
+fn new_pipe(sender: Sender, receiver: Receiver<Vec<u8>>) -> (PipeWriter, PipeReader) {
+    let writer = PipeWriter {
+        sender,
+        closed: Cell::new(false),
+    };
+    let reader = PipeReader {
+        receiver: RefCell::new(Some(receiver)),
+        buffer: RefCell::new(VecDeque::new()),
+    };
+
+    (writer, reader)
+}
+
+enum Sender {
+    Bounded(SyncSender<Vec<u8>>),
+    Unbounded(mpsc::Sender<Vec<u8>>),
+}
+
+// The end of the pipe for writing, it's moved to the thread of the command that writes the data.
+// The reader gets to the end of the data when the writer is dropped.
+pub struct PipeWriter {
+    sender: Sender,
+
+    // Whether the reader has stopped, the data written after it is dropped.
+    closed: Cell<bool>,
+}
+
+impl Stream for PipeWriter {
+    fn write(&self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        let result = match &self.sender {
+            Sender::Bounded(sender) => sender.send(data.to_vec()),
+            Sender::Unbounded(sender) => sender.send(data.to_vec()),
+        };
+        if result.is_err() {
+            self.closed.set(true);
+        }
+    }
+
+    // The data has been sent to the reader, so there is nothing to output.
+    fn output(&self) -> String {
+        String::new()
+    }
+
+    fn is_empty(&self) -> bool {
+        true
+    }
+
+    fn is_console(&self) -> bool {
+        false
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+}
+
+// The end of the pipe for reading, it's the input stream of the command that reads the data.
+pub struct PipeReader {
+    // It's None after the reader has been closed.
+    receiver: RefCell<Option<Receiver<Vec<u8>>>>,
+
+    // The data that has been received but not read yet, such as the rest of a chunk after a line.
+    buffer: RefCell<VecDeque<u8>>,
+}
+
+impl PipeReader {
+    // Wait for the next chunk and put it into the buffer, false is returned at the end of the data.
+    fn receive(&self) -> bool {
+        let chunk = match self.receiver.borrow().as_ref() {
+            Some(receiver) => receiver.recv().ok(),
+            None => None,
+        };
+
+        match chunk {
+            Some(chunk) => {
+                self.buffer.borrow_mut().extend(chunk);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Stream for PipeReader {
+    // The data is written by the other end of the pipe.
+    fn write(&self, _data: &[u8]) {}
+
+    // Read the rest of the data, it waits until the writer has finished.
+    fn output(&self) -> String {
+        super::to_text(&super::read_all(self))
+    }
+
+    // It waits until some data is written or the writer has finished.
+    fn is_empty(&self) -> bool {
+        self.buffer.borrow().is_empty() && !self.receive()
+    }
+
+    fn is_console(&self) -> bool {
+        false
+    }
+
+    fn read(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.buffer.borrow_mut().drain(..).collect())
+    }
+
+    fn read_line(&self) -> Option<String> {
+        // Wait until the buffer has a whole line or the writer has finished.
+        let mut at_end = false;
+        while !at_end && !self.buffer.borrow().contains(&b'\n') {
+            at_end = !self.receive();
+        }
+
+        super::take_line(&mut self.buffer.borrow_mut(), at_end)
+    }
+
+    // Drop the receiver, so that the writer fails to send the data and knows that it can stop.
+    fn close(&self) {
+        self.receiver.borrow_mut().take();
+        self.buffer.borrow_mut().clear();
+    }
+
+    // The receiver is moved to the reader, so the data is received while it's read,
+    // and the writer can stop when the reader is dropped.
+    fn take_reader(&self) -> Box<dyn io::Read + Send> {
+        Box::new(ChannelReader {
+            receiver: self.receiver.borrow_mut().take(),
+            chunk: self.buffer.borrow_mut().drain(..).collect(),
+            pos: 0,
+        })
+    }
+}
+
+// The reader over the data that the end of the pipe for reading has given away, see [`PipeReader::take_reader`].
+struct ChannelReader {
+    receiver: Option<Receiver<Vec<u8>>>,
+
+    // The chunk that has been received, and the position of the data that hasn't been read.
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl io::Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self
+                .receiver
+                .as_ref()
+                .and_then(|receiver| receiver.recv().ok())
+            {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
@@ -1,41 +1,58 @@
 use std::cell::RefCell;
+use std::collections::VecDeque;
 
 use crate::stream::Stream;
 
+// The stream that keeps the data in memory until it's read,
+// such as the output of a command substitution or the content of a redirected file.
 pub struct PipeLineStream {
-    data: RefCell<Vec<String>>,
+    data: RefCell<VecDeque<u8>>,
+}
+
+impl Default for PipeLineStream {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PipeLineStream {
     pub fn new() -> Self {
         Self {
-            data: RefCell::new(Vec::new()),
+            data: RefCell::new(VecDeque::new()),
         }
     }
-
-    // This method is used to join the data in the vector into a single string.
-    fn joint_data(&self) -> String {
-        let mut result = String::new();
-        for msg in self.data.borrow().iter() {
-            result.push_str(&(msg.to_string() + "\n"));
-        }
-        result.trim().to_string()
-    }
 }
 
 impl Stream for PipeLineStream {
-    fn input(&self, msg: String) {
-        self.data.borrow_mut().push(msg);
+    fn write(&self, data: &[u8]) {
+        self.data.borrow_mut().extend(data);
     }
 
+    // Take all of the data as text.
     fn output(&self) -> String {
-        let result = self.joint_data();
-        self.data.borrow_mut().clear();
-        result
+        let data: Vec<u8> = self.data.borrow_mut().drain(..).collect();
+        super::to_text(&data)
     }
 
     // The result of this method is used to check if getting the data from the pipeline stream.
     fn is_empty(&self) -> bool {
         self.data.borrow().is_empty()
     }
+
+    fn is_console(&self) -> bool {
+        false
+    }
+
+    fn read(&self) -> Option<Vec<u8>> {
+        let mut data = self.data.borrow_mut();
+        if data.is_empty() {
+            return None;
+        }
+
+        Some(data.drain(..).collect())
+    }
+
+    fn read_line(&self) -> Option<String> {
+        super::take_line(&mut self.data.borrow_mut(), true)
+    }
 }
@@ -8,6 +8,23 @@ pub enum TokenType {
     Grep, // grep
     Cat,  // cat
 
+    // Job control commands
+    Jobs,   // jobs
+    Fg,     // fg
+    Bg,     // bg
+    Wait,   // wait
+    Disown, // disown
+
+    // Variable commands
+    Export, // export
+    Unset,  // unset
+
+    // Shell option commands
+    Shopt, // shopt
+
+    // The builtin commands that are registered by other crates, see [`crate::builtin`].
+    Builtin,
+
     // Param
     ShortParam,
     LongParam,
@@ -16,6 +33,12 @@ pub enum TokenType {
 
     Literal, // Literal
 
+    HereDoc, // The body of a here-document, it follows the delimiter of '<<'.
+
+    Variable, // A word starting with a variable that is expanded before the command runs, such as '$HOME/bin'.
+
+    AssignmentWord, // A word that assigns a variable, such as 'NAME=value'.
+
     // Single Symbols
     Pipe,         // |
     Comma,        // ,
@@ -43,6 +66,9 @@ pub enum TokenType {
 
     // Combined Symbols
     DoubleMinus,          // --
+    DoubleGreaterThan,    // >>
+    GreaterThanAnd,       // >&
+    DoubleLessThan,       // <<
     GreaterThanOrEqualTo, // >=
     LessThanOrEqualTo,    // <=
     EqualTo,              // ==
@@ -54,20 +80,43 @@ pub enum TokenType {
     Eof,
 }
 
+// The position of the token in the command line, from the index of its first char to the one after its last char.
+// The indexes count the chars instead of the bytes, so they're the columns where the token is shown,
+// and the parser uses them to point at the token that causes an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 // This struct stores the token information that the lexer will analyze.
 // And the parser will use the token to build the AST.
 pub struct Token {
     token_type: TokenType,
     literal: String,
+
+    // The tokens that aren't made by the lexer, such as the ones in tests, are at the start of the command line.
+    span: Span,
 }
 
 impl Token {
     #[allow(dead_code)]
     pub fn new(token_type: TokenType, literal: &str) -> Token {
+        Self::with_span(token_type, literal, Span::default())
+    }
+
+    pub fn with_span(token_type: TokenType, literal: &str, span: Span) -> Token {
         Token {
             token_type,
             literal: literal.to_string(),
+            span,
         }
     }
 
@@ -78,4 +127,8 @@ impl Token {
     pub fn token_type(&self) -> &TokenType {
         &self.token_type
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
@@ -0,0 +1,84 @@
+mod common;
+
+// The working directory is shared by the whole process,
+// so all the cases of 'cd' run in one test to avoid changing it under other tests.
+#[cfg(test)]
+mod cd_test {
+    use std::{env, fs, rc::Rc};
+
+    use ru_shell::executor;
+    use ru_shell::stream::{pipeline_stream::PipeLineStream, Stream};
+
+    use crate::common::{lock_env, temp_dir};
+
+    #[test]
+    fn test_cd_cmd() {
+        // The directory and the variables are restored when the test ends.
+        let _env = lock_env();
+        let origin = env::current_dir().unwrap();
+        let dir = temp_dir("cd_test");
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        let root = dir.canonicalize().unwrap();
+        let stream = Rc::new(PipeLineStream::new());
+
+        // Absolute path
+        executor::execute(&format!("cd {}", root.display()), stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root);
+        assert_eq!(env::var("PWD").unwrap(), root.display().to_string());
+
+        // Relative path, and '..'
+        executor::execute("cd a/b", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b"));
+        executor::execute("cd ..", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a"));
+        assert_eq!(env::var("OLDPWD").unwrap(), root.join("a/b").display().to_string());
+
+        // 'cd -' goes back to $OLDPWD and prints it
+        executor::execute("cd -", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b"));
+        assert_eq!(stream.output(), root.join("a/b").display().to_string());
+
+        // $CDPATH
+        env::set_var("CDPATH", root.join("a").display().to_string());
+        executor::execute(&format!("cd {}", origin.display()), stream.clone());
+        executor::execute("cd b", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b"));
+        assert_eq!(stream.output(), root.join("a/b").display().to_string());
+
+        // '.' and an empty entry are the current directory at their position, and the path isn't printed.
+        fs::create_dir_all(root.join("a/b/b")).unwrap();
+        env::set_var("CDPATH", format!(".:{}", root.join("a").display()));
+        executor::execute("cd b", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b/b"));
+        assert_eq!(stream.output(), "");
+        env::set_var("CDPATH", format!("{}::", root.join("a").display()));
+        executor::execute("cd ..; cd b", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b"));
+        assert_eq!(stream.output(), root.join("a/b").display().to_string());
+        env::set_var("CDPATH", format!(":{}", root.join("a").display()));
+        executor::execute("cd b", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b/b"));
+        assert_eq!(stream.output(), "");
+        executor::execute("cd ..", stream.clone());
+        env::remove_var("CDPATH");
+
+        // Errors don't change the directory
+        executor::execute("cd no_such_dir", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b"));
+        assert_eq!(stream.output(), "cd: no_such_dir: No such file or directory");
+
+        // No argument goes to $HOME
+        env::set_var("HOME", &root);
+        executor::execute("cd", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root);
+        executor::execute("cd ~/a", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a"));
+
+        // The subshell has its own directory, but the group changes the one of the shell.
+        executor::execute("(cd b && pwd); pwd", stream.clone());
+        let a = root.join("a").display().to_string();
+        assert_eq!(stream.output(), format!("{}/b\n{}", a, a));
+        executor::execute("{ cd b; }", stream.clone());
+        assert_eq!(env::current_dir().unwrap(), root.join("a/b"));
+    }
+}
@@ -0,0 +1,84 @@
+// The helpers shared by the tests, each test file that uses them declares 'mod common;'.
+// Not every test file uses all of them.
+#![allow(dead_code)]
+
+use std::ffi::OsString;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::{env, fs, process};
+
+// The directory for the files of a test, such as '/tmp/ru_shell_glob_test_42'.
+// The pid is a part of the name, so the test processes that run at the same time don't share it.
+// It's created empty, and it's removed with its files when it's dropped, even if the test fails.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+pub fn temp_dir(name: &str) -> TempDir {
+    let path = env::temp_dir().join(format!("ru_shell_{}_{}", name, process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    TempDir { path }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+// The variables of the environment that the tests change, the shell reads them too, such as 'cd' and '~'.
+const ENV_VARS: [&str; 4] = ["HOME", "CDPATH", "PWD", "OLDPWD"];
+
+// The tests of a file run in several threads of one process, which share the environment.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+// The lock of the environment, see [`lock_env`].
+pub struct EnvLock {
+    vars: Vec<(&'static str, Option<OsString>)>,
+    dir: PathBuf,
+    _guard: MutexGuard<'static, ()>,
+}
+
+// Lock the environment for a test that changes or reads the variables in [`ENV_VARS`] or the working directory,
+// so the tests that use them run one at a time. They're restored when the lock is dropped.
+pub fn lock_env() -> EnvLock {
+    let guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    EnvLock {
+        vars: ENV_VARS
+            .iter()
+            .map(|&name| (name, env::var_os(name)))
+            .collect(),
+        dir: env::current_dir().unwrap(),
+        _guard: guard,
+    }
+}
+
+impl Drop for EnvLock {
+    fn drop(&mut self) {
+        for (name, value) in &self.vars {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+        let _ = env::set_current_dir(&self.dir);
+    }
+}
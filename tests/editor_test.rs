@@ -0,0 +1,215 @@
+mod common;
+
+#[cfg(test)]
+mod editor_test {
+    use std::fs;
+
+    use ru_shell::editor::buffer::LineBuffer;
+    use ru_shell::editor::completion::{self, complete};
+    use ru_shell::editor::key::{read_key, Key};
+    use ru_shell::editor::terminal::display_width;
+    use ru_shell::editor::{Action, LineEditor};
+
+    use crate::common::temp_dir;
+
+    #[test]
+    fn test_read_key() {
+        let mut input: &[u8] = "a你\x1b[A\x1b[1;5C\x1b[3~\x1bOH\x01\x1f\x7f\r\x1bb\t".as_bytes();
+
+        let keys = [
+            Key::Char('a'),
+            Key::Char('你'),
+            Key::Up,
+            Key::CtrlRight,
+            Key::Delete,
+            Key::Home,
+            Key::Ctrl('a'),
+            Key::Ctrl('_'),
+            Key::Backspace,
+            Key::Enter,
+            Key::Alt('b'),
+            Key::Tab,
+        ];
+        for key in keys {
+            assert_eq!(read_key(&mut input).unwrap(), Some(key));
+        }
+        assert_eq!(read_key(&mut input).unwrap(), None);
+
+        // The sequences that aren't supported are read as a whole.
+        let mut input: &[u8] = b"\x1b[15~x";
+        assert_eq!(read_key(&mut input).unwrap(), Some(Key::Unknown));
+        assert_eq!(read_key(&mut input).unwrap(), Some(Key::Char('x')));
+    }
+
+    #[test]
+    fn test_line_buffer() {
+        let mut buffer = LineBuffer::new();
+        for c in "ls 你好".chars() {
+            buffer.insert(c);
+        }
+        assert_eq!(buffer.cursor(), 5);
+
+        // The multi-byte chars are moved over and deleted as a whole.
+        buffer.move_left();
+        buffer.backspace();
+        assert_eq!(buffer.text(), "ls 好");
+        assert_eq!(buffer.cursor(), 3);
+
+        buffer.move_end();
+        buffer.replace(buffer.cursor(), " src");
+        buffer.move_word_left();
+        assert_eq!(buffer.cursor(), 5);
+        buffer.kill_to_end();
+        assert_eq!(buffer.text(), "ls 好 ");
+        buffer.kill_word_before();
+        assert_eq!(buffer.text(), "ls ");
+        buffer.move_home();
+        buffer.move_word_right();
+        assert_eq!(buffer.cursor(), 2);
+        buffer.kill_to_start();
+        assert_eq!(buffer.text(), " ");
+
+        // The edits are undone one by one.
+        for text in ["ls ", "ls 好 ", "ls 好 src", "ls 好", "ls 你好"] {
+            assert!(buffer.undo());
+            assert_eq!(buffer.text(), text);
+        }
+
+        // The chars typed in a row are undone together, a blank starts a new word.
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "ls");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "");
+        assert!(!buffer.undo());
+    }
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::new();
+        let history: Vec<String> = ["ls", "", "cd src", "cd src"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        editor.set_history(&history);
+
+        for c in "pwd".chars() {
+            editor.handle_key(Key::Char(c));
+        }
+
+        // The history is browsed from the last line, and the line being typed is kept.
+        editor.handle_key(Key::Up);
+        assert_eq!(editor.buffer().text(), "cd src");
+        editor.handle_key(Key::Ctrl('p'));
+        assert_eq!(editor.buffer().text(), "ls");
+        editor.handle_key(Key::Up);
+        assert_eq!(editor.buffer().text(), "ls");
+        editor.handle_key(Key::Down);
+        editor.handle_key(Key::Ctrl('n'));
+        assert_eq!(editor.buffer().text(), "pwd");
+
+        editor.handle_key(Key::Ctrl('a'));
+        editor.handle_key(Key::Ctrl('d'));
+        editor.handle_key(Key::Ctrl('e'));
+        editor.handle_key(Key::Char('!'));
+        assert_eq!(
+            editor.handle_key(Key::Enter),
+            Action::Accept(String::from("wd!"))
+        );
+
+        editor.handle_key(Key::Ctrl('u'));
+        assert_eq!(editor.handle_key(Key::Ctrl('d')), Action::Eof);
+        assert_eq!(editor.handle_key(Key::Ctrl('c')), Action::Cancel);
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("> "), 2);
+        assert_eq!(display_width("你好"), 4);
+        // The colors take no columns, and the control chars are shown as '^J'.
+        assert_eq!(display_width("\x1b[1;32mru\x1b[0m> "), 4);
+        assert_eq!(display_width("a\nb"), 4);
+    }
+
+    #[test]
+    fn test_complete() {
+        // The options of the builtin commands are completed from their declarations.
+        let completion = complete("ls -l --de", 10);
+        assert_eq!(completion.start, 6);
+        assert_eq!(completion.candidates, vec!["--depth"]);
+        assert_eq!(
+            complete("cat x; grep -", 13).candidates,
+            vec![
+                "--count",
+                "--ignore-case",
+                "--invert-match",
+                "--line-number",
+                "-c",
+                "-i",
+                "-n",
+                "-v"
+            ]
+        );
+
+        // The commands are completed at the position of a command only.
+        let completion = complete("echo a | A=1 gre", 16);
+        assert_eq!(completion.start, 13);
+        assert!(completion.candidates.contains(&String::from("grep")));
+        assert!(complete("echo gre", 8).candidates.is_empty());
+
+        // The paths keep the directory as it's typed, and the special chars are escaped.
+        let dir = temp_dir("complete_test");
+        fs::create_dir_all(dir.join("sub dir")).unwrap();
+        fs::write(dir.join("file a"), "").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+        let line = format!("cat src,{}/", dir.display());
+        let completion = complete(&line, line.chars().count());
+        assert_eq!(completion.start, 8);
+        assert_eq!(
+            completion.candidates,
+            vec![
+                format!("{}/file\\ a", dir.display()),
+                format!("{}/sub\\ dir/", dir.display()),
+            ]
+        );
+        let line = format!("ls {}/sub\\ ", dir.display());
+        let completion = complete(&line, line.chars().count());
+        assert_eq!(
+            completion.candidates,
+            vec![format!("{}/sub\\ dir/", dir.display())]
+        );
+
+        // The word is completed before the cursor, the rest of the line is kept.
+        let mut editor = LineEditor::new();
+        for c in "ls --tr src".chars() {
+            editor.handle_key(Key::Char(c));
+        }
+        for _ in 0..4 {
+            editor.handle_key(Key::Left);
+        }
+        editor.handle_key(Key::Tab);
+        assert_eq!(editor.buffer().text(), "ls --tree src");
+        assert_eq!(editor.buffer().cursor(), 9);
+    }
+
+    #[test]
+    fn test_completion_columns() {
+        let items: Vec<String> = ["a", "bb", "ccc", "dddd", "你好"]
+            .iter()
+            .map(|item| item.to_string())
+            .collect();
+
+        assert_eq!(completion::common_prefix(&items[..1]), "a");
+        assert_eq!(
+            completion::common_prefix(&[String::from("src/ma"), String::from("src/mb")]),
+            "src/m"
+        );
+        assert_eq!(completion::display_name("src/editor/"), "editor/");
+
+        // The items go down the columns, which are as wide as the widest item and two blanks.
+        assert_eq!(
+            completion::columns(&items, 13),
+            vec!["a     dddd", "bb    你好", "ccc"]
+        );
+        assert_eq!(completion::columns(&items[..2], 1), vec!["a", "bb"]);
+    }
+}
@@ -1,10 +1,36 @@
+mod common;
+
 #[cfg(test)]
 mod executor_test {
-    use std::{env, rc::Rc};
+    use std::os::unix::fs::PermissionsExt;
+    use std::{env, io, rc::Rc};
 
+    use ru_shell::builtin::{self, Builtin, OptionSpec};
+    use ru_shell::executor::{error::ShellError, external::ExternalCmd, Command};
+    use ru_shell::parser::ast_node_trait::CommandAstNode;
+    use ru_shell::parser::cmds_ast_node::ExeCommandAstNode;
+    use ru_shell::stream::pipeline_stream::PipeLineStream;
+    use ru_shell::token::token::{Token, TokenType};
     use ru_shell::{executor, stream::console_stream::ConsoleStream};
     use ru_shell::stream::Stream;
 
+    use crate::common::{lock_env, temp_dir};
+
+    // Run the command line, and get the exit status and the output.
+    fn run(cmd: &str) -> (i32, String) {
+        let stream = Rc::new(PipeLineStream::new());
+        let status = executor::execute(cmd, stream.clone());
+        (status, stream.output())
+    }
+
+    // Run the command line, and get the exit status, the output and the errors.
+    fn run_with_err(cmd: &str) -> (i32, String, String) {
+        let stream = Rc::new(PipeLineStream::new());
+        let err_stream = Rc::new(PipeLineStream::new());
+        let status = executor::execute_with_err_stream(cmd, stream.clone(), err_stream.clone());
+        (status, stream.output(), err_stream.output())
+    }
+
     #[test]
     fn test_new_executor() {
         let console_stream = Rc::new(ConsoleStream::new());
@@ -78,4 +104,603 @@ mod executor_test {
         executor::execute("ls -l | cat -n | grep \"Car\"", console_stream.clone());
         console_stream.output();
     }
+
+    #[test]
+    fn test_external_cmd() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("echo hello", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "hello");
+    }
+
+    #[test]
+    fn test_external_cmd_in_pipeline() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("cat Cargo.toml | head -n 1", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "[package]");
+    }
+
+    #[test]
+    fn test_external_cmd_not_found() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("no_such_program_ru_shell", pipeline_stream.clone());
+        assert_eq!(
+            pipeline_stream.output(),
+            "ru_shell: command not found: no_such_program_ru_shell"
+        );
+
+        // The program is found, but its interpreter isn't.
+        let dir = temp_dir("exec_test");
+        let path = dir.join("script");
+        std::fs::write(&path, "#!/no_such_interpreter_ru_shell\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        let status = executor::execute(&path.display().to_string(), pipeline_stream.clone());
+        assert_eq!(status, 126);
+        assert_eq!(
+            pipeline_stream.output(),
+            format!("ru_shell: {}: No such file or directory", path.display())
+        );
+    }
+
+    #[test]
+    fn test_external_cmd_exit_status() {
+        let mut node = ExeCommandAstNode::new(Token::new(TokenType::Literal, "sh"));
+        node.set_values(vec!["sh".to_string(), "-c".to_string(), "exit 3".to_string()]);
+
+        let mut cmd = ExternalCmd::from(Box::new(node) as Box<dyn CommandAstNode>);
+        cmd.add_stream(Rc::new(PipeLineStream::new()));
+        assert_eq!(cmd.exit_status(), None);
+
+        cmd.execute();
+        assert_eq!(cmd.exit_status().unwrap().code(), Some(3));
+    }
+
+    #[test]
+    fn test_output_redirection() {
+        let dir = temp_dir("redirect_test");
+        let out = dir.join("out.txt");
+        let err = dir.join("err.txt");
+        let stream = Rc::new(PipeLineStream::new());
+
+        // Truncate
+        executor::execute(&format!("echo first > {}", out.display()), stream.clone());
+        executor::execute(&format!("echo second > {}", out.display()), stream.clone());
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "second\n");
+
+        // The words after the redirection are the arguments of the command
+        executor::execute(&format!("echo x > {} y", out.display()), stream.clone());
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "x y\n");
+        executor::execute(&format!("echo second > {}", out.display()), stream.clone());
+
+        // Append, the builtin commands can be redirected too
+        executor::execute(&format!("cat Cargo.toml >> {}", out.display()), stream.clone());
+        let content = std::fs::read_to_string(&out).unwrap();
+        assert!(content.starts_with("second\n[package]\n"));
+
+        // Errors to file
+        executor::execute(
+            &format!("/bin/ls no_such_file_ru_shell 2> {}", err.display()),
+            stream.clone(),
+        );
+        assert!(!std::fs::read_to_string(&err).unwrap().is_empty());
+
+        // Errors to the same place as the output
+        executor::execute(
+            &format!("/bin/ls no_such_file_ru_shell > {} 2>&1", out.display()),
+            stream.clone(),
+        );
+        assert!(!std::fs::read_to_string(&out).unwrap().is_empty());
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn test_input_redirection() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("grep \"^name\" < Cargo.toml", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "name = \"ru_shell\"");
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("wc -l < Cargo.toml | cat", pipeline_stream.clone());
+        let lines = std::fs::read_to_string("Cargo.toml").unwrap().lines().count();
+        assert_eq!(pipeline_stream.output(), lines.to_string());
+
+        // The file is read while the command runs, so an endless file works too.
+        assert_eq!(run("head -c 3 < /dev/zero | wc -c"), (0, String::from("3")));
+        assert_eq!(run("cat < /dev/zero | head -c 3 | wc -c"), (0, String::from("3")));
+        assert_eq!(run("(head -c 3) < /dev/zero | wc -c"), (0, String::from("3")));
+    }
+
+    #[test]
+    fn test_redirection_error() {
+        // The file that can't be opened is reported without the code of the system error.
+        let (status, _, errors) = run_with_err("cat < no_such_file_ru_shell");
+        assert_eq!(status, 1);
+        assert_eq!(errors, "ru_shell: no_such_file_ru_shell: No such file or directory");
+
+        let (status, _, errors) = run_with_err("echo x > /no_such_dir_ru_shell/out.txt");
+        assert_eq!(status, 1);
+        assert_eq!(
+            errors,
+            "ru_shell: /no_such_dir_ru_shell/out.txt: No such file or directory"
+        );
+        assert!(!errors.contains("os error"));
+    }
+
+    #[test]
+    fn test_heredoc() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("cat <<EOF\nhello\nworld\nEOF\n", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "hello\nworld");
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("tr a-z A-Z <<EOF\nhello\nEOF\n", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "HELLO");
+    }
+
+    #[test]
+    fn test_list_cmd() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("false && echo a; echo b", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "b");
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("true && echo a || echo b", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "a");
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("false && echo a || echo b", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "b");
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("grep \"no_such_text_xyz\" Cargo.toml || echo none", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "none");
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("true", pipeline_stream.clone()), 0);
+        assert_eq!(executor::execute("false", pipeline_stream.clone()), 1);
+        assert_eq!(executor::execute("no_such_command_ru_shell", pipeline_stream.clone()), 127);
+        assert_eq!(executor::execute("true | false", pipeline_stream.clone()), 1);
+        assert_eq!(executor::execute("false | true", pipeline_stream.clone()), 0);
+
+        assert_eq!(executor::execute("grep \"^name\" Cargo.toml", pipeline_stream.clone()), 0);
+        assert_eq!(executor::execute("grep \"no_such_text_xyz\" Cargo.toml", pipeline_stream.clone()), 1);
+        assert_eq!(executor::execute("grep \"a\" no_such_file_ru_shell", pipeline_stream.clone()), 2);
+        assert_eq!(executor::execute("cat no_such_file_ru_shell", pipeline_stream.clone()), 1);
+        assert_eq!(executor::execute("ls no_such_file_ru_shell", pipeline_stream.clone()), 2);
+        pipeline_stream.output();
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("cat no_such_file_ru_shell", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "cat: no_such_file_ru_shell: No such file or directory");
+    }
+
+    #[test]
+    fn test_status_variable() {
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("/bin/ls no_such_file_ru_shell 2> /dev/null; echo $?", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "2");
+
+        let pipeline_stream = Rc::new(PipeLineStream::new());
+        executor::execute("false || echo $?; echo $?", pipeline_stream.clone());
+        assert_eq!(pipeline_stream.output(), "1\n0");
+    }
+
+    #[test]
+    fn test_background_job() {
+        let dir = temp_dir("job_test");
+        let out = dir.join("out.txt");
+
+        // The output of the job is written to the file, and 'wait' waits for it.
+        let stream = Rc::new(PipeLineStream::new());
+        let status = executor::execute(&format!("echo hi > {} & wait", out.display()), stream.clone());
+        assert_eq!(status, 0);
+        assert!(stream.output().starts_with("[1] "));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "hi\n");
+
+        // The exit status of the job is reported by 'wait' with the job spec.
+        let stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("false & wait %1", stream.clone()), 1);
+
+        // The running jobs are listed by 'jobs', and 'fg' waits for the job.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("sleep 1 &", stream.clone());
+        stream.output();
+        executor::execute("jobs", stream.clone());
+        assert_eq!(stream.output(), format!("[1]+  {:<24}sleep 1 &", "Running"));
+        assert_eq!(executor::execute("fg %1", stream.clone()), 0);
+        assert_eq!(stream.output(), "sleep 1");
+
+        // There is no job left.
+        assert_eq!(executor::execute("fg", stream.clone()), 1);
+        assert_eq!(stream.output(), "fg: current: no such job");
+    }
+
+    #[test]
+    fn test_shell_variables() {
+        // The names are unique, because the exported variables are shared by all tests.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("RU_VAR_A=1 RU_VAR_B=${RU_VAR_A}2; echo $RU_VAR_A $RU_VAR_B/x", stream.clone());
+        assert_eq!(stream.output(), "1 12/x");
+
+        // The variable isn't exported until 'export' is used.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("printenv RU_VAR_A; echo $?", stream.clone());
+        assert_eq!(stream.output(), "1");
+
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("export RU_VAR_A RU_VAR_C=3; printenv RU_VAR_A RU_VAR_C", stream.clone());
+        assert_eq!(stream.output(), "1\n3");
+
+        // An unset variable is empty.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("unset RU_VAR_A RU_VAR_C; echo x${RU_VAR_A}${RU_VAR_C}y", stream.clone());
+        assert_eq!(stream.output(), "xy");
+        assert!(env::var("RU_VAR_C").is_err());
+
+        let stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("export 1A", stream.clone()), 1);
+        assert_eq!(stream.output(), "export: `1A': not a valid identifier");
+
+        // 'export -p' lists the variables in a form that is read back as the same value.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("export RU_VAR_Q='a\"b$c`d\\e'; export -p", stream.clone());
+        let output = stream.output();
+        let line = output.lines().find(|line| line.starts_with("export RU_VAR_Q=")).unwrap();
+        assert_eq!(line, "export RU_VAR_Q=\"a\\\"b\\$c\\`d\\\\e\"");
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute(&format!("unset RU_VAR_Q; {}; printenv RU_VAR_Q", line), stream.clone());
+        assert_eq!(stream.output(), "a\"b$c`d\\e");
+        env::remove_var("RU_VAR_Q");
+
+        let stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("export -x", stream.clone()), 2);
+        assert_eq!(
+            stream.output(),
+            "export: -x: invalid option\nexport: usage: export [-p] [name[=value] ...]"
+        );
+    }
+
+    #[test]
+    fn test_prefix_assignment() {
+        // The variable is only set for the command.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("RU_PREFIX_VAR=1 printenv RU_PREFIX_VAR; echo x${RU_PREFIX_VAR}y", stream.clone());
+        assert_eq!(stream.output(), "1\nxy");
+        assert!(env::var("RU_PREFIX_VAR").is_err());
+    }
+
+    #[test]
+    fn test_variable_in_heredoc() {
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("RU_DOC_VAR=hi; cat << EOF\n$RU_DOC_VAR there\nEOF", stream.clone());
+        assert_eq!(stream.output(), "hi there");
+    }
+
+    #[test]
+    fn test_quoting() {
+        let dir = temp_dir("quote_test");
+        let file = dir.join("my file.txt");
+        std::fs::write(&file, "hello world\nhelloworld\n").unwrap();
+
+        // The pattern and the path with spaces are single words.
+        let stream = Rc::new(PipeLineStream::new());
+        let cmd = format!("grep \"hello world\" '{}'", file.display());
+        assert_eq!(executor::execute(&cmd, stream.clone()), 0);
+        assert_eq!(stream.output(), "hello world");
+
+        let stream = Rc::new(PipeLineStream::new());
+        let cmd = format!("cat {}", file.display().to_string().replace(' ', "\\ "));
+        executor::execute(&cmd, stream.clone());
+        assert_eq!(stream.output(), "hello world\nhelloworld");
+
+        // Double quotes expand the variables, single quotes and backslashes don't.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute(
+            "RU_QUOTE_VAR='a  b'; echo \"[$RU_QUOTE_VAR]\" '[$RU_QUOTE_VAR]' \\$RU_QUOTE_VAR \"\\\"x\\\"\"",
+            stream.clone(),
+        );
+        assert_eq!(stream.output(), "[a  b] [$RU_QUOTE_VAR] $RU_QUOTE_VAR \"x\"");
+    }
+
+    #[test]
+    fn test_glob() {
+        let dir = temp_dir("glob_test");
+        std::fs::create_dir_all(dir.join("src/sub")).unwrap();
+        for file in ["b.rs", "a.rs", ".hidden.rs", "c1.txt", "c2.txt", "src/d.rs", "src/sub/e.rs"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let run_in_dir = |cmd: &str| {
+            let (status, output) = run(&cmd.replace("DIR", &dir.display().to_string()));
+            (status, output.replace(&format!("{}/", dir.display()), ""))
+        };
+
+        // The matches are sorted, and the hidden files only match a pattern starting with '.'.
+        assert_eq!(run_in_dir("echo DIR/*.rs").1, "a.rs b.rs");
+        assert_eq!(run_in_dir("echo DIR/.*.rs").1, ".hidden.rs");
+        assert_eq!(run_in_dir("echo DIR/c[0-9].t?t DIR/c[!1]*").1, "c1.txt c2.txt c2.txt");
+        assert_eq!(run_in_dir("echo DIR/**/*.rs").1, "a.rs b.rs src/d.rs src/sub/e.rs");
+        assert_eq!(run_in_dir("echo DIR/*/").1, "src/");
+
+        // The quoted pattern is kept as it is.
+        assert_eq!(run_in_dir("echo 'DIR/*.rs'").1, "*.rs");
+
+        // The pattern that matches nothing is kept, removed with nullglob, or an error with failglob.
+        assert_eq!(run_in_dir("echo DIR/*.xyz").1, "*.xyz");
+        assert_eq!(run_in_dir("shopt -s nullglob; echo x DIR/*.xyz; shopt -u nullglob").1, "x");
+        assert_eq!(run_in_dir("shopt -s failglob; echo DIR/*.xyz"), (1, String::from("ru_shell: no match: *.xyz")));
+        assert_eq!(run_in_dir("shopt -u failglob; shopt -s dotglob; echo DIR/*.rs").1, ".hidden.rs a.rs b.rs");
+        assert_eq!(run_in_dir("shopt dotglob failglob"), (1, String::from("dotglob         on\nfailglob        off")));
+    }
+
+    #[test]
+    fn test_tilde_expansion() {
+        // A temporary home, so nothing that the commands write to '~' ends up in the real one.
+        let _env = lock_env();
+        let home_dir = temp_dir("tilde_test");
+        env::set_var("HOME", &*home_dir);
+        let home = executor::expansion::home_dir().unwrap().display().to_string();
+        assert_eq!(home, home_dir.display().to_string());
+
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("echo ~ ~/src '~' \\~ ~root/x ~no_such_user_ru_shell", stream.clone());
+        assert_eq!(
+            stream.output(),
+            format!("{} {}/src ~ ~ /root/x ~no_such_user_ru_shell", home, home)
+        );
+
+        // The tilde is expanded after every ':' in the assignment.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("RU_TILDE_VAR=~/a:~/b; echo $RU_TILDE_VAR", stream.clone());
+        assert_eq!(stream.output(), format!("{}/a:{}/b", home, home));
+    }
+
+    #[test]
+    fn test_command_substitution() {
+        // The output that isn't quoted is split into words, the quoted one is a single word.
+        assert_eq!(run("echo $(echo 'a   b') \"$(echo 'a   b')\"").1, "a b a   b");
+        assert_eq!(run("echo x`echo y z`").1, "xy z");
+        assert_eq!(run("echo $(echo $(echo nested)) \"`echo \\`echo deep\\``\"").1, "nested deep");
+        assert_eq!(run("echo \"$(echo \"a)b\")\" '$(echo no)'").1, "a)b $(echo no)");
+
+        // The words of the output are the arguments of the command.
+        assert_eq!(run("grep \"^name\" $(echo Cargo.toml)").1, "name = \"ru_shell\"");
+        assert_eq!(run("RU_SUBST_VAR=$(echo 'a   b'); echo \"$RU_SUBST_VAR\"").1, "a   b");
+
+        // The commands run in a copy of the shell, so the directory and the variables don't change.
+        let dir = env::current_dir().unwrap();
+        assert_eq!(run("echo $(cd /; pwd); pwd").1, format!("/\n{}", dir.display()));
+        assert_eq!(env::current_dir().unwrap(), dir);
+        assert_eq!(run("echo $(RU_SUBST_LEAK=1)x$RU_SUBST_LEAK").1, "x");
+        assert_eq!(run("echo $(yes | head -n 2)").1, "y y");
+    }
+
+    #[test]
+    fn test_subshell_and_group() {
+        // The variables set in the subshell don't leak, and its status is the one of its last command.
+        assert_eq!(run("RU_SUBSHELL_VAR=1; (RU_SUBSHELL_VAR=2; echo $RU_SUBSHELL_VAR); echo $RU_SUBSHELL_VAR").1, "2\n1");
+        assert_eq!(run("(true; false)"), (1, String::new()));
+        assert_eq!(run("(false) || echo failed").1, "failed");
+
+        // The group runs in the shell, and its output feeds the pipeline as a whole.
+        assert_eq!(run("{ RU_GROUP_VAR=1; }; echo $RU_GROUP_VAR").1, "1");
+        assert_eq!(run("{ echo a; echo b; } | grep b").1, "b");
+        assert_eq!(run("(echo a; echo b) | grep a").1, "a");
+        assert_eq!(run("echo a | (grep a)").1, "a");
+        assert_eq!(run("(echo a) | (cat) | grep a").1, "a");
+
+        // The subshell writes its output while it runs, so it stops when the reader stops.
+        assert_eq!(run("(yes) | head -n 1"), (0, String::from("y")));
+
+        // The input is passed to the subshell while it's written, so the writer stops when the subshell stops.
+        assert_eq!(run("yes | (head -n 1)"), (0, String::from("y")));
+        assert_eq!(run("yes | (head -n 2) | cat"), (0, String::from("y\ny")));
+        assert_eq!(run("printf 'a\\nb\\n' | (cat; echo c)").1, "a\nb\nc");
+
+        let dir = env::current_dir().unwrap();
+        assert_eq!(run("(cd /; pwd) | cat; pwd").1, format!("/\n{}", dir.display()));
+    }
+
+    #[test]
+    fn test_glued_words() {
+        // The words with symbols in them are passed as they are.
+        assert_eq!(run("echo user@host").1, "user@host");
+        assert_eq!(run("echo 10:30").1, "10:30");
+        assert_eq!(run("echo --x=1").1, "--x=1");
+        assert_eq!(run("date -u -d @0 +%Y"), (0, String::from("1970")));
+
+        let dir = temp_dir("glue_test");
+        std::fs::write(dir.join("1.txt"), "one\n").unwrap();
+        assert_eq!(run(&format!("(cd {}; cat 1.txt)", dir.display())), (0, String::from("one")));
+
+        // The commas still separate the paths of the builtin commands.
+        assert_eq!(
+            run(&format!("(cd {}; cat 1.txt,1.txt)", dir.display())).1,
+            "one\none"
+        );
+    }
+
+    #[test]
+    fn test_streaming_pipeline() {
+        // The source stops early when the destination has finished, so endless sources work.
+        assert_eq!(run("yes | head -n 2"), (0, "y\ny".to_string()));
+        assert_eq!(run("yes | cat | grep y | head -n 1"), (0, "y".to_string()));
+
+        // The input of a pipeline is passed to its source command while it's written.
+        assert_eq!(run("yes | { cat | head -n 1; }"), (0, "y".to_string()));
+
+        // The data that is larger than the pipe flows through all of the commands.
+        assert_eq!(run("seq 100000 | cat | grep -c 0").1, "33571");
+        assert_eq!(run("seq 200000 | tr 1 x | tr 2 y | tail -n 1").1, "y00000");
+
+        // The commands of the source don't change the shell, and the status is the one of the destination.
+        assert_eq!(run("RU_PIPE_VAR=1; RU_PIPE_VAR=2 | true; echo $RU_PIPE_VAR").1, "1");
+        assert_eq!(run("export RU_PIPE_ENV=1 | cat; echo x$RU_PIPE_ENV").1, "x");
+        assert!(env::var("RU_PIPE_ENV").is_err());
+        let dir = env::current_dir().unwrap();
+        assert_eq!(run("cd / | cat; pwd").1, dir.display().to_string());
+        assert_eq!(env::current_dir().unwrap(), dir);
+        assert_eq!(run("true | false").0, 1);
+    }
+
+    #[test]
+    fn test_binary_pipeline() {
+        let dir = temp_dir("binary_test");
+        let input = dir.join("in.bin");
+        let data = b"a\r\nb x \r\n\n\xff\xfe\x00bin";
+        std::fs::write(&input, data).unwrap();
+        let stream = Rc::new(PipeLineStream::new());
+
+        // The data passes through the builtin and external commands byte-for-byte.
+        let cmds = ["cat {in} | cat > {out}", "cat < {in} | tr a a | cat > {out}"];
+        for (i, cmd) in cmds.iter().enumerate() {
+            let out = dir.join(format!("out{}.bin", i));
+            let cmd = cmd
+                .replace("{in}", &input.display().to_string())
+                .replace("{out}", &out.display().to_string());
+            executor::execute(&cmd, stream.clone());
+            assert_eq!(std::fs::read(&out).unwrap(), data);
+        }
+
+        // The line of grep keeps its CRLF line break and trailing whitespace.
+        executor::execute(&format!("grep x {}", input.display()), stream.clone());
+        assert_eq!(ru_shell::stream::read_all(stream.as_ref()), b"b x \r\n");
+    }
+
+    #[test]
+    fn test_err_stream() {
+        let error = "cat: no_such_file_ru_shell: No such file or directory";
+
+        // The errors don't flow into the next command of the pipeline.
+        let (_, output, errors) = run_with_err("cat no_such_file_ru_shell | grep -c No");
+        assert_eq!((output.as_str(), errors.as_str()), ("0", error));
+        assert_eq!(
+            run_with_err("no_such_program_ru_shell | cat").2,
+            "ru_shell: command not found: no_such_program_ru_shell"
+        );
+
+        // The errors of the builtin commands, the subshells and the groups can be redirected.
+        let (_, output, errors) = run_with_err("cat no_such_file_ru_shell 2>&1 | grep -c No");
+        assert_eq!((output.as_str(), errors.as_str()), ("1", ""));
+        let (_, output, errors) = run_with_err("(cat no_such_file_ru_shell; echo out) | cat");
+        assert_eq!((output.as_str(), errors.as_str()), ("out", error));
+        assert_eq!(
+            run_with_err("{ cd no_such_dir_ru_shell; } 2>&1").1,
+            "cd: no_such_dir_ru_shell: No such file or directory"
+        );
+    }
+
+    #[test]
+    fn test_shell_error() {
+        let dir = temp_dir("error_test");
+        let file = dir.join("lines.txt");
+        std::fs::write(&file, "a\nb\n").unwrap();
+        let file = file.display().to_string();
+
+        // The commands that take several paths go on after the one that fails.
+        let (status, output, errors) = run_with_err(&format!("cat -n {} no_such_file_ru_shell {}", file, file));
+        assert_eq!(status, 1);
+        assert_eq!(output, "1 a\n2 b\n3 a\n4 b");
+        assert_eq!(errors, "cat: no_such_file_ru_shell: No such file or directory");
+
+        let (status, output, errors) = run_with_err(&format!("grep -c b {} no_such_file_ru_shell {}", file, file));
+        assert_eq!(status, 2);
+        assert_eq!(output, format!("{}: 1\n{}: 1", file, file));
+        assert_eq!(errors, "grep: no_such_file_ru_shell: No such file or directory");
+
+        let (status, output, errors) = run_with_err("ls no_such_file_ru_shell Cargo.toml");
+        assert_eq!(status, 2);
+        assert!(output.contains("Cargo.toml"));
+        assert_eq!(errors, "ls: cannot access 'no_such_file_ru_shell': No such file or directory");
+
+        // The error tells the command, the action and the path.
+        let error = ShellError::io_action(
+            "ls",
+            "cannot open directory",
+            "x",
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+        assert_eq!(error.to_string(), "ls: cannot open directory 'x': permission denied");
+        assert_eq!(ShellError::new("grep", "bad pattern").to_string(), "grep: bad pattern");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let stream = Rc::new(PipeLineStream::new());
+        let err_stream = Rc::new(PipeLineStream::new());
+
+        // Nothing runs if any part of the command line can't be parsed.
+        let status = executor::execute_with_err_stream(
+            "echo first; ls | ; echo last",
+            stream.clone(),
+            err_stream.clone(),
+        );
+        assert_eq!(status, 2);
+        assert_eq!(stream.output(), "");
+        assert_eq!(
+            err_stream.output(),
+            "ru_shell: parse error: Missing the command after the pipe\n  echo first; ls | ; echo last\n                   ^"
+        );
+    }
+
+    // The builtin command that is registered from outside of the shell.
+    struct GreetCmd {
+        name: String,
+        shout: bool,
+        stream: Option<Rc<dyn Stream>>,
+    }
+
+    impl Command for GreetCmd {
+        fn execute(&self) -> i32 {
+            let greeting = format!("hello {}", self.name);
+            let greeting = if self.shout {
+                greeting.to_uppercase()
+            } else {
+                greeting
+            };
+            self.stream.as_ref().unwrap().input(greeting);
+            0
+        }
+
+        fn add_stream(&mut self, stream: Rc<dyn Stream>) {
+            self.stream = Some(stream);
+        }
+
+        fn add_err_stream(&mut self, _stream: Rc<dyn Stream>) {}
+    }
+
+    impl From<Box<dyn CommandAstNode>> for GreetCmd {
+        fn from(cmd: Box<dyn CommandAstNode>) -> Self {
+            GreetCmd {
+                name: cmd
+                    .get_option("-n")
+                    .or(cmd.get_option("--name"))
+                    .unwrap_or("world")
+                    .to_string(),
+                shout: cmd.get_option("-s").or(cmd.get_option("--shout")).is_some(),
+                stream: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_register_builtin() {
+        builtin::register(
+            Builtin::new("greet", |cmd| Box::new(GreetCmd::from(cmd))).with_options(vec![
+                OptionSpec::new(Some('n'), Some("name")).with_value(),
+                OptionSpec::new(Some('s'), Some("shout")),
+            ]),
+        );
+
+        assert_eq!(run("greet"), (0, String::from("hello world")));
+        assert_eq!(run("greet --name=ru"), (0, String::from("hello ru")));
+        // The value of the short option is the rest of the word or the next word.
+        assert_eq!(run("greet -snru"), (0, String::from("HELLO RU")));
+        assert_eq!(run("greet -sn ru | grep -c RU"), (0, String::from("1")));
+        // The name is a builtin command only at the position of a command.
+        assert_eq!(run("echo greet"), (0, String::from("greet")));
+    }
 }
@@ -1,38 +1,93 @@
+mod common;
+
 mod file_operator_test {
+    use std::{fs, io};
+
     use ru_shell::file_operator::FileOperator;
 
+    use crate::common::temp_dir;
+
     const FILE_PATH: &str = "tests/resources/test.txt";
 
     #[test]
     fn test_new_file_operator() {
         let file_operator = FileOperator::new(FILE_PATH, false, 1024);
-        assert_eq!(file_operator.is_open, false);
+        assert!(!file_operator.is_open);
         assert_eq!(file_operator.max_size, 1024);
         assert_eq!(file_operator.path, FILE_PATH);
     }
 
     #[test]
-    fn test_ready_file_operator() {
+    fn test_open_file_operator() -> io::Result<()> {
         let mut file_operator = FileOperator::new(FILE_PATH, false, 1024);
-        file_operator.ready();
-        assert_eq!(file_operator.is_open, true);
+        file_operator.open()?;
+        assert!(file_operator.is_open);
+        Ok(())
     }
 
     #[test]
-    fn test_write_file_operator() {
+    fn test_write_file_operator() -> io::Result<()> {
         let mut file_operator = FileOperator::new(FILE_PATH, false, 1024);
-        file_operator.ready();
+        file_operator.open()?;
         for index in 0..100 {
             let msg = format!("{} -- Hello, world!\n", index);
-            file_operator.write(&msg).unwrap();
+            file_operator.write(&msg)?;
         }
+        Ok(())
     }
 
     #[test]
-    fn test_close_file_operator() {
+    fn test_close_file_operator() -> io::Result<()> {
         let mut file_operator = FileOperator::new(FILE_PATH, false, 1024);
-        file_operator.ready();
+        file_operator.open()?;
+        file_operator.close();
+        assert!(!file_operator.is_open);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_entries_file_operator() {
+        let dir = temp_dir("entries_test");
+        let path = dir.join("file").to_string_lossy().into_owned();
+
+        // The file is trimmed to half of the entries when it's full, the backups are off.
+        let mut file_operator = FileOperator::new(&path, true, 1024).with_max_entries(4);
+        for index in 1..=5 {
+            file_operator.write(&format!("{}\n", index)).unwrap();
+        }
+        file_operator.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4\n5\n");
+        assert!(fs::metadata(file_operator.backup_path(1)).is_err());
+
+        // The entries in the file are counted when it's opened again.
+        let mut file_operator = FileOperator::new(&path, false, 1024).with_max_entries(4);
+        for index in 6..=8 {
+            file_operator.write(&format!("{}\n", index)).unwrap();
+        }
+        file_operator.close();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "7\n8\n");
+    }
+
+    #[test]
+    fn test_rotate_file_operator() {
+        let dir = temp_dir("rotate_test");
+        let path = dir.join("file").to_string_lossy().into_owned();
+
+        // The whole file is kept in the backups before it's trimmed by the size, 'x.1' is the newest.
+        let mut file_operator = FileOperator::new(&path, true, 8).with_backups(2);
+        for entry in ["aa\n", "bb\n", "cc\n", "dd\n", "ee\n", "ff\n", "gg\n"] {
+            file_operator.write(entry).unwrap();
+        }
+        file_operator.close();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "gg\n");
+        assert_eq!(fs::read_to_string(file_operator.backup_path(1)).unwrap(), "ee\nff\n");
+        assert_eq!(fs::read_to_string(file_operator.backup_path(2)).unwrap(), "cc\ndd\n");
+        assert!(fs::metadata(file_operator.backup_path(3)).is_err());
+
+        // The file is replaced as a whole, and the data after it is appended.
+        file_operator.rewrite(b"xx\n").unwrap();
+        file_operator.write("yy\n").unwrap();
         file_operator.close();
-        assert_eq!(file_operator.is_open, false);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "xx\nyy\n");
     }
 }
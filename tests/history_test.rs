@@ -0,0 +1,158 @@
+mod common;
+
+#[cfg(test)]
+mod history_test {
+    use std::{fs, rc::Rc};
+
+    use ru_shell::executor;
+    use ru_shell::history::History;
+    use ru_shell::session;
+    use ru_shell::stream::{pipeline_stream::PipeLineStream, Stream};
+
+    use crate::common::temp_dir;
+
+    fn history(lines: &[&str]) -> History {
+        let mut history = History::new();
+        for line in lines {
+            history.add(line).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn test_history_expansion() {
+        let history = history(&["ls -l", "", "cd src", "exit", "grep main src | cat"]);
+        assert_eq!(history.lines(), ["ls -l", "cd src", "grep main src | cat"]);
+
+        let expand = |line: &str| history.expand(line).unwrap();
+        assert_eq!(
+            expand("echo !!"),
+            Some(String::from("echo grep main src | cat"))
+        );
+        assert_eq!(expand("!1 src"), Some(String::from("ls -l src")));
+        assert_eq!(
+            expand("!-2;!gr"),
+            Some(String::from("cd src;grep main src | cat"))
+        );
+        assert_eq!(
+            expand("^main^lib^ -c"),
+            Some(String::from("grep lib src | cat -c"))
+        );
+        assert_eq!(
+            expand("^src^lib"),
+            Some(String::from("grep main lib | cat"))
+        );
+
+        // The '!' is kept if it's quoted or followed by a blank, '=' or '('.
+        assert_eq!(expand("echo '!!' \\!! ! a != b !(x)"), None);
+        assert_eq!(
+            expand("echo \"!!\""),
+            Some(String::from("echo \"grep main src | cat\""))
+        );
+
+        assert_eq!(history.expand("!4").unwrap_err(), "!4: event not found");
+        assert_eq!(history.expand("!-9").unwrap_err(), "!-9: event not found");
+        assert_eq!(
+            history.expand("echo !x|cat").unwrap_err(),
+            "!x: event not found"
+        );
+        assert_eq!(
+            history.expand("^foo^bar").unwrap_err(),
+            "^foo^bar: substitution failed"
+        );
+        assert!(History::new().expand("!!").is_err());
+    }
+
+    #[test]
+    fn test_history_file() {
+        let dir = temp_dir("history_test");
+        let path = dir.join("history").to_string_lossy().into_owned();
+
+        // The file doesn't exist yet.
+        let mut history = History::with_file(&path);
+        history.load().unwrap();
+        for line in ["ls", "  ", "cd src", "exit", "pwd"] {
+            history.add(line).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ls\ncd src\npwd\n");
+
+        let mut loaded = History::with_file(&path);
+        loaded.load().unwrap();
+        assert_eq!(loaded.lines(), ["ls", "cd src", "pwd"]);
+
+        // The file is written again when a line is deleted or the history is cleared.
+        loaded.delete(2).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ls\npwd\n");
+        assert_eq!(
+            loaded.delete(3).unwrap_err(),
+            "3: history position out of range"
+        );
+        loaded.clear().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        // A line of several lines is one line in the file, and so is the backslash.
+        loaded.add("echo 'a\nb' \\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "echo 'a\\nb' \\\\n\n");
+        let mut loaded = History::with_file(&path);
+        loaded.load().unwrap();
+        assert_eq!(loaded.lines(), ["echo 'a\nb' \\n"]);
+    }
+
+    #[test]
+    fn test_history_limit() {
+        // Only the newest lines are kept, as many as the history file keeps.
+        let mut history = History::new();
+        for i in 0..1005 {
+            history.add(&format!("echo {}", i)).unwrap();
+        }
+        assert_eq!(history.lines().len(), 1000);
+        assert_eq!(history.lines()[0], "echo 5");
+        assert_eq!(history.expand("!1").unwrap(), Some(String::from("echo 5")));
+
+        // The file keeps the same lines as the memory.
+        let dir = temp_dir("history_limit");
+        let path = dir.join("history").to_string_lossy().into_owned();
+        let mut history = History::with_file(&path);
+        for i in 0..1005 {
+            history.add(&format!("echo {}", i)).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1000);
+
+        let mut loaded = History::with_file(&path);
+        loaded.load().unwrap();
+        assert_eq!(loaded.lines(), history.lines());
+
+        // The count of the lines is kept after loading the file.
+        loaded.add("echo last").unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 1000);
+        assert!(content.starts_with("echo 6\n"));
+        assert!(content.ends_with("echo last\n"));
+    }
+
+    #[test]
+    fn test_history_cmd() {
+        session::with_history(|h| *h = history(&["ls", "cd src", "pwd"]));
+
+        let stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("history", stream.clone()), 0);
+        assert_eq!(stream.output(), "    1  ls\n    2  cd src\n    3  pwd");
+
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("history -d 2; history 1", stream.clone());
+        assert_eq!(stream.output(), "    2  pwd");
+
+        let stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("history --delete=5", stream.clone()), 1);
+        assert_eq!(stream.output(), "history: 5: history position out of range");
+
+        let stream = Rc::new(PipeLineStream::new());
+        assert_eq!(executor::execute("history x", stream.clone()), 1);
+        assert_eq!(stream.output(), "history: x: numeric argument required");
+
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("history -c; history", stream.clone());
+        assert_eq!(stream.output(), "");
+        assert!(session::with_history(|h| h.lines().is_empty()));
+    }
+}
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod test {
-    use ru_shell::lexer::Lexer;
-    use ru_shell::token::token::{Token, TokenType};
+    use ru_shell::lexer::{quote, Lexer};
+    use ru_shell::token::token::{Span, Token, TokenType};
 
     #[test]
     fn test_new_lexer() {
@@ -22,7 +22,7 @@ mod test {
     fn test_lexer_one_param() {
         let l = Lexer::new("ls -t");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Ls, "ls"),
             Token::new(TokenType::ShortParam, "-t"),
             Token::new(TokenType::Eof, ""),
@@ -39,7 +39,7 @@ mod test {
     fn test_lexer_more_short_param() {
         let l = Lexer::new("ls -l -h -t");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Ls, "ls"),
             Token::new(TokenType::ShortParam, "-l"),
             Token::new(TokenType::ShortParam, "-h"),
@@ -58,7 +58,7 @@ mod test {
     fn test_cd_command() {
         let l = Lexer::new("cd");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Cd, "cd"),
             Token::new(TokenType::Eof, ""),
         ];
@@ -74,7 +74,7 @@ mod test {
     fn test_parse_long_param() {
         let l = Lexer::new("  ls -l --lp  ");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Ls, "ls"),
             Token::new(TokenType::ShortParam, "-l"),
             Token::new(TokenType::LongParam, "--lp"),
@@ -92,7 +92,7 @@ mod test {
     fn test_single_symbols() {
         let l = Lexer::new("   ,| ;  >   < .:= /* & && &&& || ~");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Comma, ","),
             Token::new(TokenType::Pipe, "|"),
             Token::new(TokenType::Semicolon, ";"),
@@ -123,7 +123,7 @@ mod test {
     fn test_num_tokens() {
         let l = Lexer::new("123 456 123_456 12_3456 1_000_000 1_0000_0000_0000");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Num, "123"),
             Token::new(TokenType::Num, "456"),
             Token::new(TokenType::Num, "123_456"),
@@ -144,7 +144,7 @@ mod test {
     fn test_literal_tokens() {
         let l = Lexer::new("a abc _abc _123 Abc_de 123_abc 123_A_b ru-shell aa/bb/cc/.dd/ee_ff");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Literal, "a"),
             Token::new(TokenType::Literal, "abc"),
             Token::new(TokenType::Literal, "_abc"),
@@ -168,7 +168,7 @@ mod test {
     fn test_split_tokens() {
         let l = Lexer::new("Programs/Rust/ru-shell,Programs/Rust/ru-shell,Programs/Rust/ru-shell");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Literal, "Programs/Rust/ru-shell"),
             Token::new(TokenType::Comma, ","),
             Token::new(TokenType::Literal, "Programs/Rust/ru-shell"),
@@ -188,7 +188,7 @@ mod test {
     fn test_grep_token() {
         let l = Lexer::new("grep a bac");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Grep, "grep"),
             Token::new(TokenType::Literal, "a"),
             Token::new(TokenType::Literal, "bac"),
@@ -204,18 +204,14 @@ mod test {
 
     #[test]
     fn test_quote_token() {
-        let l = Lexer::new("grep \"hello world\" 'hello world'");
+        let l = Lexer::new("grep \"hello world\" 'hello world' a\\ b\"c\\\"\"d");
 
-        let tokens = vec![
+        // The quoted text is a part of the word, the quotes are removed by the parser.
+        let tokens = [
             Token::new(TokenType::Grep, "grep"),
-            Token::new(TokenType::Quote, "\""),
-            Token::new(TokenType::Literal, "hello"),
-            Token::new(TokenType::Literal, "world"),
-            Token::new(TokenType::Quote, "\""),
-            Token::new(TokenType::SingleQuote, "'"),
-            Token::new(TokenType::Literal, "hello"),
-            Token::new(TokenType::Literal, "world"),
-            Token::new(TokenType::SingleQuote, "'"),
+            Token::new(TokenType::Literal, "\"hello world\""),
+            Token::new(TokenType::Literal, "'hello world'"),
+            Token::new(TokenType::Literal, "a\\ b\"c\\\"\"d"),
             Token::new(TokenType::Eof, ""),
         ];
 
@@ -231,7 +227,7 @@ mod test {
     fn test_get_tokens_by_range() {
         let l = Lexer::new("ls -l -h -t");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Ls, "ls"),
             Token::new(TokenType::ShortParam, "-l"),
             Token::new(TokenType::ShortParam, "-h"),
@@ -254,7 +250,7 @@ mod test {
     fn test_cat_cmd() {
         let l = Lexer::new("cat -n -s -b -e file3");
 
-        let tokens = vec![
+        let tokens = [
             Token::new(TokenType::Cat, "cat"),
             Token::new(TokenType::ShortParam, "-n"),
             Token::new(TokenType::ShortParam, "-s"),
@@ -271,4 +267,250 @@ mod test {
             assert_eq!(token.literal(), next_token.literal());
         }
     }
+
+    #[test]
+    fn test_single_minus_token() {
+        let l = Lexer::new("cd - --");
+
+        let tokens = [
+            Token::new(TokenType::Cd, "cd"),
+            Token::new(TokenType::Minus, "-"),
+            Token::new(TokenType::DoubleMinus, "--"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_redirect_tokens() {
+        let l = Lexer::new("ls > a >> b 2> c 2>&1");
+
+        let tokens = [
+            Token::new(TokenType::Ls, "ls"),
+            Token::new(TokenType::GreaterThan, ">"),
+            Token::new(TokenType::Literal, "a"),
+            Token::new(TokenType::DoubleGreaterThan, ">>"),
+            Token::new(TokenType::Literal, "b"),
+            Token::new(TokenType::GreaterThan, "2>"),
+            Token::new(TokenType::Literal, "c"),
+            Token::new(TokenType::GreaterThanAnd, "2>&"),
+            Token::new(TokenType::Num, "1"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_heredoc_tokens() {
+        let l = Lexer::new("cat <<'EOF' < in\nhello\n$HOME\nEOF\n");
+
+        let tokens = [
+            Token::new(TokenType::Cat, "cat"),
+            Token::new(TokenType::DoubleLessThan, "<<"),
+            Token::new(TokenType::Literal, "'EOF'"),
+            Token::new(TokenType::HereDoc, "hello\n$HOME\n"),
+            Token::new(TokenType::LessThan, "<"),
+            Token::new(TokenType::Literal, "in"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_status_variable_token() {
+        let l = Lexer::new("echo $? $");
+
+        let tokens = [
+            Token::new(TokenType::Literal, "echo"),
+            Token::new(TokenType::Variable, "$?"),
+            Token::new(TokenType::Literal, "$"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_variable_and_assignment_tokens() {
+        let l = Lexer::new("PATH=$HOME/bin:$PATH ls ${DIR}/src a=b");
+
+        let tokens = [
+            Token::new(TokenType::AssignmentWord, "PATH=$HOME/bin:$PATH"),
+            Token::new(TokenType::Ls, "ls"),
+            Token::new(TokenType::Variable, "${DIR}/src"),
+            Token::new(TokenType::AssignmentWord, "a=b"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_unclosed_quote() {
+        assert_eq!(Lexer::new("echo \"a 'b").unclosed_quote(), Some('"'));
+        assert_eq!(Lexer::new("echo 'a \"b").unclosed_quote(), Some('\''));
+        assert_eq!(Lexer::new("echo \"a\" 'b' \\'").unclosed_quote(), None);
+        assert_eq!(Lexer::new("echo $(pwd").unclosed_quote(), Some(')'));
+        assert_eq!(Lexer::new("echo \"`pwd\"").unclosed_quote(), Some('`'));
+    }
+
+    #[test]
+    fn test_command_substitution_tokens() {
+        let l = Lexer::new("cat $(ls | grep \")\") a`pwd`b \"$(echo $(pwd))\";");
+
+        let tokens = [
+            Token::new(TokenType::Cat, "cat"),
+            Token::new(TokenType::Variable, "$(ls | grep \")\")"),
+            Token::new(TokenType::Literal, "a`pwd`b"),
+            Token::new(TokenType::Literal, "\"$(echo $(pwd))\""),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(quote::unquote("\"hello world\""), "hello world");
+        assert_eq!(quote::unquote("'$HOME *'"), "\\$HOME \\*");
+        assert_eq!(quote::unquote("\"$HOME *\""), "\"$HOME \\*\"");
+        assert_eq!(quote::unquote("$(echo \"a  b\")'c'"), "$(echo \"a  b\")c");
+        assert_eq!(quote::unquote("a\\ b\\*"), "a b\\*");
+        assert_eq!(quote::unescape(&quote::unquote("\"a\\\"b\\c\"")), "a\"b\\c");
+    }
+
+    #[test]
+    fn test_subshell_and_group_tokens() {
+        let l = Lexer::new("(cd a) | { ls; a}; }");
+
+        let tokens = [
+            Token::new(TokenType::LeftParen, "("),
+            Token::new(TokenType::Cd, "cd"),
+            Token::new(TokenType::Literal, "a"),
+            Token::new(TokenType::RightParen, ")"),
+            Token::new(TokenType::Pipe, "|"),
+            Token::new(TokenType::LeftBrace, "{"),
+            Token::new(TokenType::Ls, "ls"),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::Literal, "a"),
+            Token::new(TokenType::Literal, "}"),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::RightBrace, "}"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let l = Lexer::new("ls  -l | grep \"a b\"\n");
+
+        let spans = [(0, 2), (4, 6), (7, 8), (9, 13), (14, 19), (19, 19)];
+        for (start, end) in spans {
+            assert_eq!(l.next_token().unwrap().span(), Span::new(start, end));
+        }
+
+        // The spans count the chars, so they're the columns in the line.
+        let l = Lexer::new("cat 文件 |");
+        l.next_token();
+        assert_eq!(l.next_token().unwrap().span(), Span::new(4, 6));
+        assert_eq!(l.line_of(Span::new(7, 8)), (String::from("cat 文件 |"), 7));
+    }
+
+    #[test]
+    fn test_glued_word_tokens() {
+        // The symbols in a word split it into tokens that touch each other, so the parser joins them back.
+        let words = [
+            ("echo user@host", "user@host"),
+            ("cat 1.txt", "1.txt"),
+            ("date +%Y", "+%Y"),
+            ("echo 10:30", "10:30"),
+            ("echo --x=1", "--x=1"),
+        ];
+        for (command, word) in words {
+            let l = Lexer::new(command);
+            l.next_token();
+
+            let first = l.next_token().unwrap();
+            let (start, mut end) = (first.span().start, first.span().end);
+            let mut token = l.next_token().unwrap();
+            while *token.token_type() != TokenType::Eof {
+                assert_eq!(token.span().start, end, "{}", command);
+                end = token.span().end;
+                token = l.next_token().unwrap();
+            }
+            assert_eq!(l.text(Span::new(start, end)), word);
+        }
+    }
+
+    #[test]
+    fn test_keywords() {
+        let l = Lexer::new("lsblk -f; category x | grepper -1 && cat ls; A=1 jobs");
+
+        let tokens = [
+            Token::new(TokenType::Literal, "lsblk"),
+            Token::new(TokenType::ShortParam, "-f"),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::Literal, "category"),
+            Token::new(TokenType::Literal, "x"),
+            Token::new(TokenType::Pipe, "|"),
+            Token::new(TokenType::Literal, "grepper"),
+            Token::new(TokenType::ShortParam, "-1"),
+            Token::new(TokenType::And, "&&"),
+            Token::new(TokenType::Cat, "cat"),
+            Token::new(TokenType::Literal, "ls"),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::AssignmentWord, "A=1"),
+            Token::new(TokenType::Jobs, "jobs"),
+            Token::new(TokenType::Eof, ""),
+        ];
+
+        for token in tokens.iter() {
+            let next_token = l.next_token().unwrap();
+            assert_eq!(*token.token_type(), *next_token.token_type());
+            assert_eq!(token.literal(), next_token.literal());
+        }
+
+        // The options are whole words, and a short word at the end is a word too.
+        let l = Lexer::new("ls -la --line-number c");
+        let literals: Vec<String> = std::iter::from_fn(|| l.next_token())
+            .map(|token| token.literal().to_string())
+            .collect();
+        assert_eq!(literals, vec!["ls", "-la", "--line-number", "c", ""]);
+
+        assert_eq!(*Lexer::new("").next_token().unwrap().token_type(), TokenType::Eof);
+    }
 }
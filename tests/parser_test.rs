@@ -3,7 +3,7 @@ mod parser_test {
     use ru_shell::parser::ast_node_trait::{CommandAstNode, CommandType};
     use ru_shell::parser::cmds_ast_node::ExeCommandAstNode;
     use ru_shell::parser::Parser;
-    use ru_shell::token::token::{Token, TokenType};
+    use ru_shell::token::token::{Span, Token, TokenType};
 
     #[test]
     fn test_show_command() {
@@ -161,8 +161,18 @@ mod parser_test {
     }
 
     #[test]
-    fn test_error_grep_cmd_without_path() {
+    fn test_grep_cmd_without_path() {
+        // The data is read from the input, such as 'ls | grep main'.
         let parser = Parser::new("grep -i -n -r \"main\"");
+        assert!(parser.errors().is_empty());
+
+        let parser = Parser::new("ls | grep -i -n -r main");
+        assert!(parser.errors().is_empty());
+    }
+
+    #[test]
+    fn test_error_grep_cmd_without_right_quotation_mark_of_pattern() {
+        let parser = Parser::new("grep -i -n \"col Cargo.toml");
 
         let errs = parser.errors();
 
@@ -173,26 +183,270 @@ mod parser_test {
     }
 
     #[test]
-    fn test_error_grep_cmd_without_left_quotation_mark_of_pattern() {
-        let parser = Parser::new("grep -i -n -r main");
+    fn test_external_cmd_parse() {
+        let parser = Parser::new("git log -n 3 --oneline ./src/main.rs");
 
-        let errs = parser.errors();
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::ExtCommand);
+        assert_eq!(cmd.token_type(), &TokenType::Literal);
+        assert_eq!(
+            cmd.get_values().unwrap(),
+            vec!["git", "log", "-n", "3", "--oneline", "./src/main.rs"]
+        );
+        assert!(parser.errors().is_empty());
+    }
 
-        assert_eq!(errs.len(), 1);
-        for err in errs {
-            println!("{}", err);
-        }
+    #[test]
+    fn test_cd_previous_dir_parse() {
+        let parser = Parser::new("cd -");
+
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::Cd);
+        assert_eq!(cmd.get_values().unwrap(), vec!["-"]);
     }
 
     #[test]
-    fn test_error_grep_cmd_without_right_quotation_mark_of_pattern() {
-        let parser = Parser::new("grep -i -n col Cargo.toml");
+    fn test_redirect_cmd_parse() {
+        // The first redirection is the outermost node:
+        //   Redirect(>)
+        //       |
+        //   Redirect(2>&1)
+        //       |
+        //      Ls
+        let parser = Parser::new("ls -l > out.txt 2>&1");
+
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::RedirectCommand);
+        assert_eq!(cmd.token_type(), &TokenType::GreaterThan);
+        assert_eq!(cmd.get_option("fd"), Some("1"));
+        assert_eq!(cmd.get_values().unwrap(), vec!["out.txt"]);
+
+        let inner = cmd.get_source().unwrap();
+        assert_eq!(inner.cmd_type(), &CommandType::RedirectCommand);
+        assert_eq!(inner.token_type(), &TokenType::GreaterThanAnd);
+        assert_eq!(inner.get_option("fd"), Some("2"));
+        assert_eq!(inner.get_values().unwrap(), vec!["1"]);
+
+        let ls = inner.get_source().unwrap();
+        assert_eq!(ls.token_type(), &TokenType::Ls);
+        assert_eq!(ls.get_option("-l"), Some(""));
+    }
+
+    #[test]
+    fn test_append_redirect_cmd_parse() {
+        let parser = Parser::new("cat Cargo.toml >> out.txt | grep \"a\"");
+
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::Pipe);
+
+        let source = cmd.get_source().unwrap();
+        assert_eq!(source.token_type(), &TokenType::DoubleGreaterThan);
+        assert_eq!(source.get_values().unwrap(), vec!["out.txt"]);
+    }
+
+    #[test]
+    fn test_input_redirect_cmd_parse() {
+        let parser = Parser::new("grep \"a\" < Cargo.toml");
+
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::LessThan);
+        assert_eq!(cmd.get_option("fd"), Some("0"));
+        assert_eq!(cmd.get_values().unwrap(), vec!["Cargo.toml"]);
+        assert_eq!(cmd.get_source().unwrap().token_type(), &TokenType::Grep);
+    }
+
+    #[test]
+    fn test_redirect_in_middle_of_cmd_parse() {
+        // The words after the redirection are the arguments of the command.
+        let parser = Parser::new("echo x >o3 y 2>&1 z");
+
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::GreaterThan);
+        assert_eq!(cmd.get_values().unwrap(), vec!["o3"]);
+
+        let inner = cmd.get_source().unwrap();
+        assert_eq!(inner.token_type(), &TokenType::GreaterThanAnd);
+        assert_eq!(inner.get_option("fd"), Some("2"));
+
+        let echo = inner.get_source().unwrap();
+        assert_eq!(echo.get_values().unwrap(), vec!["echo", "x", "y", "z"]);
+
+        // The options of the builtin command may follow the redirection too.
+        let parser = Parser::new("ls > out.txt -l src");
+        assert!(parser.errors().is_empty());
+
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::GreaterThan);
+
+        let ls = cmd.get_source().unwrap();
+        assert_eq!(ls.token_type(), &TokenType::Ls);
+        assert_eq!(ls.get_option("-l"), Some(""));
+        assert_eq!(ls.get_values().unwrap(), vec!["src"]);
+    }
+
+    #[test]
+    fn test_heredoc_cmd_parse() {
+        let parser = Parser::new("cat <<\"EOF\"\nhello\nEOF\n");
 
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::DoubleLessThan);
+        assert_eq!(cmd.get_option("fd"), Some("0"));
+        assert_eq!(cmd.get_option("quoted"), Some(""));
+        assert_eq!(cmd.get_values().unwrap(), vec!["hello\n"]);
+    }
+
+    #[test]
+    fn test_list_cmd_parse() {
+        let parser = Parser::new("ls && cat a | grep \"b\" || cd c; ls");
+
+        let mut cmds = parser.iter();
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::ListCommand);
+        assert_eq!(cmd.token_type(), &TokenType::Or);
+        assert_eq!(cmd.get_destination().unwrap().token_type(), &TokenType::Cd);
+
+        let source = cmd.get_source().unwrap();
+        assert_eq!(source.token_type(), &TokenType::And);
+        assert_eq!(source.get_source().unwrap().token_type(), &TokenType::Ls);
+        assert_eq!(source.get_destination().unwrap().token_type(), &TokenType::Pipe);
+
+        assert_eq!(cmds.next().unwrap().token_type(), &TokenType::Ls);
+        assert!(cmds.next().is_none());
+    }
+
+    #[test]
+    fn test_error_list_cmd_without_destination() {
+        let parser = Parser::new("ls &&");
+
+        assert!(parser.iter().next().is_none());
+        assert_eq!(parser.errors().len(), 1);
+    }
+
+    #[test]
+    fn test_background_cmd_parse() {
+        let parser = Parser::new("sleep 1 & jobs");
+
+        let mut cmds = parser.iter();
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::BackgroundCommand);
+        assert_eq!(cmd.get_values().unwrap(), vec!["sleep 1"]);
+        assert_eq!(cmd.get_source().unwrap().get_values().unwrap(), vec!["sleep", "1"]);
+
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::Jobs);
+        assert!(cmds.next().is_none());
+    }
+
+    #[test]
+    fn test_assignment_cmd_parse() {
+        let parser = Parser::new("A=1 B=$A; FOO=bar env; export A");
+
+        let mut cmds = parser.iter();
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::AssignmentCommand);
+        assert_eq!(cmd.get_values().unwrap(), vec!["A=1", "B=$A"]);
+        assert!(cmd.get_source().is_none());
+
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::AssignmentCommand);
+        assert_eq!(cmd.get_values().unwrap(), vec!["FOO=bar"]);
+        assert_eq!(cmd.get_source().unwrap().get_values().unwrap(), vec!["env"]);
+
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::Export);
+        assert_eq!(cmd.get_values().unwrap(), vec!["A"]);
+        assert!(cmds.next().is_none());
+    }
+
+    #[test]
+    fn test_quoted_args_parse() {
+        let parser = Parser::new("grep \"hello world\" 'my file' a\\ b; echo \"$HOME\" '$HOME'");
+
+        let mut cmds = parser.iter();
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.token_type(), &TokenType::Grep);
+        assert_eq!(cmd.get_values().unwrap(), vec!["hello world", "my file", "a b"]);
+
+        // The '$' in single quotes is escaped, so it isn't expanded,
+        // and the double quotes are kept around the variable, so that its value isn't split.
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.get_values().unwrap(), vec!["echo", "\"$HOME\"", "\\$HOME"]);
+        assert!(cmds.next().is_none());
+    }
+
+    #[test]
+    fn test_subshell_and_group_parse() {
+        let parser = Parser::new("(cd src && ls; pwd) > out; { ls; cat f; } | grep x");
+
+        // The commands inside are joined by ';' lists.
+        let mut cmds = parser.iter();
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::RedirectCommand);
+        let subshell = cmd.get_source().unwrap();
+        assert_eq!(subshell.cmd_type(), &CommandType::SubshellCommand);
+        let list = subshell.get_source().unwrap();
+        assert_eq!(list.token_type(), &TokenType::Semicolon);
+        assert_eq!(list.get_source().unwrap().token_type(), &TokenType::And);
+        assert_eq!(list.get_destination().unwrap().get_values().unwrap(), vec!["pwd"]);
+
+        let cmd = cmds.next().unwrap();
+        assert_eq!(cmd.cmd_type(), &CommandType::ChainCommand);
+        let group = cmd.get_source().unwrap();
+        assert_eq!(group.cmd_type(), &CommandType::GroupCommand);
+        assert_eq!(group.get_source().unwrap().token_type(), &TokenType::Semicolon);
+        assert!(cmds.next().is_none());
+
+        let parser = Parser::new("(ls; { pwd");
+        assert!(parser.iter().next().is_none());
+        assert_eq!(parser.errors().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_error_span() {
+        let parser = Parser::new("ls -l | grep");
         let errs = parser.errors();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].message(), "Missing pattern");
+        assert_eq!(errs[0].column(), 12);
+        assert_eq!(
+            errs[0].to_string(),
+            "ru_shell: parse error: Missing pattern\n  ls -l | grep\n              ^"
+        );
 
+        // The caret is as wide as the token, and it's under the line where the token is.
+        let parser = Parser::new("echo \"a\nb\" ) c\n");
+        let errs = parser.errors();
         assert_eq!(errs.len(), 1);
-        for err in errs {
-            println!("{}", err);
-        }
+        assert_eq!(
+            errs[0].to_string(),
+            "ru_shell: parse error: Unexpected token `)`\n  b\" ) c\n     ^"
+        );
+
+        let parser = Parser::new("echo \"abc");
+        assert_eq!(parser.errors()[0].span(), Span::new(5, 9));
+        assert!(parser.errors()[0].to_string().ends_with("\n       ^^^^"));
+    }
+
+    #[test]
+    fn test_combined_options_parse() {
+        // The short options are split, and the options may follow the values until '--'.
+        let parser = Parser::new("ls -la src --depth=2 -- -t");
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.get_option("-l"), Some(""));
+        assert_eq!(cmd.get_option("-a"), Some(""));
+        assert_eq!(cmd.get_option("--depth"), Some("2"));
+        assert_eq!(cmd.get_option("-t"), None);
+        assert_eq!(cmd.get_values().unwrap(), vec!["src", "-t"]);
+
+        let parser = Parser::new("grep -in main src");
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.get_option("-i"), Some(""));
+        assert_eq!(cmd.get_option("-n"), Some(""));
+        assert_eq!(cmd.get_values().unwrap(), vec!["main", "src"]);
+
+        // The commands without options take the words as they are.
+        let parser = Parser::new("export -p");
+        let cmd = parser.iter().next().unwrap();
+        assert_eq!(cmd.get_values().unwrap(), vec!["-p"]);
     }
 }
@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod prompt_test {
+    use std::path::Path;
+    use std::{env, fs, rc::Rc};
+
+    use ru_shell::executor;
+    use ru_shell::executor::expansion::home_dir;
+    use ru_shell::prompt::{abbreviate_home, git_branch, render};
+    use ru_shell::session;
+    use ru_shell::stream::{pipeline_stream::PipeLineStream, Stream};
+
+    use crate::common::{lock_env, temp_dir};
+
+    #[test]
+    fn test_render_prompt() {
+        session::set_last_status(2);
+        assert_eq!(render("[\\?] \\j\\n\\\\ \\x\\", false), "[2] 0\n\\ \\x\\");
+
+        // The directory is read from $PWD, which another test changes.
+        let _env = lock_env();
+        let dir = env::current_dir().unwrap();
+        let name = dir.file_name().unwrap().to_string_lossy();
+        assert_eq!(render("\\W> ", false), format!("{}> ", name));
+
+        // The segments are colored, and the text around them isn't.
+        session::set_last_status(0);
+        colored::control::set_override(true);
+        assert_eq!(render("\\?> ", true), "\x1b[32m0\x1b[0m> ");
+
+        // The template is read from 'PS1'.
+        let stream = Rc::new(PipeLineStream::new());
+        executor::execute("PS1='\\?$ '", stream.clone());
+        assert_eq!(stream.output(), "");
+        assert_eq!(ru_shell::prompt::prompt(false), "0$ ");
+    }
+
+    #[test]
+    fn test_abbreviate_home() {
+        let home = Some(Path::new("/home/ru"));
+        assert_eq!(abbreviate_home(Path::new("/home/ru"), home), "~");
+        assert_eq!(
+            abbreviate_home(Path::new("/home/ru/src/a"), home),
+            "~/src/a"
+        );
+        assert_eq!(abbreviate_home(Path::new("/home/rust"), home), "/home/rust");
+        assert_eq!(abbreviate_home(Path::new("/tmp"), None), "/tmp");
+    }
+
+    #[test]
+    fn test_prompt_dir() {
+        // The directory is the one in $PWD, such as a link to the working directory.
+        let _env = lock_env();
+        let cwd = env::current_dir().unwrap();
+        let dir = temp_dir("prompt_test");
+        let link = dir.join(cwd.file_name().unwrap());
+        std::os::unix::fs::symlink(&cwd, &link).unwrap();
+
+        env::set_var("PWD", &link);
+        let home = home_dir();
+        assert_eq!(
+            render("\\w", false),
+            abbreviate_home(&link, home.as_deref())
+        );
+
+        // The real path is used if $PWD is another directory.
+        env::set_var("PWD", &*dir);
+        assert_eq!(render("\\w", false), abbreviate_home(&cwd, home.as_deref()));
+    }
+
+    #[test]
+    fn test_git_branch() {
+        let dir = temp_dir("git_test");
+        let sub_dir = dir.join("src/prompt");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(&sub_dir).unwrap();
+
+        // The branch is found from the parent directories.
+        fs::write(dir.join(".git/HEAD"), "ref: refs/heads/feature/prompt\n").unwrap();
+        assert_eq!(git_branch(&sub_dir), Some(String::from("feature/prompt")));
+
+        fs::write(dir.join(".git/HEAD"), "0123456789abcdef\n").unwrap();
+        assert_eq!(git_branch(&dir), Some(String::from("0123456")));
+
+        // '.git' may be a file that tells where the git directory is.
+        fs::create_dir_all(dir.join("worktrees/wt")).unwrap();
+        fs::write(dir.join("worktrees/wt/HEAD"), "ref: refs/heads/wt\n").unwrap();
+        fs::write(sub_dir.join(".git"), "gitdir: ../../worktrees/wt\n").unwrap();
+        assert_eq!(git_branch(&sub_dir), Some(String::from("wt")));
+    }
+}
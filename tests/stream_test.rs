@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod stream_test {
-    use ru_shell::stream::{pipeline_stream::PipeLineStream, Stream};
+    use std::thread;
+
+    use ru_shell::stream::{
+        self, pipe_stream::pipe_stream, pipeline_stream::PipeLineStream, Stream,
+    };
 
     #[test]
     fn test_pipeline_stream() {
@@ -8,6 +12,68 @@ mod stream_test {
 
         pipeline_stream.input("Hello, world!".to_string());
         pipeline_stream.input("Hello, world!".to_string());
-        assert_eq!(pipeline_stream.output().trim(), "Hello, world!\n\rHello, world!");
+        assert_eq!(
+            pipeline_stream.output().trim(),
+            "Hello, world!\nHello, world!"
+        );
+    }
+
+    #[test]
+    fn test_pipe_stream() {
+        let (writer, reader) = pipe_stream();
+
+        // The lines are read while the writer is still writing, more than the pipe can hold.
+        let writer_thread = thread::spawn(move || {
+            for i in 0..5000 {
+                writer.input(i.to_string());
+            }
+        });
+        let lines: Vec<String> = stream::lines(&reader).collect();
+        writer_thread.join().unwrap();
+
+        assert_eq!(lines.len(), 5000);
+        assert_eq!(lines.last().map(String::as_str), Some("4999"));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_closed_pipe_stream() {
+        let (writer, reader) = pipe_stream();
+
+        // The writer is told that the reader has stopped, so it stops instead of waiting forever.
+        let writer_thread = thread::spawn(move || {
+            let mut count = 0;
+            while !writer.is_closed() {
+                writer.input("y".to_string());
+                count += 1;
+            }
+            count
+        });
+        assert_eq!(reader.read_line(), Some("y".to_string()));
+        reader.close();
+
+        assert!(writer_thread.join().unwrap() > 1);
+        assert_eq!(reader.read_line(), None);
+    }
+
+    #[test]
+    fn test_byte_stream() {
+        let pipeline_stream = PipeLineStream::new();
+
+        // The lines keep the '\r' of CRLF and the trailing whitespace, invalid UTF-8 is replaced.
+        pipeline_stream.write(b"a \r\n\n\xffb");
+        assert_eq!(pipeline_stream.read_line(), Some("a \r".to_string()));
+        assert_eq!(pipeline_stream.read_line(), Some(String::new()));
+        assert_eq!(pipeline_stream.read_line(), Some("\u{fffd}b".to_string()));
+        assert_eq!(pipeline_stream.read_line(), None);
+
+        // The data is kept as it is when it's read as bytes.
+        let (writer, reader) = pipe_stream();
+        let writer_thread = thread::spawn(move || {
+            writer.write(b"\x00\xff\r\n");
+            writer.input("line".to_string());
+        });
+        assert_eq!(stream::read_all(&reader), b"\x00\xff\r\nline\n");
+        writer_thread.join().unwrap();
     }
 }